@@ -1,9 +1,14 @@
 use std::collections::VecDeque;
 use std::sync::mpsc::{channel, Receiver, RecvError, Sender, TryRecvError};
 use std::thread;
+use std::time::{Duration, Instant};
 use url::Url;
 
-use languageserver_types::{Position, Range};
+use languageserver_types::{
+    DiagnosticSeverity, DocumentHighlightKind, Position, Range, SymbolKind,
+};
+
+mod test;
 
 pub type TaskId = usize;
 
@@ -11,12 +16,22 @@ pub type TaskId = usize;
 #[derive(Debug)]
 pub enum QueryRequest {
     TypeAtPosition(TaskId, Url, Position),
+    Hover(TaskId, Url, Position),
     RenameAtPosition(TaskId, Url, Position, String),
     DefinitionAtPosition(TaskId, Url, Position),
     ReferencesAtPosition(TaskId, Url, Position, bool),
+    DocumentHighlight(TaskId, Url, Position),
+    InlayHints(TaskId, Url, Range),
+    WorkspaceSymbols(TaskId, String),
+    IncomingCalls(TaskId, Url, Position),
+    /// Asks for the selection ranges at each of the positions.
+    SelectionRange(TaskId, Url, Vec<Position>),
+    CodeAction(TaskId, Url, Range),
     OpenFile(Url, String),
     EditFile(Url, Vec<(Range, String)>),
-    Initialize(TaskId),
+    /// The flag says whether the IDE supports work-done progress
+    /// (`window.workDoneProgress`); it is handed back in `Initialized`.
+    Initialize(TaskId, bool),
 }
 impl QueryRequest {
     /// True if this query will cause us to mutate the state of the
@@ -28,9 +43,94 @@ impl QueryRequest {
             | QueryRequest::RenameAtPosition(..)
             | QueryRequest::Initialize(..) => true,
             QueryRequest::TypeAtPosition(..) => false,
+            QueryRequest::Hover(..) => false,
             QueryRequest::DefinitionAtPosition(..) => false,
             QueryRequest::ReferencesAtPosition(..) => false,
+            QueryRequest::DocumentHighlight(..) => false,
+            QueryRequest::InlayHints(..) => false,
+            QueryRequest::WorkspaceSymbols(..) => false,
+            QueryRequest::IncomingCalls(..) => false,
+            QueryRequest::SelectionRange(..) => false,
+            QueryRequest::CodeAction(..) => false,
+        }
+    }
+
+    /// How important it is to answer this query when the query
+    /// system is busy; see `Priority`.
+    pub fn priority(&self) -> Priority {
+        match self {
+            QueryRequest::TypeAtPosition(..)
+            | QueryRequest::Hover(..)
+            | QueryRequest::DocumentHighlight(..)
+            | QueryRequest::InlayHints(..)
+            | QueryRequest::SelectionRange(..)
+            | QueryRequest::CodeAction(..) => Priority::Low,
+            QueryRequest::RenameAtPosition(..)
+            | QueryRequest::DefinitionAtPosition(..)
+            | QueryRequest::ReferencesAtPosition(..)
+            | QueryRequest::WorkspaceSymbols(..)
+            | QueryRequest::IncomingCalls(..)
+            | QueryRequest::OpenFile(..)
+            | QueryRequest::EditFile(..)
+            | QueryRequest::Initialize(..) => Priority::High,
+        }
+    }
+
+    /// The task that is waiting for a response to this query, if
+    /// any. `OpenFile` and `EditFile` are notifications and expect no
+    /// response.
+    pub fn task_id(&self) -> Option<TaskId> {
+        match self {
+            QueryRequest::TypeAtPosition(id, ..)
+            | QueryRequest::Hover(id, ..)
+            | QueryRequest::RenameAtPosition(id, ..)
+            | QueryRequest::DefinitionAtPosition(id, ..)
+            | QueryRequest::ReferencesAtPosition(id, ..)
+            | QueryRequest::DocumentHighlight(id, ..)
+            | QueryRequest::InlayHints(id, ..)
+            | QueryRequest::WorkspaceSymbols(id, ..)
+            | QueryRequest::IncomingCalls(id, ..)
+            | QueryRequest::SelectionRange(id, ..)
+            | QueryRequest::CodeAction(id, ..)
+            | QueryRequest::Initialize(id, _) => Some(*id),
+            QueryRequest::OpenFile(..) | QueryRequest::EditFile(..) => None,
+        }
+    }
+}
+
+/// How important it is to answer a query. When the query system is
+/// busy, low-priority queries are turned away first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Queries the editor sends on its own as the user moves around
+    /// (hovers, highlights, completions and the like); if one goes
+    /// unanswered, another will soon follow.
+    Low,
+
+    /// Queries the user explicitly asked for, such as going to a
+    /// definition or renaming.
+    High,
+}
+
+/// What to show when the user hovers over some position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HoverContent {
+    /// The signature or type of the thing being hovered over.
+    pub signature: String,
+
+    /// The doc comments of the hovered entity, if any.
+    pub docs: Option<String>,
+}
+
+impl HoverContent {
+    /// Renders the signature as a code block, followed by the docs.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("```lark\n{}\n```", self.signature);
+        if let Some(docs) = &self.docs {
+            markdown.push_str("\n\n");
+            markdown.push_str(docs);
         }
+        markdown
     }
 }
 
@@ -38,13 +138,64 @@ impl QueryRequest {
 /// the query system.
 pub enum LspResponse {
     Type(TaskId, String),
+    Hover(TaskId, Option<HoverContent>),
     Range(TaskId, Url, Range),
     Ranges(TaskId, Vec<(Url, Range)>),
+    Highlights(TaskId, Vec<(Range, DocumentHighlightKind)>),
+    InlayHints(TaskId, Vec<(Position, String)>),
+    Symbols(TaskId, Vec<(String, SymbolKind, Url, Range)>),
+    /// For each call site: the name of the caller and the location
+    /// of the call.
+    IncomingCalls(TaskId, Vec<(String, Url, Range)>),
+    /// For each requested position, the ranges to expand the
+    /// selection through, innermost first; each contains the one
+    /// before it.
+    SelectionRanges(TaskId, Vec<Vec<Range>>),
+    /// For each quick fix: its title and the edits it makes.
+    CodeActions(TaskId, Vec<(String, Vec<(Url, Range, String)>)>),
     WorkspaceEdits(TaskId, Vec<(Url, Range, String)>),
     Completions(TaskId, Vec<(String, String)>),
-    Initialized(TaskId),
+    /// Whether the IDE supports work-done progress; see
+    /// `QueryRequest::Initialize`.
+    Initialized(TaskId, bool),
     Nothing(TaskId),
-    Diagnostics(Url, Vec<(Range, String)>),
+    /// The request could not be answered; the string says why.
+    Error(TaskId, String),
+    /// Intermediate progress `(done, total)` for a long-running task;
+    /// always followed by the task's final response.
+    Progress(TaskId, u32, u32),
+    Diagnostics(Url, Vec<(Range, String, DiagnosticSeverity)>),
+    /// The diagnostics for several files, to be sent to the IDE in a
+    /// single write; see `BatchingResponder`.
+    DiagnosticsBatch(Vec<(Url, Vec<(Range, String, DiagnosticSeverity)>)>),
+}
+
+impl LspResponse {
+    /// The task this is the final response to, if any. `Progress`
+    /// is not final, and diagnostics are not sent in response to a
+    /// task at all.
+    pub fn task_id(&self) -> Option<TaskId> {
+        match self {
+            LspResponse::Type(id, ..)
+            | LspResponse::Hover(id, ..)
+            | LspResponse::Range(id, ..)
+            | LspResponse::Ranges(id, ..)
+            | LspResponse::Highlights(id, ..)
+            | LspResponse::InlayHints(id, ..)
+            | LspResponse::Symbols(id, ..)
+            | LspResponse::IncomingCalls(id, ..)
+            | LspResponse::SelectionRanges(id, ..)
+            | LspResponse::CodeActions(id, ..)
+            | LspResponse::WorkspaceEdits(id, ..)
+            | LspResponse::Completions(id, ..)
+            | LspResponse::Initialized(id, ..)
+            | LspResponse::Nothing(id)
+            | LspResponse::Error(id, ..) => Some(*id),
+            LspResponse::Progress(..)
+            | LspResponse::Diagnostics(..)
+            | LspResponse::DiagnosticsBatch(..) => None,
+        }
+    }
 }
 
 /// An actor in the task system. This gives a uniform way to
@@ -70,6 +221,107 @@ pub trait Actor {
     ///     messages if they have arrived in the meantime.
     ///     - This is only important if you are trying to remove outdated messages.
     fn receive_messages(&mut self, messages: &mut VecDeque<Self::InMessage>);
+
+    /// Invoked once, just before the actor's thread exits, because
+    /// the channel feeding it has been disconnected. Actors that
+    /// buffer results or hold on to resources should flush or release
+    /// them here.
+    fn shutdown(&mut self) {}
+}
+
+/// Wraps the actor that sends responses back to the IDE so that
+/// diagnostics are forwarded in batches rather than one at a time.
+///
+/// Responses to individual requests (types, hovers, and so on) are
+/// forwarded as soon as they arrive. Diagnostics are held back and
+/// coalesced -- only the latest diagnostics for each file are kept --
+/// until no more responses are waiting, `max_batch` files have
+/// diagnostics pending, or the oldest pending diagnostics have waited
+/// for `window`. They are then handed to the inner actor as a single
+/// `LspResponse::DiagnosticsBatch`.
+pub struct BatchingResponder<A> {
+    inner: A,
+    max_batch: usize,
+    window: Duration,
+
+    /// Diagnostics not yet forwarded, at most one entry per file, and
+    /// when the first of them arrived.
+    pending: Vec<(Url, Vec<(Range, String, DiagnosticSeverity)>)>,
+    pending_since: Option<Instant>,
+}
+
+impl<A> BatchingResponder<A>
+where
+    A: Actor<InMessage = LspResponse>,
+{
+    pub const DEFAULT_MAX_BATCH: usize = 64;
+    pub const DEFAULT_WINDOW: Duration = Duration::from_millis(50);
+
+    pub fn new(inner: A) -> Self {
+        Self::with_limits(inner, Self::DEFAULT_MAX_BATCH, Self::DEFAULT_WINDOW)
+    }
+
+    pub fn with_limits(inner: A, max_batch: usize, window: Duration) -> Self {
+        assert!(max_batch > 0);
+        BatchingResponder {
+            inner,
+            max_batch,
+            window,
+            pending: vec![],
+            pending_since: None,
+        }
+    }
+
+    /// Hands a single response to the inner actor.
+    fn forward(&mut self, response: LspResponse) {
+        let mut messages = VecDeque::new();
+        messages.push_back(response);
+        while !messages.is_empty() {
+            self.inner.receive_messages(&mut messages);
+        }
+    }
+
+    /// Hands all pending diagnostics to the inner actor at once.
+    fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            let batch = std::mem::replace(&mut self.pending, vec![]);
+            self.pending_since = None;
+            self.forward(LspResponse::DiagnosticsBatch(batch));
+        }
+    }
+}
+
+impl<A> Actor for BatchingResponder<A>
+where
+    A: Actor<InMessage = LspResponse>,
+{
+    type InMessage = LspResponse;
+
+    fn receive_messages(&mut self, messages: &mut VecDeque<LspResponse>) {
+        match messages.pop_front().unwrap() {
+            LspResponse::Diagnostics(url, diagnostics) => {
+                match self.pending.iter_mut().find(|(u, _)| *u == url) {
+                    Some(entry) => entry.1 = diagnostics,
+                    None => self.pending.push((url, diagnostics)),
+                }
+                self.pending_since.get_or_insert_with(Instant::now);
+            }
+
+            response => self.forward(response),
+        }
+
+        let window_elapsed = self
+            .pending_since
+            .map_or(false, |since| since.elapsed() >= self.window);
+        if messages.is_empty() || self.pending.len() >= self.max_batch || window_elapsed {
+            self.flush();
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.flush();
+        self.inner.shutdown();
+    }
 }
 
 pub struct ActorControl<MessageType: Send + Sync + 'static> {
@@ -77,11 +329,58 @@ pub struct ActorControl<MessageType: Send + Sync + 'static> {
     pub join_handle: std::thread::JoinHandle<()>,
 }
 
-pub fn spawn_actor<T: Actor + Send + 'static>(mut actor: T) -> ActorControl<T::InMessage> {
+pub fn spawn_actor<T: Actor + Send + 'static>(actor: T) -> ActorControl<T::InMessage> {
     let (actor_tx, actor_rx) = channel();
+    let handle = thread::spawn(move || run_actor(actor, actor_rx));
+
+    ActorControl {
+        channel: actor_tx,
+        join_handle: handle,
+    }
+}
+
+/// Spawns a two-stage pipeline: `second` is spawned first, and then
+/// `make_first` is given a channel to it with which to construct the
+/// first stage. The returned control feeds the first stage.
+///
+/// When the returned channel is dropped, the first stage shuts down,
+/// dropping its channel to the second stage, which then shuts down
+/// too. The returned join handle waits for both.
+pub fn spawn_pipeline<A, B>(
+    make_first: impl FnOnce(Sender<B::InMessage>) -> A,
+    second: B,
+) -> ActorControl<A::InMessage>
+where
+    A: Actor + Send + 'static,
+    B: Actor + Send + 'static,
+{
+    let ActorControl {
+        channel: second_tx,
+        join_handle: second_handle,
+    } = spawn_actor(second);
+    let first = make_first(second_tx);
+
+    let (actor_tx, actor_rx) = channel();
+    let handle = thread::spawn(move || {
+        // `run_actor` drops `first` -- and hence its channel to the
+        // second stage -- once it returns.
+        run_actor(first, actor_rx);
+        second_handle.join().unwrap();
+    });
+
+    ActorControl {
+        channel: actor_tx,
+        join_handle: handle,
+    }
+}
+
+/// Feeds messages from `actor_rx` to `actor` until the channel is
+/// disconnected, then delivers any messages still queued and shuts
+/// the actor down.
+fn run_actor<T: Actor>(mut actor: T, actor_rx: Receiver<T::InMessage>) {
     let mut message_queue = VecDeque::default();
 
-    let handle = thread::spawn(move || loop {
+    loop {
         match push_all_pending(&actor_rx, &mut message_queue) {
             Ok(()) => {
                 actor.receive_messages(&mut message_queue);
@@ -93,14 +392,16 @@ pub fn spawn_actor<T: Actor + Send + 'static>(mut actor: T) -> ActorControl<T::I
                     }
                 }
 
+                // Messages may have been queued before we noticed the
+                // disconnect; the actor must still see them.
+                while !message_queue.is_empty() {
+                    actor.receive_messages(&mut message_queue);
+                }
+
+                actor.shutdown();
                 break;
             }
         }
-    });
-
-    ActorControl {
-        channel: actor_tx,
-        join_handle: handle,
     }
 }
 
@@ -0,0 +1,232 @@
+#![cfg(test)]
+
+use crate::{spawn_actor, spawn_pipeline, Actor, BatchingResponder, LspResponse, QueryRequest};
+use languageserver_types::{DiagnosticSeverity, Position, Range};
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+
+struct FlushOnShutdown {
+    received: usize,
+    received_at_shutdown: Arc<Mutex<Option<usize>>>,
+}
+
+impl Actor for FlushOnShutdown {
+    type InMessage = ();
+
+    fn receive_messages(&mut self, messages: &mut VecDeque<()>) {
+        messages.pop_front().unwrap();
+        self.received += 1;
+    }
+
+    fn shutdown(&mut self) {
+        *self.received_at_shutdown.lock().unwrap() = Some(self.received);
+    }
+}
+
+#[test]
+fn shutdown_on_disconnect() {
+    let received_at_shutdown = Arc::new(Mutex::new(None));
+    let control = spawn_actor(FlushOnShutdown {
+        received: 0,
+        received_at_shutdown: received_at_shutdown.clone(),
+    });
+
+    control.channel.send(()).unwrap();
+    control.channel.send(()).unwrap();
+
+    // Dropping the sender disconnects the channel, which should shut
+    // the actor down -- but only once it has seen both messages.
+    drop(control.channel);
+    control.join_handle.join().unwrap();
+
+    assert_eq!(*received_at_shutdown.lock().unwrap(), Some(2));
+}
+
+struct Doubler {
+    output: Sender<u32>,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Actor for Doubler {
+    type InMessage = u32;
+
+    fn receive_messages(&mut self, messages: &mut VecDeque<u32>) {
+        let message = messages.pop_front().unwrap();
+        self.output.send(message * 2).unwrap();
+    }
+
+    fn shutdown(&mut self) {
+        self.log
+            .lock()
+            .unwrap()
+            .push("doubler shut down".to_string());
+    }
+}
+
+struct Collector {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Actor for Collector {
+    type InMessage = u32;
+
+    fn receive_messages(&mut self, messages: &mut VecDeque<u32>) {
+        let message = messages.pop_front().unwrap();
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("collected {}", message));
+    }
+
+    fn shutdown(&mut self) {
+        self.log
+            .lock()
+            .unwrap()
+            .push("collector shut down".to_string());
+    }
+}
+
+#[test]
+fn pipeline() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let control = spawn_pipeline(
+        |output| Doubler {
+            output,
+            log: log.clone(),
+        },
+        Collector { log: log.clone() },
+    );
+
+    control.channel.send(1).unwrap();
+    control.channel.send(2).unwrap();
+
+    drop(control.channel);
+    control.join_handle.join().unwrap();
+
+    // The collector may still be draining its queue when the doubler
+    // shuts down, so the relative order of those entries can vary.
+    // But it must see every message, in order, and the doubler must
+    // shut down first (dropping its channel is what shuts the
+    // collector down).
+    let log = log.lock().unwrap();
+    let collected: Vec<&String> = log.iter().filter(|l| l.starts_with("collected")).collect();
+    assert_eq!(collected, vec!["collected 2", "collected 4"]);
+
+    let shutdowns: Vec<&String> = log.iter().filter(|l| l.ends_with("shut down")).collect();
+    assert_eq!(shutdowns, vec!["doubler shut down", "collector shut down"]);
+    assert_eq!(log.last().unwrap(), "collector shut down");
+}
+
+#[test]
+fn undeliverable_request_keeps_task_id() {
+    let (sender, receiver) = channel();
+
+    // Once the query system has shut down, sending hands the request
+    // back, and we can still find out which task to answer with an error.
+    drop(receiver);
+    let url = Url::parse("file:///test.lark").unwrap();
+    let error = sender
+        .send(QueryRequest::Hover(22, url.clone(), Position::new(0, 0)))
+        .unwrap_err();
+    assert_eq!(error.0.task_id(), Some(22));
+
+    let error = sender
+        .send(QueryRequest::OpenFile(url, String::new()))
+        .unwrap_err();
+    assert_eq!(error.0.task_id(), None);
+}
+
+/// Like `LspResponder`, takes one response per call.
+struct RecordResponses {
+    responses: Arc<Mutex<Vec<String>>>,
+}
+
+impl Actor for RecordResponses {
+    type InMessage = LspResponse;
+
+    fn receive_messages(&mut self, messages: &mut VecDeque<LspResponse>) {
+        let description = match messages.pop_front().unwrap() {
+            LspResponse::Diagnostics(url, _) => format!("diagnostics for {}", url.path()),
+            LspResponse::DiagnosticsBatch(batch) => {
+                let files: Vec<String> = batch
+                    .iter()
+                    .map(|(url, diagnostics)| format!("{} ({})", url.path(), diagnostics.len()))
+                    .collect();
+                format!("diagnostics for {}", files.join(", "))
+            }
+            LspResponse::Hover(..) => "hover".to_string(),
+            _ => "other".to_string(),
+        };
+        self.responses.lock().unwrap().push(description);
+    }
+}
+
+#[test]
+fn batch_diagnostics() {
+    let responses = Arc::new(Mutex::new(vec![]));
+    // A long window, so that only running out of messages flushes.
+    let mut responder = BatchingResponder::with_limits(
+        RecordResponses {
+            responses: responses.clone(),
+        },
+        64,
+        Duration::from_secs(3600),
+    );
+
+    let a = Url::parse("file:///a.lark").unwrap();
+    let b = Url::parse("file:///b.lark").unwrap();
+    let range = Range::new(Position::new(0, 0), Position::new(0, 1));
+    let mut messages = VecDeque::new();
+    messages.push_back(LspResponse::Diagnostics(a.clone(), vec![]));
+    messages.push_back(LspResponse::Diagnostics(b, vec![]));
+    messages.push_back(LspResponse::Hover(22, None));
+    messages.push_back(LspResponse::Diagnostics(
+        a,
+        vec![(range, "oops".into(), DiagnosticSeverity::Error)],
+    ));
+
+    // Deliver the messages the way `run_actor` would.
+    while !messages.is_empty() {
+        responder.receive_messages(&mut messages);
+    }
+
+    // The hover is not held up by the diagnostics, which are then
+    // forwarded together, keeping only the latest for each file.
+    assert_eq!(
+        *responses.lock().unwrap(),
+        vec!["hover", "diagnostics for /a.lark (1), /b.lark (0)"]
+    );
+}
+
+#[test]
+fn batch_diagnostics_up_to_max_batch() {
+    let responses = Arc::new(Mutex::new(vec![]));
+    let mut responder = BatchingResponder::with_limits(
+        RecordResponses {
+            responses: responses.clone(),
+        },
+        2,
+        Duration::from_secs(3600),
+    );
+
+    let mut messages = VecDeque::new();
+    for name in &["a", "b", "c"] {
+        let url = Url::parse(&format!("file:///{}.lark", name)).unwrap();
+        messages.push_back(LspResponse::Diagnostics(url, vec![]));
+    }
+
+    while !messages.is_empty() {
+        responder.receive_messages(&mut messages);
+    }
+
+    assert_eq!(
+        *responses.lock().unwrap(),
+        vec![
+            "diagnostics for /a.lark (0), /b.lark (0)",
+            "diagnostics for /c.lark (0)",
+        ]
+    );
+}
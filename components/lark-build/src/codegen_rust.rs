@@ -5,7 +5,9 @@ use lark_hir as hir;
 use lark_intern::{Intern, Untern};
 use lark_parser::{ParserDatabase, ParserDatabaseExt};
 use lark_query_system::LarkDatabase;
+use lark_ty::base_inferred::BaseInferred;
 use lark_ty::Ty;
+use lark_type_check::{TypeCheckDatabase, TypeCheckResults};
 
 fn build_variable_name(
     db: &LarkDatabase,
@@ -28,9 +30,17 @@ fn build_entity_name(db: &LarkDatabase, entity: Entity) -> String {
     }
 }
 
+fn build_member_name(db: &LarkDatabase, entity: Entity) -> String {
+    match entity.untern(db) {
+        EntityData::MemberName { id, .. } => id.untern(db).to_string(),
+        x => unimplemented!("Unsupported member name: {:#?}", x),
+    }
+}
+
 pub fn build_place(
     db: &LarkDatabase,
     fn_body: &std::sync::Arc<hir::FnBody>,
+    results: &TypeCheckResults<BaseInferred>,
     place: hir::Place,
 ) -> String {
     match &fn_body.tables[place] {
@@ -41,11 +51,13 @@ pub fn build_place(
 
             format!(
                 "{}.{}",
-                build_place(db, fn_body, *owner),
+                build_place(db, fn_body, results, *owner),
                 identifier.text.untern(db).to_string()
             )
         }
-        hir::PlaceData::Temporary(expression) => build_expression(db, fn_body, *expression),
+        hir::PlaceData::Temporary(expression) => {
+            build_expression(db, fn_body, results, *expression)
+        }
     }
 }
 
@@ -149,6 +161,7 @@ pub fn codegen_struct(
 pub fn build_expression(
     db: &LarkDatabase,
     fn_body: &std::sync::Arc<hir::FnBody>,
+    results: &TypeCheckResults<BaseInferred>,
     expression: hir::Expression,
 ) -> String {
     match fn_body.tables[expression] {
@@ -160,25 +173,30 @@ pub fn build_expression(
             Some(init_expression) => format!(
                 "{{ let {} = {};\n{}}}",
                 build_variable_name(db, fn_body, variable),
-                build_expression(db, fn_body, init_expression),
-                build_expression(db, fn_body, body),
+                build_expression(db, fn_body, results, init_expression),
+                build_expression(db, fn_body, results, body),
             ),
             None => format!("let {};\n", build_variable_name(db, fn_body, variable)),
         },
 
-        hir::ExpressionData::Place { place } => build_place(db, fn_body, place),
+        hir::ExpressionData::Place { place } => build_place(db, fn_body, results, place),
 
         hir::ExpressionData::Assignment { place, value } => format!(
             "{} = {};\n",
-            build_place(db, fn_body, place),
-            build_expression(db, fn_body, value)
+            build_place(db, fn_body, results, place),
+            build_expression(db, fn_body, results, value)
         ),
 
         hir::ExpressionData::MethodCall { method, arguments } => {
             let mut arguments = arguments.iter(fn_body);
             let mut output = String::new();
 
-            output.push_str(&build_expression(db, fn_body, arguments.next().unwrap()));
+            output.push_str(&build_expression(
+                db,
+                fn_body,
+                results,
+                arguments.next().unwrap(),
+            ));
 
             let method_name = fn_body.tables[method].text.untern(db);
             output.push_str(&format!(".{}(", method_name));
@@ -190,7 +208,7 @@ pub fn build_expression(
                 } else {
                     first = false;
                 }
-                output.push_str(&build_expression(db, fn_body, argument));
+                output.push_str(&build_expression(db, fn_body, results, argument));
             }
             output.push_str(")");
 
@@ -203,7 +221,7 @@ pub fn build_expression(
         } => {
             let mut output = String::new();
 
-            output.push_str(&build_expression(db, fn_body, function));
+            output.push_str(&build_expression(db, fn_body, results, function));
 
             output.push_str("(");
 
@@ -231,7 +249,7 @@ pub fn build_expression(
                 } else {
                     first = false;
                 }
-                output.push_str(&build_expression(db, fn_body, argument));
+                output.push_str(&build_expression(db, fn_body, results, argument));
             }
             output.push_str(")");
 
@@ -240,8 +258,8 @@ pub fn build_expression(
 
         hir::ExpressionData::Sequence { first, second } => format!(
             "{};\n {}",
-            build_expression(db, fn_body, first),
-            build_expression(db, fn_body, second)
+            build_expression(db, fn_body, results, first),
+            build_expression(db, fn_body, results, second)
         ),
 
         hir::ExpressionData::If {
@@ -250,42 +268,46 @@ pub fn build_expression(
             if_false,
         } => format!(
             "if {} {{ {} \n}} else {{ {} \n}}",
-            build_expression(db, fn_body, condition),
-            build_expression(db, fn_body, if_true),
-            build_expression(db, fn_body, if_false)
+            build_expression(db, fn_body, results, condition),
+            build_expression(db, fn_body, results, if_true),
+            build_expression(db, fn_body, results, if_false)
         ),
 
         hir::ExpressionData::Binary {
             operator,
             left,
             right,
-        } => format!(
-            "({} {} {})",
-            build_expression(db, fn_body, left),
-            match operator {
-                hir::BinaryOperator::Add => "+",
-                hir::BinaryOperator::Subtract => "-",
-                hir::BinaryOperator::Multiply => "*",
-                hir::BinaryOperator::Divide => "/",
-                hir::BinaryOperator::Equals => "==",
-                hir::BinaryOperator::NotEquals => "!=",
-            },
-            build_expression(db, fn_body, right),
-        ),
+        } => match results.entities.get(&expression.into()) {
+            // An operator on a struct resolves to one of its methods
+            // (e.g., `a + b` to `a.add(b)`).
+            Some(&method) => format!(
+                "{}.{}({})",
+                build_expression(db, fn_body, results, left),
+                build_member_name(db, method),
+                build_expression(db, fn_body, results, right),
+            ),
+
+            None => format!(
+                "({} {} {})",
+                build_expression(db, fn_body, results, left),
+                hir::operator_symbol(operator),
+                build_expression(db, fn_body, results, right),
+            ),
+        },
 
         hir::ExpressionData::Unary { operator, value } => format!(
             "{}({})",
             match operator {
                 hir::UnaryOperator::Not => "!",
             },
-            build_expression(db, fn_body, value)
+            build_expression(db, fn_body, results, value)
         ),
 
         hir::ExpressionData::Literal { data } => match data {
             hir::LiteralData {
                 kind: hir::LiteralKind::String,
                 value,
-            } => format!("{}.to_string()", value.untern(db)),
+            } => format!("{:?}.to_string()", &value.untern(db)[..]),
             hir::LiteralData {
                 kind: hir::LiteralKind::UnsignedInteger,
                 value,
@@ -313,7 +335,7 @@ pub fn build_expression(
                     fn_body.tables[identified_expression.identifier]
                         .text
                         .untern(db),
-                    build_expression(db, fn_body, identified_expression.expression),
+                    build_expression(db, fn_body, results, identified_expression.expression),
                 ));
             }
             output.push_str("}");
@@ -335,6 +357,7 @@ pub fn codegen_function(
     let mut errors: Vec<Diagnostic> = vec![];
 
     let fn_body = db.fn_body(entity).accumulate_errors_into(&mut errors);
+    let results = db.base_type_check(entity).into_value();
 
     let signature = db
         .signature(entity)
@@ -368,7 +391,7 @@ pub fn codegen_function(
     output.push_str(&format!("{}", build_type(db, &signature.output)));
     output.push_str(&format!(
         " {{\n{} }}\n",
-        build_expression(db, &fn_body, fn_body.root_expression)
+        build_expression(db, &fn_body, &results, fn_body.root_expression)
     ));
 
     WithError {
@@ -86,8 +86,9 @@ impl LarkDatabaseExt for LarkDatabase {
         Ok(())
     }
 
-    /// Displays all errors for the project on stderr. Returns `Ok(n)` where
-    /// n is the number of errors (or `Cancelled` if execution is cancelled).
+    /// Displays all errors and warnings for the project on stderr. Returns
+    /// `Ok(n)` where n is the number of errors, not counting warnings (or
+    /// `Cancelled` if execution is cancelled).
     fn display_errors(&self, mut out: impl WriteColor) -> Result<usize, Cancelled> {
         let db = self;
 
@@ -99,13 +100,19 @@ impl LarkDatabaseExt for LarkDatabase {
             let file_id: FileName = file_name.into_file_name(&db);
 
             for ranged_diagnostic in ranged_diagnostics {
-                error_count += 1;
+                let severity = match ranged_diagnostic.severity {
+                    lark_error::Severity::Error => {
+                        error_count += 1;
+                        Severity::Error
+                    }
+                    lark_error::Severity::Warning => Severity::Warning,
+                };
                 if !std::mem::replace(&mut first, false) {
                     eprintln!("");
                 }
 
                 let range = ranged_diagnostic.range;
-                let error = Diagnostic::new(Severity::Error, ranged_diagnostic.label);
+                let error = Diagnostic::new(severity, ranged_diagnostic.label);
 
                 let span = Span::new(
                     file_id,
@@ -87,7 +87,7 @@ pub fn repl() {
 
         virtual_fn.push(input);
 
-        db.query_mut(lark_parser::FileTextQuery).set(
+        db.query_mut(lark_parser::FileDiskTextQuery).set(
             repl_filename,
             format!("def main() {{\n{}\n}}", virtual_fn.join("\n")).into(),
         );
@@ -65,11 +65,60 @@ impl ErrorReported {
 pub struct Diagnostic {
     pub span: Span<FileName>,
     pub label: String,
+    pub severity: Severity,
+
+    /// A replacement for the text at `span`, offered to the user as
+    /// "did you mean `...`?".
+    pub suggestion: Option<String>,
+}
+
+/// How serious a diagnostic is.
+#[derive(Copy, Clone, Debug, DebugWith, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// The program is invalid; we will not build or run it.
+    Error,
+
+    /// Something is likely a mistake, but the program is still valid.
+    Warning,
 }
 
 impl Diagnostic {
     pub fn new(label: String, span: Span<FileName>) -> Self {
-        Diagnostic { label, span }
+        Diagnostic {
+            label,
+            span,
+            severity: Severity::Error,
+            suggestion: None,
+        }
+    }
+
+    pub fn warning(label: String, span: Span<FileName>) -> Self {
+        Diagnostic {
+            label,
+            span,
+            severity: Severity::Warning,
+            suggestion: None,
+        }
+    }
+
+    pub fn with_suggestion(self, suggestion: impl Into<String>) -> Self {
+        Diagnostic {
+            suggestion: Some(suggestion.into()),
+            ..self
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// The label together with the suggestion, if any, as shown to
+    /// the user.
+    pub fn message(&self) -> String {
+        match &self.suggestion {
+            Some(suggestion) => format!("{}; did you mean `{}`?", self.label, suggestion),
+            None => self.label.clone(),
+        }
     }
 }
 
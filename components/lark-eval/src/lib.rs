@@ -413,10 +413,7 @@ pub fn eval_expression(
                 value,
             } => {
                 if ready_to_execute {
-                    let text = value.untern(db);
-                    let string = text.to_string();
-                    let string = string[1..string.len()-1].to_string();
-                    Value::Str(string)
+                    Value::Str(value.untern(db).to_string())
                 } else {
                     Value::Skipped
                 }
@@ -10,6 +10,7 @@ use lark_collections::{FxIndexMap, IndexVec, U32Index};
 use lark_debug_derive::DebugWith;
 use lark_debug_with::DebugWith;
 use lark_entity::Entity;
+use lark_entity::LangItem;
 use lark_entity::MemberKind;
 use lark_error::ErrorReported;
 use lark_error::ErrorSentinel;
@@ -111,6 +112,12 @@ impl FnBodyTables {
     }
 }
 
+impl AsRef<FnBodyTables> for FnBodyTables {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
 impl AsMut<FnBodyTables> for FnBodyTables {
     fn as_mut(&mut self) -> &mut Self {
         self
@@ -124,6 +131,9 @@ pub trait HirIndex: U32Index + Into<MetaIndex> + DebugWith {
 
     fn index_vec(hir: &FnBodyTables) -> &IndexVec<Self, Self::Data>;
     fn index_vec_mut(hir: &mut FnBodyTables) -> &mut IndexVec<Self, Self::Data>;
+
+    /// How far indices of this kind were shifted by an `append`.
+    fn append_offset(offsets: &AppendOffsets) -> usize;
 }
 
 pub trait HirIndexData: Sized + Clone + DebugWith {
@@ -244,6 +254,10 @@ macro_rules! define_meta_index {
                 ) -> &mut IndexVec<Self, Self::Data> {
                     &mut hir.$field
                 }
+
+                fn append_offset(offsets: &AppendOffsets) -> usize {
+                    offsets.$field
+                }
             }
 
             impl HirIndexData for $data_ty {
@@ -291,6 +305,60 @@ macro_rules! define_meta_index {
                 }
             }
         }
+
+        /// Records, for each kind of index, how far the indices were
+        /// shifted when one set of tables was appended onto another.
+        /// See `FnBodyTables::append`.
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        pub struct AppendOffsets {
+            $(
+                $field: usize,
+            )*
+        }
+
+        impl AppendOffsets {
+            fn remap_meta_index(&self, index: MetaIndex) -> MetaIndex {
+                match index {
+                    $(
+                        MetaIndex::$index_ty(index) => MetaIndex::$index_ty(self.remap(index)),
+                    )*
+                }
+            }
+        }
+
+        impl FnBodyTables {
+            /// Appends all the nodes from `other` onto these tables
+            /// (e.g., to splice a callee's body into its caller),
+            /// rewriting the indices within them to match their new
+            /// positions. The returned offsets can be used to
+            /// translate other indices from `other` (such as its root
+            /// expression) into indices in `self`.
+            pub fn append(&mut self, other: &FnBodyTables) -> AppendOffsets {
+                let offsets = AppendOffsets {
+                    $(
+                        $field: self.$field.len(),
+                    )*
+                };
+
+                let mut remapper = Remapper {
+                    offsets: &offsets,
+                    source: other,
+                    list_entries: &mut self.list_entries,
+                };
+
+                $(
+                    for data in other.$field.iter() {
+                        self.$field.push(data.clone().remap_indices(&mut remapper));
+                    }
+                )*
+
+                for (&index, &span) in other.spans.iter() {
+                    self.spans.insert(offsets.remap_meta_index(index), span);
+                }
+
+                offsets
+            }
+        }
     };
 }
 
@@ -303,6 +371,179 @@ define_meta_index! {
     (Error, ErrorData, errors),
 }
 
+impl AppendOffsets {
+    /// Translates `index`, which refers into the tables that were
+    /// appended, into the corresponding index in the combined tables.
+    pub fn remap<I: HirIndex>(&self, index: I) -> I {
+        I::from_usize(index.as_usize() + I::append_offset(self))
+    }
+}
+
+/// State used by `FnBodyTables::append` while copying nodes over.
+struct Remapper<'t> {
+    offsets: &'t AppendOffsets,
+
+    /// The tables being appended.
+    source: &'t FnBodyTables,
+
+    /// The `list_entries` of the tables being appended onto; lists
+    /// are copied here as they are encountered.
+    list_entries: &'t mut Vec<u32>,
+}
+
+/// Implemented by the HIR indices and by any data that contains
+/// them, so that they can be adjusted when appended onto other
+/// tables.
+trait RemapIndices {
+    fn remap_indices(self, remapper: &mut Remapper<'_>) -> Self;
+}
+
+impl<I: HirIndex> RemapIndices for I {
+    fn remap_indices(self, remapper: &mut Remapper<'_>) -> Self {
+        remapper.offsets.remap(self)
+    }
+}
+
+impl<I: HirIndex> RemapIndices for List<I> {
+    fn remap_indices(self, remapper: &mut Remapper<'_>) -> Self {
+        let Remapper {
+            offsets,
+            source,
+            list_entries,
+        } = remapper;
+        let start_index = list_entries.len();
+        list_entries.extend(
+            self.iter(*source)
+                .map(|index| offsets.remap(index).as_u32()),
+        );
+        List::from_start_and_end(start_index, list_entries.len())
+    }
+}
+
+impl RemapIndices for ExpressionData {
+    fn remap_indices(self, remapper: &mut Remapper<'_>) -> Self {
+        match self {
+            ExpressionData::Let {
+                variable,
+                initializer,
+                body,
+            } => ExpressionData::Let {
+                variable: variable.remap_indices(remapper),
+                initializer: initializer.map(|initializer| initializer.remap_indices(remapper)),
+                body: body.remap_indices(remapper),
+            },
+
+            ExpressionData::Place { place } => ExpressionData::Place {
+                place: place.remap_indices(remapper),
+            },
+
+            ExpressionData::Assignment { place, value } => ExpressionData::Assignment {
+                place: place.remap_indices(remapper),
+                value: value.remap_indices(remapper),
+            },
+
+            ExpressionData::MethodCall { method, arguments } => ExpressionData::MethodCall {
+                method: method.remap_indices(remapper),
+                arguments: arguments.remap_indices(remapper),
+            },
+
+            ExpressionData::Call {
+                function,
+                arguments,
+            } => ExpressionData::Call {
+                function: function.remap_indices(remapper),
+                arguments: arguments.remap_indices(remapper),
+            },
+
+            ExpressionData::Sequence { first, second } => ExpressionData::Sequence {
+                first: first.remap_indices(remapper),
+                second: second.remap_indices(remapper),
+            },
+
+            ExpressionData::If {
+                condition,
+                if_true,
+                if_false,
+            } => ExpressionData::If {
+                condition: condition.remap_indices(remapper),
+                if_true: if_true.remap_indices(remapper),
+                if_false: if_false.remap_indices(remapper),
+            },
+
+            ExpressionData::Binary {
+                operator,
+                left,
+                right,
+            } => ExpressionData::Binary {
+                operator,
+                left: left.remap_indices(remapper),
+                right: right.remap_indices(remapper),
+            },
+
+            ExpressionData::Unary { operator, value } => ExpressionData::Unary {
+                operator,
+                value: value.remap_indices(remapper),
+            },
+
+            ExpressionData::Aggregate { entity, fields } => ExpressionData::Aggregate {
+                entity,
+                fields: fields.remap_indices(remapper),
+            },
+
+            ExpressionData::Error { error } => ExpressionData::Error {
+                error: error.remap_indices(remapper),
+            },
+
+            ExpressionData::Literal { .. } | ExpressionData::Unit {} => self,
+        }
+    }
+}
+
+impl RemapIndices for IdentifiedExpressionData {
+    fn remap_indices(self, remapper: &mut Remapper<'_>) -> Self {
+        IdentifiedExpressionData {
+            identifier: self.identifier.remap_indices(remapper),
+            expression: self.expression.remap_indices(remapper),
+        }
+    }
+}
+
+impl RemapIndices for PlaceData {
+    fn remap_indices(self, remapper: &mut Remapper<'_>) -> Self {
+        match self {
+            PlaceData::Variable(variable) => PlaceData::Variable(variable.remap_indices(remapper)),
+            PlaceData::Entity(_) => self,
+            PlaceData::Temporary(expression) => {
+                PlaceData::Temporary(expression.remap_indices(remapper))
+            }
+            PlaceData::Field { owner, name } => PlaceData::Field {
+                owner: owner.remap_indices(remapper),
+                name: name.remap_indices(remapper),
+            },
+        }
+    }
+}
+
+impl RemapIndices for VariableData {
+    fn remap_indices(self, remapper: &mut Remapper<'_>) -> Self {
+        VariableData {
+            name: self.name.remap_indices(remapper),
+        }
+    }
+}
+
+impl RemapIndices for IdentifierData {
+    fn remap_indices(self, _remapper: &mut Remapper<'_>) -> Self {
+        self
+    }
+}
+
+impl RemapIndices for ErrorData {
+    fn remap_indices(self, _remapper: &mut Remapper<'_>) -> Self {
+        self
+    }
+}
+
 /// A list of "HIR indices" of type `I`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct List<I: HirIndex> {
@@ -505,6 +746,19 @@ pub enum BinaryOperator {
     NotEquals,
 }
 
+/// The symbol used for `op` in source code (e.g., `+` for `Add`), for
+/// use when printing HIR or describing an expression to the user.
+pub fn operator_symbol(op: BinaryOperator) -> &'static str {
+    match op {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Equals => "==",
+        BinaryOperator::NotEquals => "!=",
+    }
+}
+
 #[derive(Copy, Clone, Debug, DebugWith, PartialEq, Eq, Hash)]
 pub enum UnaryOperator {
     Not,
@@ -538,7 +792,8 @@ pub struct LiteralData {
 
     /// We represent all literals as strings internally, which
     /// sidesteps questions about how many bits to allocate for an
-    /// integer and so forth.
+    /// integer and so forth. For a string literal, this is the string
+    /// itself, without quotes and with escapes decoded.
     pub value: GlobalIdentifier,
 }
 
@@ -548,6 +803,14 @@ pub enum LiteralKind {
     String,
 }
 
+/// The lang item naming the type of literals of the given kind.
+pub fn literal_lang_item(kind: LiteralKind) -> LangItem {
+    match kind {
+        LiteralKind::UnsignedInteger => LangItem::Uint,
+        LiteralKind::String => LangItem::String,
+    }
+}
+
 lark_collections::index_type! {
     pub struct Variable { .. }
 }
@@ -1,12 +1,14 @@
 use lark_actor::{self, Actor, LspResponse, QueryRequest};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::io::prelude::{Read, Write};
 use std::sync::mpsc::Sender;
 use url::Url;
 
+mod test;
+
 /// The command given by the IDE to the LSP server. These represent the actions of the user in the IDE,
 /// as well as actions the IDE might perform as a result of user actions (like cancelling a task)
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,6 +48,33 @@ pub enum LSPCommand {
         id: usize,
         params: languageserver_types::TextDocumentPositionParams,
     },
+    #[serde(rename = "textDocument/documentHighlight")]
+    documentHighlight {
+        id: usize,
+        params: languageserver_types::TextDocumentPositionParams,
+    },
+    #[serde(rename = "textDocument/inlayHint")]
+    inlayHint { id: usize, params: InlayHintParams },
+    #[serde(rename = "workspace/symbol")]
+    workspaceSymbol {
+        id: usize,
+        params: languageserver_types::WorkspaceSymbolParams,
+    },
+    #[serde(rename = "lark/incomingCalls")]
+    incomingCalls {
+        id: usize,
+        params: languageserver_types::TextDocumentPositionParams,
+    },
+    #[serde(rename = "textDocument/selectionRange")]
+    selectionRange {
+        id: usize,
+        params: SelectionRangeParams,
+    },
+    #[serde(rename = "textDocument/codeAction")]
+    codeAction {
+        id: usize,
+        params: languageserver_types::CodeActionParams,
+    },
     #[serde(rename = "textDocument/rename")]
     rename {
         id: usize,
@@ -62,6 +91,167 @@ pub enum LSPCommand {
     },
 }
 
+/// Parameters of the `textDocument/inlayHint` request, which
+/// `languageserver_types` does not yet know about.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHintParams {
+    pub text_document: languageserver_types::TextDocumentIdentifier,
+    pub range: languageserver_types::Range,
+}
+
+/// A single inlay hint: some text to display at a position in the
+/// document.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InlayHint {
+    pub position: languageserver_types::Position,
+    pub label: String,
+}
+
+/// A call to the requested fn, as returned by `lark/incomingCalls`
+/// (`languageserver_types` does not yet know about call hierarchies).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingCall {
+    /// The name of the calling fn.
+    pub from: String,
+    pub location: languageserver_types::Location,
+}
+
+/// Parameters of the `textDocument/selectionRange` request, which
+/// `languageserver_types` does not yet know about.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectionRangeParams {
+    pub text_document: languageserver_types::TextDocumentIdentifier,
+    pub positions: Vec<languageserver_types::Position>,
+}
+
+/// A range to expand the selection to, along with the next range
+/// out (which contains it).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectionRange {
+    pub range: languageserver_types::Range,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<Box<SelectionRange>>,
+}
+
+impl SelectionRange {
+    /// Nests `ranges`, which are given innermost first, so that each
+    /// range is the parent of the one before it.
+    pub fn from_ranges(ranges: Vec<languageserver_types::Range>) -> Option<SelectionRange> {
+        ranges.into_iter().rev().fold(None, |parent, range| {
+            Some(SelectionRange {
+                range,
+                parent: parent.map(Box::new),
+            })
+        })
+    }
+}
+
+/// Parameters of the `$/progress` notification, reporting how far
+/// along the work identified by `token` is. The token must first be
+/// created with a `window/workDoneProgress/create` request.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProgressParams {
+    pub token: usize,
+    pub value: WorkDoneProgress,
+}
+
+/// The stages of work-done progress: one `Begin`, any number of
+/// `Report`s, then one `End`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum WorkDoneProgress {
+    Begin {
+        title: String,
+        message: String,
+        percentage: u32,
+    },
+    Report {
+        message: String,
+        percentage: u32,
+    },
+    End {},
+}
+
+/// Parameters of the `window/workDoneProgress/create` request.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkDoneProgressCreateParams {
+    pub token: usize,
+}
+
+/// A message that `ProgressTracker` wants sent to the IDE.
+#[derive(Debug, PartialEq)]
+enum ProgressMessage {
+    /// A `window/workDoneProgress/create` request.
+    Create(WorkDoneProgressCreateParams),
+    /// A `$/progress` notification.
+    Notify(ProgressParams),
+}
+
+/// Turns the `(done, total)` progress of tasks into LSP work-done
+/// progress. Each task's id is used as its progress token; the token
+/// is created and the progress begun on the first update, and the
+/// progress ends once the task's final response goes out. Nothing is
+/// sent unless the IDE said it supports `window.workDoneProgress`.
+#[derive(Debug, Default)]
+struct ProgressTracker {
+    enabled: bool,
+    in_progress: HashSet<usize>,
+}
+
+impl ProgressTracker {
+    /// Records whether the IDE supports work-done progress.
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// The messages reporting that `task` has done `done` out of
+    /// `total` units of work.
+    fn progress(&mut self, task: usize, done: u32, total: u32) -> Vec<ProgressMessage> {
+        if !self.enabled {
+            return vec![];
+        }
+
+        let message = format!("{}/{}", done, total);
+        let percentage = if total == 0 { 100 } else { done * 100 / total };
+        if self.in_progress.insert(task) {
+            vec![
+                ProgressMessage::Create(WorkDoneProgressCreateParams { token: task }),
+                ProgressMessage::Notify(ProgressParams {
+                    token: task,
+                    value: WorkDoneProgress::Begin {
+                        title: "lark".to_string(),
+                        message,
+                        percentage,
+                    },
+                }),
+            ]
+        } else {
+            vec![ProgressMessage::Notify(ProgressParams {
+                token: task,
+                value: WorkDoneProgress::Report {
+                    message,
+                    percentage,
+                },
+            })]
+        }
+    }
+
+    /// The message ending the progress of `task`, if any was begun,
+    /// to be sent along with its final response.
+    fn finished(&mut self, task: usize) -> Option<ProgressMessage> {
+        if self.in_progress.remove(&task) {
+            Some(ProgressMessage::Notify(ProgressParams {
+                token: task,
+                value: WorkDoneProgress::End {},
+            }))
+        } else {
+            None
+        }
+    }
+}
+
 /// A wrapper for responses back to the IDE from the LSP service. These must follow
 /// the JSON 2.0 RPC spec
 #[derive(Debug, Serialize, Deserialize)]
@@ -80,6 +270,34 @@ impl<T> JsonRPCResponse<T> {
     }
 }
 
+/// A wrapper for error responses back to the IDE from the LSP service. These must
+/// follow the JSON 2.0 RPC spec
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonRPCErrorResponse {
+    jsonrpc: String,
+    pub id: usize,
+    pub error: JsonRPCError,
+}
+impl JsonRPCErrorResponse {
+    pub fn new(id: usize, code: i64, message: String) -> Self {
+        JsonRPCErrorResponse {
+            jsonrpc: "2.0".into(),
+            id,
+            error: JsonRPCError { code, message },
+        }
+    }
+}
+
+/// The error object of a `JsonRPCErrorResponse`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonRPCError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// The JSON RPC error code for an internal error in the server
+const INTERNAL_ERROR: i64 = -32603;
+
 /// A wrapper for proactive notifications to the IDE (eg. diagnostics). These must
 /// follow the JSON 2.0 RPC spec
 #[derive(Debug, Serialize, Deserialize)]
@@ -98,6 +316,26 @@ impl<T> JsonRPCNotification<T> {
     }
 }
 
+/// A request from the LSP service to the IDE (eg. to create a
+/// progress token). These must follow the JSON 2.0 RPC spec
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonRPCRequest<T> {
+    jsonrpc: String,
+    pub id: usize,
+    pub method: String,
+    pub params: T,
+}
+impl<T> JsonRPCRequest<T> {
+    pub fn new(id: usize, method: String, params: T) -> Self {
+        JsonRPCRequest {
+            jsonrpc: "2.0".into(),
+            id,
+            method,
+            params,
+        }
+    }
+}
+
 /// Helper function to do the work of sending a result back to the IDE
 fn send_response<T: Serialize>(id: usize, result: T) {
     let response = JsonRPCResponse::new(id, result);
@@ -108,6 +346,62 @@ fn send_response<T: Serialize>(id: usize, result: T) {
     let _ = io::stdout().flush();
 }
 
+/// Helper function to send an error back to the IDE in place of a result
+fn send_error_response(id: usize, message: String) {
+    let response = JsonRPCErrorResponse::new(id, INTERNAL_ERROR, message);
+    let response_raw = serde_json::to_string(&response).unwrap();
+
+    print!("Content-Length: {}\r\n\r\n", response_raw.len());
+    print!("{}", response_raw);
+    let _ = io::stdout().flush();
+}
+
+/// Helper function to forward a request to the query system. If the query
+/// system has shut down (e.g., because it panicked), the request can never be
+/// answered, so we reply to the IDE with an error rather than leave it waiting.
+fn forward_to_query_system(send_to_query_channel: &Sender<QueryRequest>, request: QueryRequest) {
+    if let Some(LspResponse::Error(id, message)) =
+        try_forward_to_query_system(send_to_query_channel, request)
+    {
+        send_error_response(id, message);
+    }
+}
+
+/// The guts of `forward_to_query_system`: returns the error to reply
+/// with if `request` could not be delivered and expects a reply.
+fn try_forward_to_query_system(
+    send_to_query_channel: &Sender<QueryRequest>,
+    request: QueryRequest,
+) -> Option<LspResponse> {
+    let request = send_to_query_channel.send(request).err()?.0;
+    eprintln!("Query system is not running, dropping {:?}", request);
+    let id = request.task_id()?;
+    Some(LspResponse::Error(
+        id,
+        "query system is not running".to_string(),
+    ))
+}
+
+/// Helper function to group edits by file into a `WorkspaceEdit`
+fn workspace_edit(
+    edits: Vec<(Url, languageserver_types::Range, String)>,
+) -> languageserver_types::WorkspaceEdit {
+    let mut map_of_edits: HashMap<Url, Vec<languageserver_types::TextEdit>> = HashMap::new();
+
+    for edit in edits.into_iter() {
+        let entry = map_of_edits.entry(edit.0).or_insert(vec![]);
+        (*entry).push(languageserver_types::TextEdit {
+            range: edit.1,
+            new_text: edit.2,
+        });
+    }
+
+    languageserver_types::WorkspaceEdit {
+        changes: Some(map_of_edits),
+        document_changes: None,
+    }
+}
+
 /// Helper function to send a proactive notification back to the IDE
 fn send_notification<T: Serialize>(method: String, notice: T) {
     let response = JsonRPCNotification::new(method, notice);
@@ -118,13 +412,68 @@ fn send_notification<T: Serialize>(method: String, notice: T) {
     let _ = io::stdout().flush();
 }
 
+/// Helper function to send a request of our own to the IDE
+fn send_request<T: Serialize>(id: usize, method: String, params: T) {
+    let request = JsonRPCRequest::new(id, method, params);
+    let request_raw = serde_json::to_string(&request).unwrap();
+
+    print!("Content-Length: {}\r\n\r\n", request_raw.len());
+    print!("{}", request_raw);
+    let _ = io::stdout().flush();
+}
+
+fn publish_diagnostics_params(
+    url: Url,
+    diagnostics: Vec<(
+        languageserver_types::Range,
+        String,
+        languageserver_types::DiagnosticSeverity,
+    )>,
+) -> languageserver_types::PublishDiagnosticsParams {
+    let lsp_diagnostics: Vec<languageserver_types::Diagnostic> = diagnostics
+        .into_iter()
+        .map(|(range, diag, severity)| {
+            let mut diagnostic = languageserver_types::Diagnostic::new_simple(range, diag);
+            diagnostic.severity = Some(severity);
+            diagnostic
+        })
+        .collect();
+
+    languageserver_types::PublishDiagnosticsParams {
+        uri: url,
+        diagnostics: lsp_diagnostics,
+    }
+}
+
 /// The LSP service is split into two parts:
 ///   * The server, which handles incoming requests from the IDE
 ///   * The responder, which sends out results when they're ready
 /// The server sends messages *to* the task manager for work that
 /// needs to be done. The responder receives messages *from* the
 /// task manager for work that has been accomplished.
-pub struct LspResponder;
+#[derive(Default)]
+pub struct LspResponder {
+    /// Which tasks we have begun reporting progress for.
+    progress: ProgressTracker,
+
+    /// The id of the next request we send to the IDE.
+    next_request_id: usize,
+}
+
+impl LspResponder {
+    fn send_progress(&mut self, message: ProgressMessage) {
+        match message {
+            ProgressMessage::Create(params) => {
+                let id = self.next_request_id;
+                self.next_request_id += 1;
+                send_request(id, "window/workDoneProgress/create".into(), params);
+            }
+            ProgressMessage::Notify(params) => {
+                send_notification("$/progress".into(), params);
+            }
+        }
+    }
+}
 
 impl Actor for LspResponder {
     type InMessage = LspResponse;
@@ -133,7 +482,14 @@ impl Actor for LspResponder {
     /// a given task. This allows us to repond to the IDE in an orderly
     /// manner.
     fn receive_messages(&mut self, messages: &mut VecDeque<Self::InMessage>) {
-        match messages.pop_front().unwrap() {
+        let message = messages.pop_front().unwrap();
+        if let Some(id) = message.task_id() {
+            if let Some(end) = self.progress.finished(id) {
+                self.send_progress(end);
+            }
+        }
+
+        match message {
             LspResponse::Type(id, ty) => {
                 let result = languageserver_types::Hover {
                     contents: languageserver_types::HoverContents::Scalar(
@@ -144,6 +500,19 @@ impl Actor for LspResponder {
 
                 send_response(id, result);
             }
+            LspResponse::Hover(id, content) => {
+                let result = content.map(|content| languageserver_types::Hover {
+                    contents: languageserver_types::HoverContents::Markup(
+                        languageserver_types::MarkupContent {
+                            kind: languageserver_types::MarkupKind::Markdown,
+                            value: content.to_markdown(),
+                        },
+                    ),
+                    range: None,
+                });
+
+                send_response(id, result);
+            }
             LspResponse::Range(id, uri, range) => {
                 let result = languageserver_types::Location { uri, range };
 
@@ -160,25 +529,79 @@ impl Actor for LspResponder {
 
                 send_response(id, result);
             }
-            LspResponse::WorkspaceEdits(id, vec_of_edits) => {
-                let mut map_of_edits: HashMap<Url, Vec<languageserver_types::TextEdit>> =
-                    HashMap::new();
-
-                for edit in vec_of_edits.into_iter() {
-                    let entry = map_of_edits.entry(edit.0).or_insert(vec![]);
-                    (*entry).push(languageserver_types::TextEdit {
-                        range: edit.1,
-                        new_text: edit.2,
-                    });
-                }
+            LspResponse::Highlights(id, highlights) => {
+                let result: Vec<languageserver_types::DocumentHighlight> = highlights
+                    .into_iter()
+                    .map(|(range, kind)| languageserver_types::DocumentHighlight {
+                        range,
+                        kind: Some(kind),
+                    })
+                    .collect();
 
-                let result = languageserver_types::WorkspaceEdit {
-                    changes: Some(map_of_edits),
-                    document_changes: None,
-                };
+                send_response(id, result);
+            }
+            LspResponse::InlayHints(id, hints) => {
+                let result: Vec<InlayHint> = hints
+                    .into_iter()
+                    .map(|(position, label)| InlayHint { position, label })
+                    .collect();
+
+                send_response(id, result);
+            }
+            LspResponse::Symbols(id, symbols) => {
+                let result: Vec<languageserver_types::SymbolInformation> = symbols
+                    .into_iter()
+                    .map(
+                        |(name, kind, uri, range)| languageserver_types::SymbolInformation {
+                            name,
+                            kind,
+                            deprecated: None,
+                            location: languageserver_types::Location { uri, range },
+                            container_name: None,
+                        },
+                    )
+                    .collect();
 
                 send_response(id, result);
             }
+            LspResponse::IncomingCalls(id, calls) => {
+                let result: Vec<IncomingCall> = calls
+                    .into_iter()
+                    .map(|(from, uri, range)| IncomingCall {
+                        from,
+                        location: languageserver_types::Location { uri, range },
+                    })
+                    .collect();
+
+                send_response(id, result);
+            }
+            LspResponse::SelectionRanges(id, ranges) => {
+                // The query system gives at least one range for each
+                // position.
+                let result: Vec<SelectionRange> = ranges
+                    .into_iter()
+                    .map(|ranges| SelectionRange::from_ranges(ranges).unwrap())
+                    .collect();
+
+                send_response(id, result);
+            }
+            LspResponse::CodeActions(id, actions) => {
+                let result: Vec<languageserver_types::CodeAction> = actions
+                    .into_iter()
+                    .map(|(title, edits)| languageserver_types::CodeAction {
+                        title,
+                        kind: Some("quickfix".into()),
+                        diagnostics: None,
+                        edit: Some(workspace_edit(edits)),
+                        command: None,
+                    })
+                    .collect();
+
+                send_response(id, result);
+            }
+            LspResponse::WorkspaceEdits(id, vec_of_edits) => {
+                send_response(id, workspace_edit(vec_of_edits));
+            }
             LspResponse::Nothing(id) => {
                 send_response(id, ());
             }
@@ -199,7 +622,9 @@ impl Actor for LspResponder {
 
                 send_response(id, result);
             }
-            LspResponse::Initialized(id) => {
+            LspResponse::Initialized(id, work_done_progress) => {
+                self.progress.set_enabled(work_done_progress);
+
                 let result = languageserver_types::InitializeResult {
                     capabilities: languageserver_types::ServerCapabilities {
                         text_document_sync: Some(
@@ -220,10 +645,12 @@ impl Actor for LspResponder {
                         type_definition_provider: None,
                         implementation_provider: None,
                         references_provider: Some(true),
-                        document_highlight_provider: None,
+                        document_highlight_provider: Some(true),
                         document_symbol_provider: None,
-                        workspace_symbol_provider: None,
-                        code_action_provider: None,
+                        workspace_symbol_provider: Some(true),
+                        code_action_provider: Some(
+                            languageserver_types::CodeActionProviderCapability::Simple(true),
+                        ),
                         code_lens_provider: None,
                         document_formatting_provider: None,
                         document_range_formatting_provider: None,
@@ -240,29 +667,97 @@ impl Actor for LspResponder {
 
                 send_response(id, result);
             }
+            LspResponse::Progress(id, done, total) => {
+                for message in self.progress.progress(id, done, total) {
+                    self.send_progress(message);
+                }
+            }
+            LspResponse::Error(id, message) => {
+                send_error_response(id, message);
+            }
             LspResponse::Diagnostics(url, diagnostics) => {
-                let lsp_diagnostics: Vec<languageserver_types::Diagnostic> = diagnostics
-                    .iter()
-                    .map(|(range, diag)| {
-                        languageserver_types::Diagnostic::new_simple(*range, diag.clone())
-                    })
-                    .collect();
-
-                let notice = languageserver_types::PublishDiagnosticsParams {
-                    uri: url,
-                    diagnostics: lsp_diagnostics,
-                };
+                send_notification(
+                    "textDocument/publishDiagnostics".into(),
+                    publish_diagnostics_params(url, diagnostics),
+                );
+            }
+            LspResponse::DiagnosticsBatch(batch) => {
+                // LSP has no batched notifications, so send one
+                // notification per file, but write them all at once.
+                let mut output = String::new();
+                for (url, diagnostics) in batch {
+                    let notice = JsonRPCNotification::new(
+                        "textDocument/publishDiagnostics".into(),
+                        publish_diagnostics_params(url, diagnostics),
+                    );
+                    let notice_raw = serde_json::to_string(&notice).unwrap();
+                    output.push_str(&format!("Content-Length: {}\r\n\r\n", notice_raw.len()));
+                    output.push_str(&notice_raw);
+                }
 
-                send_notification("textDocument/publishDiagnostics".into(), notice);
+                print!("{}", output);
+                let _ = io::stdout().flush();
             }
         }
     }
 }
 
+/// Tracks the text each file had when the IDE last opened it. Editors
+/// often re-send `didOpen` for a file that is already open (e.g., when
+/// it regains focus); if the text is unchanged, there is no need to
+/// send it to the query system (and re-check the project) again.
+#[derive(Default)]
+struct OpenFiles {
+    contents: HashMap<Url, String>,
+}
+
+impl OpenFiles {
+    /// Records that `url` was opened with `text`. Returns false if
+    /// that is what it was last opened with (and it has not been
+    /// edited since).
+    fn open(&mut self, url: &Url, text: &str) -> bool {
+        if self.contents.get(url).map(|contents| &contents[..]) == Some(text) {
+            return false;
+        }
+        self.contents.insert(url.clone(), text.to_string());
+        true
+    }
+
+    /// Records that `url` was edited, so that its text is no longer
+    /// known here.
+    fn edited(&mut self, url: &Url) {
+        self.contents.remove(url);
+    }
+}
+
+/// True if the raw `initialize` request says that the IDE supports
+/// work-done progress, which `languageserver_types` does not yet know
+/// about.
+fn supports_work_done_progress(raw: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(raw)
+        .ok()
+        .and_then(|request| {
+            request
+                .pointer("/params/capabilities/window/workDoneProgress")
+                .and_then(|value| value.as_bool())
+        })
+        .unwrap_or(false)
+}
+
+/// True if the raw message is a response (which, unlike a request or
+/// notification, has no method).
+fn is_response(raw: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(raw)
+        .map(|message| message.get("method").is_none())
+        .unwrap_or(false)
+}
+
 /// The workhorse function for handling incoming requests from the IDE. This will
 /// take instructions from stdin sent by the IDE and then send them to the appropriate
 /// system.
 pub fn lsp_serve(send_to_query_channel: Sender<QueryRequest>) {
+    let mut open_files = OpenFiles::default();
+
     loop {
         let mut input = String::new();
         match io::stdin().read_line(&mut input) {
@@ -279,7 +774,13 @@ pub fn lsp_serve(send_to_query_channel: Sender<QueryRequest>) {
 
                     match command {
                         Ok(LSPCommand::initialize { id, .. }) => {
-                            let _ = send_to_query_channel.send(QueryRequest::Initialize(id));
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::Initialize(
+                                    id,
+                                    supports_work_done_progress(&buffer_string),
+                                ),
+                            );
                         }
                         Ok(LSPCommand::initialized) => {
                             //eprintln!("Initialized received");
@@ -287,56 +788,129 @@ pub fn lsp_serve(send_to_query_channel: Sender<QueryRequest>) {
                         Ok(LSPCommand::didOpen { params }) => {
                             //eprintln!("didOpen: {:#?}", params);
 
-                            let _ = send_to_query_channel.send(QueryRequest::OpenFile(
-                                params.text_document.uri.clone(),
-                                params.text_document.text.clone(),
-                            ));
+                            let document = params.text_document;
+                            if open_files.open(&document.uri, &document.text) {
+                                forward_to_query_system(
+                                    &send_to_query_channel,
+                                    QueryRequest::OpenFile(document.uri, document.text),
+                                );
+                            }
                         }
                         Ok(LSPCommand::didChange { params }) => {
                             //eprintln!("didChange: {:#?}", params);
 
+                            open_files.edited(&params.text_document.uri);
+
                             let changes = params
                                 .content_changes
                                 .iter()
                                 .map(|x| (x.range.unwrap(), x.text.clone()))
                                 .collect();
 
-                            let _ = send_to_query_channel.send(QueryRequest::EditFile(
-                                params.text_document.uri.clone(),
-                                changes,
-                            ));
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::EditFile(params.text_document.uri.clone(), changes),
+                            );
                         }
                         Ok(LSPCommand::hover { id, params }) => {
                             //eprintln!("hover: id={} {:#?}", id, params);
 
-                            let _ = send_to_query_channel.send(QueryRequest::TypeAtPosition(
-                                id,
-                                params.text_document.uri.clone(),
-                                params.position.clone(),
-                            ));
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::Hover(
+                                    id,
+                                    params.text_document.uri.clone(),
+                                    params.position.clone(),
+                                ),
+                            );
                         }
                         Ok(LSPCommand::definition { id, params }) => {
-                            let _ = send_to_query_channel.send(QueryRequest::DefinitionAtPosition(
-                                id,
-                                params.text_document.uri.clone(),
-                                params.position.clone(),
-                            ));
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::DefinitionAtPosition(
+                                    id,
+                                    params.text_document.uri.clone(),
+                                    params.position.clone(),
+                                ),
+                            );
                         }
                         Ok(LSPCommand::references { id, params }) => {
-                            let _ = send_to_query_channel.send(QueryRequest::ReferencesAtPosition(
-                                id,
-                                params.text_document.uri.clone(),
-                                params.position.clone(),
-                                true,
-                            ));
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::ReferencesAtPosition(
+                                    id,
+                                    params.text_document.uri.clone(),
+                                    params.position.clone(),
+                                    true,
+                                ),
+                            );
+                        }
+                        Ok(LSPCommand::documentHighlight { id, params }) => {
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::DocumentHighlight(
+                                    id,
+                                    params.text_document.uri.clone(),
+                                    params.position.clone(),
+                                ),
+                            );
+                        }
+                        Ok(LSPCommand::inlayHint { id, params }) => {
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::InlayHints(
+                                    id,
+                                    params.text_document.uri.clone(),
+                                    params.range.clone(),
+                                ),
+                            );
+                        }
+                        Ok(LSPCommand::workspaceSymbol { id, params }) => {
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::WorkspaceSymbols(id, params.query.clone()),
+                            );
+                        }
+                        Ok(LSPCommand::incomingCalls { id, params }) => {
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::IncomingCalls(
+                                    id,
+                                    params.text_document.uri.clone(),
+                                    params.position.clone(),
+                                ),
+                            );
+                        }
+                        Ok(LSPCommand::selectionRange { id, params }) => {
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::SelectionRange(
+                                    id,
+                                    params.text_document.uri.clone(),
+                                    params.positions,
+                                ),
+                            );
+                        }
+                        Ok(LSPCommand::codeAction { id, params }) => {
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::CodeAction(
+                                    id,
+                                    params.text_document.uri.clone(),
+                                    params.range,
+                                ),
+                            );
                         }
                         Ok(LSPCommand::rename { id, params }) => {
-                            let _ = send_to_query_channel.send(QueryRequest::RenameAtPosition(
-                                id,
-                                params.text_document.uri.clone(),
-                                params.position.clone(),
-                                params.new_name.clone(),
-                            ));
+                            forward_to_query_system(
+                                &send_to_query_channel,
+                                QueryRequest::RenameAtPosition(
+                                    id,
+                                    params.text_document.uri.clone(),
+                                    params.position.clone(),
+                                    params.new_name.clone(),
+                                ),
+                            );
                         }
                         Ok(LSPCommand::completion { .. }) => {
                             //eprintln!("completion: id={} {:#?}", id, params);
@@ -360,6 +934,9 @@ pub fn lsp_serve(send_to_query_channel: Sender<QueryRequest>) {
                                 "Non-number cancellation IDs not currently supported"
                             ),
                         },
+                        // The IDE's replies to our own requests (e.g.,
+                        // to create a progress token) need no handling.
+                        Err(_) if is_response(&buffer_string) => {}
                         Err(e) => eprintln!("Error handling command: {:?}", e),
                     }
                 }
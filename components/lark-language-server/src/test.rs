@@ -0,0 +1,112 @@
+#![cfg(test)]
+
+use crate::{
+    supports_work_done_progress, try_forward_to_query_system, OpenFiles, ProgressMessage,
+    ProgressParams, ProgressTracker, WorkDoneProgress, WorkDoneProgressCreateParams,
+};
+use languageserver_types::Position;
+use lark_actor::{LspResponse, QueryRequest};
+use std::sync::mpsc::channel;
+use url::Url;
+
+#[test]
+fn reopen_with_same_contents() {
+    let mut open_files = OpenFiles::default();
+    let url = Url::parse("file:///test.lark").unwrap();
+
+    assert!(open_files.open(&url, "def main() {}"));
+    assert!(!open_files.open(&url, "def main() {}"));
+    assert!(open_files.open(&url, "def main() { debug(true) }"));
+
+    // After an edit, we no longer know the file's text, so reopening
+    // it must be forwarded again.
+    open_files.edited(&url);
+    assert!(open_files.open(&url, "def main() { debug(true) }"));
+}
+
+#[test]
+fn progress_needs_client_support() {
+    let mut progress = ProgressTracker::default();
+    assert_eq!(progress.progress(1, 1, 2), vec![]);
+    assert_eq!(progress.finished(1), None);
+}
+
+#[test]
+fn progress_begins_reports_and_ends() {
+    let mut progress = ProgressTracker::default();
+    progress.set_enabled(true);
+
+    assert_eq!(
+        progress.progress(1, 1, 4),
+        vec![
+            ProgressMessage::Create(WorkDoneProgressCreateParams { token: 1 }),
+            ProgressMessage::Notify(ProgressParams {
+                token: 1,
+                value: WorkDoneProgress::Begin {
+                    title: "lark".to_string(),
+                    message: "1/4".to_string(),
+                    percentage: 25,
+                },
+            }),
+        ]
+    );
+    assert_eq!(
+        progress.progress(1, 2, 4),
+        vec![ProgressMessage::Notify(ProgressParams {
+            token: 1,
+            value: WorkDoneProgress::Report {
+                message: "2/4".to_string(),
+                percentage: 50,
+            },
+        })]
+    );
+    assert_eq!(
+        progress.finished(1),
+        Some(ProgressMessage::Notify(ProgressParams {
+            token: 1,
+            value: WorkDoneProgress::End {},
+        }))
+    );
+
+    // A task that never reported progress has none to end.
+    assert_eq!(progress.finished(2), None);
+}
+
+#[test]
+fn progress_kind_is_serialized() {
+    let end = serde_json::to_value(WorkDoneProgress::End {}).unwrap();
+    assert_eq!(end, serde_json::json!({ "kind": "end" }));
+}
+
+#[test]
+fn work_done_progress_capability() {
+    let with = r#"{"method":"initialize","id":0,
+                   "params":{"capabilities":{"window":{"workDoneProgress":true}}}}"#;
+    let without = r#"{"method":"initialize","id":0,"params":{"capabilities":{}}}"#;
+    assert!(supports_work_done_progress(with));
+    assert!(!supports_work_done_progress(without));
+}
+
+#[test]
+fn undeliverable_request_is_answered_with_error() {
+    let (sender, receiver) = channel();
+    let url = Url::parse("file:///test.lark").unwrap();
+
+    let hover = QueryRequest::Hover(22, url.clone(), Position::new(0, 0));
+    assert!(try_forward_to_query_system(&sender, hover).is_none());
+    assert_eq!(receiver.recv().unwrap().task_id(), Some(22));
+
+    // Once the query system has shut down, the request is answered
+    // with an error instead (if it expects an answer at all).
+    drop(receiver);
+    let hover = QueryRequest::Hover(23, url.clone(), Position::new(0, 0));
+    match try_forward_to_query_system(&sender, hover) {
+        Some(LspResponse::Error(23, message)) => {
+            assert_eq!(message, "query system is not running")
+        }
+        _ => panic!("expected an error for task 23"),
+    }
+
+    let open = QueryRequest::OpenFile(url, String::new());
+    assert!(try_forward_to_query_system(&sender, open).is_none());
+}
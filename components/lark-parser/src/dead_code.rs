@@ -0,0 +1,152 @@
+use crate::ParserDatabase;
+use lark_collections::{FxIndexSet, Seq};
+use lark_entity::{Entity, EntityData, ItemKind, MemberKind};
+use lark_error::Diagnostic;
+use lark_intern::{Intern, Untern};
+use lark_span::FileName;
+use lark_ty::declaration::Declaration;
+use lark_ty::{BaseKind, BoundVarOr, Ty};
+
+/// Returns the top-level entities (fns and structs) of the given file
+/// that cannot be reached from any of `roots`, in source order. An
+/// entity reaches whatever its fn body refers to, the types in its
+/// signature, and (for a struct) its fields and methods.
+crate fn unused_entities(
+    db: &impl ParserDatabase,
+    id: FileName,
+    roots: Seq<Entity>,
+) -> Seq<Entity> {
+    let mut reached = walk(roots.iter().cloned(), |entity| direct_uses(db, entity));
+    reached.extend(roots.iter().cloned());
+
+    let file_entity = EntityData::InputFile { file: id }.intern(db);
+    db.child_entities(file_entity)
+        .iter()
+        .filter(|entity| !reached.contains(entity))
+        .cloned()
+        .collect()
+}
+
+/// If `check_dead_code` is enabled, warns about each top-level entity
+/// of the given file that `main` does not reach. A file without a
+/// `main` is treated as a library, and so gets no warnings.
+crate fn dead_code_warnings(db: &impl ParserDatabase, id: FileName) -> Seq<Diagnostic> {
+    if !db.check_dead_code() {
+        return Seq::default();
+    }
+
+    let main_name = "main".intern(db);
+    let file_entity = EntityData::InputFile { file: id }.intern(db);
+    let roots: Seq<Entity> = db
+        .child_entities(file_entity)
+        .iter()
+        .filter(|entity| match entity.untern(db) {
+            EntityData::ItemName {
+                kind: ItemKind::Function,
+                id,
+                ..
+            } => id == main_name,
+            _ => false,
+        })
+        .cloned()
+        .collect();
+    if roots.is_empty() {
+        return Seq::default();
+    }
+
+    db.unused_entities(id, roots)
+        .iter()
+        .filter_map(|&entity| {
+            let (kind, name) = match entity.untern(db) {
+                EntityData::ItemName {
+                    kind: ItemKind::Struct,
+                    id,
+                    ..
+                } => ("struct", id),
+                EntityData::ItemName {
+                    kind: ItemKind::Function,
+                    id,
+                    ..
+                } => ("function", id),
+                _ => return None,
+            };
+            let label = format!("{} `{}` is never used", kind, name.untern(db));
+            Some(Diagnostic::warning(
+                label,
+                db.characteristic_entity_span(entity),
+            ))
+        })
+        .collect()
+}
+
+/// Walks the graph whose edges are given by `successors`, starting
+/// from `start`, and returns every entity reached along the way in
+/// the order they were first reached (breadth-first). The starting
+/// entities are only included if they are reachable from one
+/// another. Each entity is visited once, so cycles terminate.
+crate fn walk(
+    start: impl IntoIterator<Item = Entity>,
+    mut successors: impl FnMut(Entity) -> Vec<Entity>,
+) -> FxIndexSet<Entity> {
+    let mut reached = FxIndexSet::default();
+    let mut queue: Vec<Entity> = start.into_iter().collect();
+    let mut next = 0;
+    while let Some(&current) = queue.get(next) {
+        next += 1;
+        for successor in successors(current) {
+            if reached.insert(successor) {
+                queue.push(successor);
+            }
+        }
+    }
+    reached
+}
+
+/// The entities that `entity` refers to directly.
+fn direct_uses(db: &impl ParserDatabase, entity: Entity) -> Vec<Entity> {
+    let mut uses = vec![];
+    let entity_data = entity.untern(db);
+
+    if entity_data.has_fn_body() {
+        uses.extend(db.referenced_entities(entity).iter().cloned());
+        if let Ok(signature) = db.signature(entity).into_value() {
+            for &ty in signature.inputs.iter().chain(Some(&signature.output)) {
+                named_types(db, ty, &mut uses);
+            }
+        }
+    }
+
+    match entity_data {
+        EntityData::ItemName {
+            kind: ItemKind::Struct,
+            ..
+        } => {
+            if let Ok(members) = db.members(entity) {
+                uses.extend(members.iter().map(|member| member.entity));
+            }
+        }
+
+        EntityData::MemberName {
+            kind: MemberKind::Field,
+            ..
+        } => named_types(db, db.ty(entity).into_value(), &mut uses),
+
+        _ => {}
+    }
+
+    uses
+}
+
+/// Pushes each struct (or other named type) that appears in `ty`,
+/// including in its generic arguments, onto `output`.
+fn named_types(db: &impl ParserDatabase, ty: Ty<Declaration>, output: &mut Vec<Entity>) {
+    if let BoundVarOr::Known(base_data) = ty.base.untern(db) {
+        if let BaseKind::Named(entity) = base_data.kind {
+            output.push(entity);
+        }
+
+        for generic in base_data.generics.iter() {
+            named_types(db, generic.assert_ty(), output);
+        }
+    }
+}
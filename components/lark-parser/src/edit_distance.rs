@@ -0,0 +1,56 @@
+/// Computes the edit distance between `a` and `b` -- that is, the
+/// number of single-character insertions, deletions, substitutions,
+/// or swaps of two adjacent characters needed to turn one into the
+/// other (the "optimal string alignment" distance). Counting a swap
+/// as one edit means that a typo like `dfe` is close to `def`.
+crate fn edit_distance(a: &str, b: &str) -> usize {
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+
+    // `row[j]` is the distance between the prefix of `a` processed so
+    // far and the first `j` characters of `b`; `previous_row` is the
+    // same for the prefix one character shorter.
+    let mut previous_row: Vec<usize> = vec![0; b_chars.len() + 1];
+    let mut row: Vec<usize> = (0..=b_chars.len()).collect();
+
+    for (i, &a_char) in a_chars.iter().enumerate() {
+        let mut next_row = vec![i + 1; b_chars.len() + 1];
+        for (j, &b_char) in b_chars.iter().enumerate() {
+            let substitution = row[j] + if a_char == b_char { 0 } else { 1 };
+            let mut distance = substitution.min(row[j + 1] + 1).min(next_row[j] + 1);
+            if i > 0 && j > 0 && a_char == b_chars[j - 1] && a_chars[i - 1] == b_char {
+                distance = distance.min(previous_row[j - 1] + 1);
+            }
+            next_row[j + 1] = distance;
+        }
+        previous_row = std::mem::replace(&mut row, next_row);
+    }
+
+    row[b_chars.len()]
+}
+
+/// Finds the candidate closest to `name` (ignoring case), provided it
+/// is within `max_distance` edits. Ties are resolved in favor of the
+/// candidate that appears first.
+crate fn closest_match<'c>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'c str>,
+    max_distance: usize,
+) -> Option<&'c str> {
+    let name = name.to_lowercase();
+    let mut best: Option<(usize, &'c str)> = None;
+
+    for candidate in candidates {
+        let distance = edit_distance(&name, &candidate.to_lowercase());
+        if distance > max_distance {
+            continue;
+        }
+
+        match best {
+            Some((best_distance, _)) if best_distance <= distance => {}
+            _ => best = Some((distance, candidate)),
+        }
+    }
+
+    best.map(|(_, candidate)| candidate)
+}
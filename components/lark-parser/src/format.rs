@@ -0,0 +1,197 @@
+use crate::lexer::token::LexToken;
+use crate::ParserDatabase;
+use lark_collections::Seq;
+use lark_error::{Diagnostic, WithError};
+use lark_span::{FileName, Span};
+
+/// Number of spaces per indentation level.
+const INDENT: &str = "  ";
+
+/// Re-emits the text of the given file with each line indented
+/// according to its brace depth (see `indentation_levels`) and with
+/// single spaces between tokens, except where the punctuation calls
+/// for none (e.g., `foo(a, b)`). Comments are preserved, as are
+/// tokens that span multiple lines (which are emitted verbatim).
+///
+/// If the file has syntax errors, it is returned unchanged along
+/// with those errors.
+crate fn format_file(db: &impl ParserDatabase, id: FileName) -> WithError<String> {
+    let text = db.file_text(id);
+    let WithError {
+        value: tokens,
+        errors: mut token_errors,
+    } = db.file_tokens(id);
+    let parse_errors = db.parsed_file(id).errors;
+    if !token_errors.is_empty() || !parse_errors.is_empty() {
+        token_errors.extend(parse_errors);
+        return WithError {
+            value: text.to_string(),
+            errors: token_errors,
+        };
+    }
+
+    let line_offsets = db.line_offsets(id);
+    let indentation_levels = db.indentation_levels(id);
+    let mut tokens = tokens.iter().peekable();
+    let mut covered_until = 0;
+    let mut output = String::new();
+
+    // The indentation level and text of the line being built.
+    let mut current_line: Option<(u32, String)> = None;
+    let mut previous: Option<(LexToken, &str)> = None;
+
+    for &(line, level) in indentation_levels.iter() {
+        // A line that starts within a multi-line token (e.g., a
+        // block comment) continues the line where that token began.
+        let line_start = line_offsets[line];
+        if line_start >= covered_until {
+            if let Some((level, line_text)) = current_line.take() {
+                push_line(&mut output, level, &line_text);
+            }
+            current_line = Some((level, String::new()));
+            previous = None;
+        }
+        let line_text = &mut current_line.as_mut().unwrap().1;
+
+        let line_end = line_offsets[line + 1];
+        while let Some(&token) = tokens.peek() {
+            if token.span.start().to_usize() >= line_end {
+                break;
+            }
+            tokens.next();
+            covered_until = token.span.end().to_usize();
+
+            let token_text = match token.value {
+                LexToken::Whitespace | LexToken::Newline => continue,
+
+                // End-of-line comments include their newline.
+                LexToken::Comment | LexToken::DocComment => text[token.span].trim_end(),
+
+                _ => &text[token.span],
+            };
+
+            if let Some(previous) = previous {
+                if space_between(previous, (token.value, token_text)) {
+                    line_text.push(' ');
+                }
+            }
+            line_text.push_str(token_text);
+            previous = Some((token.value, token_text));
+        }
+    }
+
+    if let Some((level, line_text)) = current_line {
+        push_line(&mut output, level, &line_text);
+    }
+
+    // End the file with exactly one newline (or nothing, if empty).
+    output.truncate(output.trim_end().len());
+    if !output.is_empty() {
+        output.push('\n');
+    }
+
+    WithError::ok(output)
+}
+
+fn push_line(output: &mut String, level: u32, line_text: &str) {
+    if !line_text.is_empty() {
+        for _ in 0..level {
+            output.push_str(INDENT);
+        }
+        output.push_str(line_text);
+    }
+    output.push('\n');
+}
+
+/// True if a space belongs between the adjacent tokens `previous`
+/// and `next` on a line.
+fn space_between(previous: (LexToken, &str), next: (LexToken, &str)) -> bool {
+    let (previous_kind, previous_text) = previous;
+    let (next_kind, next_text) = next;
+
+    match next_kind {
+        LexToken::Comment | LexToken::DocComment => return true,
+        _ => {}
+    }
+
+    match (previous_text, next_text) {
+        ("(", _) | ("[", _) | (".", _) | ("@", _) => false,
+        (_, ")") | (_, "]") | (_, ",") | (_, ":") | (_, ".") => false,
+        ("{", "}") => false,
+
+        // Calls (`foo(..)`) and generics (`Foo[..]`).
+        (_, "(") | (_, "[") => match previous_kind {
+            LexToken::Identifier => is_keyword(previous_text),
+            _ => previous_text != ")" && previous_text != "]",
+        },
+
+        _ => true,
+    }
+}
+
+/// Identifiers that are followed by a space even before an open
+/// paren or square bracket, since they introduce syntax rather than
+/// naming something being called or instantiated.
+fn is_keyword(text: &str) -> bool {
+    match text {
+        "if" | "else" | "let" | "def" | "struct" => true,
+        _ => false,
+    }
+}
+
+/// Checks the leading whitespace of each line against the depth
+/// expected by `indentation_levels`: each level is either `INDENT` or
+/// a single tab. Blank lines, and lines that continue a multi-line
+/// token, are not checked.
+crate fn indentation_warnings(db: &impl ParserDatabase, id: FileName) -> Seq<Diagnostic> {
+    if !db.check_indentation() {
+        return Seq::default();
+    }
+
+    let text: &str = &db.file_text(id);
+    let tokens = db.file_tokens(id).into_value();
+    let line_offsets = db.line_offsets(id);
+    let mut tokens = tokens.iter().peekable();
+    let mut covered_until = 0;
+    let mut warnings = vec![];
+
+    for &(line, level) in db.indentation_levels(id).iter() {
+        let line_start = line_offsets[line];
+        let line_end = line_offsets[line + 1];
+        let continues_token = line_start < covered_until;
+        while let Some(&token) = tokens.peek() {
+            if token.span.start().to_usize() >= line_end {
+                break;
+            }
+            tokens.next();
+            covered_until = token.span.end().to_usize();
+        }
+        if continues_token {
+            continue;
+        }
+
+        let line_text = text[line_start..line_end].trim_end_matches(|c| c == '\n' || c == '\r');
+        let content = line_text.trim_start_matches(|c| c == ' ' || c == '\t');
+        if content.is_empty() {
+            continue;
+        }
+
+        let leading = &line_text[..line_text.len() - content.len()];
+        let span = Span::new(id, line_start, line_start + line_text.len());
+        if leading.contains(' ') && leading.contains('\t') {
+            warnings.push(Diagnostic::warning(
+                "indentation mixes tabs and spaces".to_string(),
+                span,
+            ));
+        } else if leading != INDENT.repeat(level as usize)
+            && leading != "\t".repeat(level as usize)
+        {
+            warnings.push(Diagnostic::warning(
+                format!("expected indentation depth of {}", level),
+                span,
+            ));
+        }
+    }
+
+    Seq::from(warnings)
+}
@@ -19,6 +19,7 @@ crate enum LexerState {
     Number,
     Comment(u32),
     EolComment,
+    DocComment,
 }
 
 impl LexerDelegateTrait for LexerState {
@@ -36,7 +37,7 @@ impl LexerDelegateTrait for LexerState {
                 None => LexerNext::EOF,
                 Some(c) => match c {
                     '/' => LexerNext::begin(Slash),
-                    c if UnicodeXID::is_xid_start(c) => LexerNext::begin(StartIdent),
+                    c if UnicodeXID::is_xid_start(c) || c == '_' => LexerNext::begin(StartIdent),
                     c if is_delimiter_sigil_char(c) => {
                         consume(c).and_emit(LexToken::Sigil).and_remain()
                     }
@@ -48,16 +49,25 @@ impl LexerDelegateTrait for LexerState {
                     '"' => consume(c).and_transition(StringLiteral),
                     '\n' => LexerNext::sigil(LexToken::Newline),
                     c if c.is_whitespace() => LexerNext::begin(Whitespace),
-                    _ if rest.starts_with("/*") => consume_str("/*").and_push(Comment(1)),
                     _ => consume(c).and_emit(LexToken::Error).and_remain(),
                 },
             },
 
+            // We have not yet consumed the `/` itself, so `rest` starts
+            // with it.
             LexerState::Slash => match c {
                 None => reconsume()
                     .and_emit(LexToken::Sigil)
                     .and_transition(LexerState::Top),
-                Some('/') => reconsume().and_transition(LexerState::EolComment),
+                Some('/') if rest.starts_with("///") && !rest.starts_with("////") => {
+                    reconsume().and_transition(LexerState::DocComment)
+                }
+                Some('/') if rest.starts_with("//") => {
+                    reconsume().and_transition(LexerState::EolComment)
+                }
+                Some('/') if rest.starts_with("/*") => {
+                    consume_str("/*").and_transition(LexerState::Comment(1))
+                }
                 Some(c) => consume(c).and_transition(LexerState::Sigil),
             },
 
@@ -133,6 +143,10 @@ impl LexerDelegateTrait for LexerState {
                 },
             },
 
+            // Inside a block comment, nested `n` deep. Everything but
+            // `/*` and `*/` (including quotes and braces) is ignored.
+            // An unterminated comment becomes a single `Error` token
+            // starting at the outermost `/*`.
             LexerState::Comment(1) => {
                 if rest.starts_with("/*") {
                     consume_str("/*")
@@ -166,7 +180,7 @@ impl LexerDelegateTrait for LexerState {
                         Some(c) => consume(c).and_remain(),
                         None => reconsume()
                             .and_emit(LexToken::Error)
-                            .and_transition(LexerState::Comment(n - 1)),
+                            .and_transition(LexerState::Top),
                     }
                 }
             }
@@ -180,6 +194,16 @@ impl LexerDelegateTrait for LexerState {
                     .and_transition(LexerState::Top),
                 Some(c) => consume(c).and_remain(),
             },
+
+            LexerState::DocComment => match c {
+                None => reconsume()
+                    .and_emit(LexToken::DocComment)
+                    .and_transition(LexerState::Top),
+                Some('\n') => consume('\n')
+                    .and_emit(LexToken::DocComment)
+                    .and_transition(LexerState::Top),
+                Some(c) => consume(c).and_remain(),
+            },
         };
 
         out
@@ -195,7 +219,7 @@ fn is_sigil_char(c: char) -> bool {
 
 fn is_delimiter_sigil_char(c: char) -> bool {
     match c {
-        '{' | '}' | '(' | ')' => true,
+        '{' | '}' | '(' | ')' | '[' | ']' | '@' => true,
         _ => false,
     }
 }
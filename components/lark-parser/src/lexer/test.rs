@@ -75,3 +75,23 @@ fn test_quicklex() -> Result<(), Span<CurrentFile>> {
 
     Ok(())
 }
+
+#[test]
+fn test_block_comments() -> Result<(), Span<CurrentFile>> {
+    let source = unindent(
+        r##"
+            /* "unterminated */ def f(){}
+            00000000000000000001222345678 Comment Whitespace Identifier Whitespace Identifier Sigil Sigil Sigil Sigil
+            /* { /* } */ " */ x
+            0000000000000000012 Comment Whitespace Identifier
+            a / b
+            01234 Identifier Whitespace Sigil Whitespace Identifier
+            /* a /* b */
+            000000000000 Error
+            "##,
+    );
+
+    process(&source)?;
+
+    Ok(())
+}
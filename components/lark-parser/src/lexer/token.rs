@@ -4,13 +4,14 @@ use lark_error::ErrorSentinel;
 
 /// The different kinds of token our lexer can distinguish. Note that
 /// you can recover the full text of the token using its span.
-#[derive(Copy, Clone, Debug, DebugWith, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, DebugWith, PartialEq, Eq, Hash)]
 pub enum LexToken {
     Whitespace,
     Identifier,
     Integer,
     Sigil,
     Comment,
+    DocComment,
     String,
     Newline,
     EOF,
@@ -5,9 +5,10 @@
 #![feature(try_blocks)]
 #![allow(dead_code)]
 
-use crate::lexer::token::LexToken;
 use crate::macros::EntityMacroDefinition;
-use crate::syntax::entity::ParsedEntity;
+use crate::parser::Parser;
+use crate::syntax::entity::{EntitySyntax, ParsedEntity};
+use crate::syntax::skip_newline::SkipNewline;
 use lark_collections::{FxIndexMap, Seq};
 use lark_debug_derive::DebugWith;
 use lark_entity::Entity;
@@ -34,16 +35,29 @@ use lark_ty::declaration::DeclarationTables;
 use std::sync::Arc;
 
 pub mod current_file;
+mod dead_code;
+mod edit_distance;
+mod format;
 mod ir;
 mod lexer;
 pub mod macros;
+mod naming;
 mod parser;
 mod query_definitions;
 mod scope;
 pub mod syntax;
+mod token_diff;
 mod type_conversion;
+mod well_known;
 
 pub use self::ir::ParsedFile;
+pub use self::lexer::token::LexToken;
+pub use self::syntax::attribute::EntityAnnotation;
+pub use self::token_diff::TokenDiff;
+pub use self::well_known::WellKnownIdentifiers;
+
+/// The default value for the `expression_depth_limit` input.
+pub const DEFAULT_EXPRESSION_DEPTH_LIMIT: usize = 128;
 
 #[salsa::query_group(ParserStorage)]
 pub trait ParserDatabase:
@@ -52,19 +66,137 @@ pub trait ParserDatabase:
     #[salsa::input]
     fn file_names(&self) -> Seq<FileName>;
 
+    /// The names that are considered "active" for `@cfg(name)`
+    /// attributes. Entities whose cfg name is not in this list are
+    /// excluded from their parent's children, and errors within them
+    /// are not reported.
+    #[salsa::input]
+    fn active_cfg_names(&self) -> Seq<GlobalIdentifier>;
+
+    /// How deeply expressions may be nested in a fn body before we
+    /// give up with a "nesting too deep" error (rather than risk
+    /// overflowing the stack). Defaults to
+    /// `DEFAULT_EXPRESSION_DEPTH_LIMIT`.
+    #[salsa::input]
+    fn expression_depth_limit(&self) -> usize;
+
+    /// When true, `indentation_warnings` reports lines whose
+    /// indentation does not match their brace depth. Off by default.
+    #[salsa::input]
+    fn check_indentation(&self) -> bool;
+
+    /// When true, `naming_warnings` reports names that do not follow
+    /// the usual casing conventions. Off by default.
     #[salsa::input]
+    fn check_naming(&self) -> bool;
+
+    /// When true, `dead_code_warnings` reports top-level items that
+    /// `main` never reaches. Off by default.
+    #[salsa::input]
+    fn check_dead_code(&self) -> bool;
+
+    /// When true, `file_text` converts each `\r\n` or lone `\r` to
+    /// `\n`. Every line ending stays a single line ending, so lines
+    /// and columns are the same as in the original text (only byte
+    /// offsets shift), and positions reported to the LSP still match
+    /// the editor's copy of the file. Off by default.
+    #[salsa::input]
+    fn normalize_newlines(&self) -> bool;
+
+    /// When true, identifiers may only contain ASCII letters, digits,
+    /// and underscores; `file_tokens` reports any others. Otherwise,
+    /// any Unicode identifier (per UAX #31) is accepted. Off by
+    /// default.
+    #[salsa::input]
+    fn ascii_identifiers(&self) -> bool;
+
+    /// The contents of the given file as found on disk (or otherwise
+    /// supplied by whoever added the file). Most code should use
+    /// `file_text` instead, which takes the overlay into account.
+    #[salsa::input]
+    fn file_disk_text(&self, id: FileName) -> Text;
+
+    /// In-memory contents for the given file (e.g., the unsaved
+    /// buffer from an editor), if any. When present, this takes
+    /// precedence over `file_disk_text`. Setting the overlay back to
+    /// `None` falls back to the contents on disk.
+    #[salsa::input]
+    fn file_overlay(&self, id: FileName) -> Option<Text>;
+
+    /// The authoritative contents of the given file: the overlay, if
+    /// one is set, and otherwise the contents from disk. See also
+    /// `normalize_newlines`.
+    #[salsa::invoke(query_definitions::file_text)]
     fn file_text(&self, id: FileName) -> Text;
 
+    /// The length of the given file's text, in bytes.
+    #[salsa::invoke(query_definitions::file_len)]
+    fn file_len(&self, id: FileName) -> usize;
+
+    /// Interned ids for keywords and builtin names.
+    #[salsa::invoke(query_definitions::well_known_identifiers)]
+    fn well_known_identifiers(&self) -> WellKnownIdentifiers;
+
+    /// Returns the file in which `entity` is declared, walking up
+    /// through any enclosing items. Returns `None` for lang items and
+    /// errors, which are not declared in any file.
+    #[salsa::invoke(query_definitions::declaring_file)]
+    fn declaring_file(&self, entity: Entity) -> Option<FileName>;
+
+    /// Returns the fully-qualified name of `entity`, with the names
+    /// of its enclosing items joined by `::` (e.g.,
+    /// `file::Point::x`). If `include_file` is false, the leading
+    /// input-file segment is omitted (e.g., `Point::x`). Lang items
+    /// render as their builtin name and errors as `<error>`.
+    #[salsa::invoke(query_definitions::qualified_name)]
+    fn qualified_name(&self, entity: Entity, include_file: bool) -> String;
+
     #[salsa::invoke(query_definitions::entity_span)]
     fn entity_span(&self, entity: Entity) -> Span<FileName>;
 
+    /// Returns the indentation (in characters) of the line on which
+    /// `entity` starts, so that edits inserting code next to it can
+    /// match. Returns 0 for entities that are not declared in a file.
+    #[salsa::invoke(query_definitions::entity_indent)]
+    fn entity_indent(&self, entity: Entity) -> u32;
+
+    /// Returns each top-level entity in `file` along with a span
+    /// that extends its `entity_span` back to the end of the previous
+    /// entity (so as to include its doc comments and the whitespace
+    /// before it). The last entity's span also extends to the end of
+    /// the file, so the spans partition the file without overlapping.
+    #[salsa::invoke(query_definitions::entity_full_spans)]
+    fn entity_full_spans(&self, file: FileName) -> Seq<(Entity, Span<FileName>)>;
+
     #[salsa::invoke(query_definitions::characteristic_entity_span)]
     fn characteristic_entity_span(&self, entity: Entity) -> Span<FileName>;
 
+    /// Returns the span of the keyword (e.g., `def`) that introduces
+    /// `entity`, or `None` for entities that have no keyword (such as
+    /// fields).
+    #[salsa::invoke(query_definitions::entity_keyword_span)]
+    fn entity_keyword_span(&self, entity: Entity) -> Option<Span<FileName>>;
+
+    /// Returns the `///` doc comments attached to `entity` (with the
+    /// `///` markers stripped), if any.
+    #[salsa::invoke(query_definitions::entity_docs)]
+    fn entity_docs(&self, entity: Entity) -> Option<String>;
+
+    /// Returns the message from `entity`'s `@deprecated("message")`
+    /// attribute, if it has one.
+    #[salsa::invoke(query_definitions::entity_deprecation)]
+    fn entity_deprecation(&self, entity: Entity) -> Option<Text>;
+
+    /// Returns the attributes on `entity` that the compiler does not
+    /// interpret itself (e.g., `@custom(1)`), for use by other tools.
+    #[salsa::invoke(query_definitions::entity_annotations)]
+    fn entity_annotations(&self, entity: Entity) -> Seq<EntityAnnotation>;
+
     /// Returns, for each line in the given file, the start index
     /// -- the final element is the length of the file (there is
     /// kind of a "pseudo-empty line" at the end, so to speak). So
-    /// for the input "a\nb\r\nc" you would get `[0, 2, 5, 6]`.
+    /// for the input "a\nb\r\nc" you would get `[0, 2, 5, 6]`, and for
+    /// an empty file just `[0]`.
     #[salsa::invoke(query_definitions::line_offsets)]
     fn line_offsets(&self, id: FileName) -> Seq<usize>;
 
@@ -76,6 +208,58 @@ pub trait ParserDatabase:
     #[salsa::invoke(query_definitions::byte_index)]
     fn byte_index(&self, id: FileName, line: u64, column: u64) -> ByteIndex;
 
+    /// Returns the last token in the given file that ends at or before
+    /// `index`. If `skip_trivia` is true, whitespace, newlines, and
+    /// comments are ignored.
+    #[salsa::invoke(query_definitions::token_before)]
+    fn token_before(
+        &self,
+        id: FileName,
+        index: ByteIndex,
+        skip_trivia: bool,
+    ) -> Option<Spanned<LexToken, FileName>>;
+
+    /// Returns the token in the given file whose span contains
+    /// `index`. If there is none -- typically because `index` is at
+    /// the end of the file -- returns an empty `EOF` token at the
+    /// file's end, so that e.g. completions at the very end of a file
+    /// have something to anchor to.
+    #[salsa::invoke(query_definitions::token_at)]
+    fn token_at(&self, id: FileName, index: ByteIndex) -> Spanned<LexToken, FileName>;
+
+    /// Returns the span of the identifier token containing `index`,
+    /// if any, so that edits like renames touch exactly that
+    /// identifier. Keywords, sigils, and other tokens give `None`.
+    #[salsa::invoke(query_definitions::identifier_span_at)]
+    fn identifier_span_at(&self, id: FileName, index: ByteIndex) -> Option<Span<FileName>>;
+
+    /// Returns, for each line in the given file, the pair of the
+    /// (zero-based) line number and its expected indentation depth,
+    /// based on the nesting of curly braces. A line that begins with
+    /// a closing brace is at the depth of the matching open brace.
+    #[salsa::invoke(query_definitions::indentation_levels)]
+    fn indentation_levels(&self, id: FileName) -> Seq<(usize, u32)>;
+
+    /// Returns the text of the given file, reformatted with
+    /// canonical indentation and spacing. If the file has syntax
+    /// errors, its text is returned unchanged.
+    #[salsa::invoke(format::format_file)]
+    fn format_file(&self, id: FileName) -> WithError<String>;
+
+    /// If `check_indentation` is enabled, returns a warning for each
+    /// line whose leading whitespace does not match its depth in
+    /// `indentation_levels`, or that mixes tabs and spaces.
+    /// Otherwise, returns nothing.
+    #[salsa::invoke(format::indentation_warnings)]
+    fn indentation_warnings(&self, id: FileName) -> Seq<Diagnostic>;
+
+    /// If `check_naming` is enabled, returns a warning for each name
+    /// declared in the given file (including variables) that is not in
+    /// `UpperCamelCase` (for structs) or `snake_case` (for everything
+    /// else). Otherwise, returns nothing.
+    #[salsa::invoke(naming::naming_warnings)]
+    fn naming_warnings(&self, id: FileName) -> Seq<Diagnostic>;
+
     // FIXME: In general, this is wasteful of space, and not
     // esp. incremental friendly. It would be better store
     // e.g. the length of each token only, so that we can adjust
@@ -84,6 +268,39 @@ pub trait ParserDatabase:
     #[salsa::invoke(query_definitions::file_tokens)]
     fn file_tokens(&self, id: FileName) -> WithError<Seq<Spanned<LexToken, FileName>>>;
 
+    /// The text of the given file as of `revision`, as recorded by
+    /// `ParserDatabaseExt::record_file_revision`.
+    #[salsa::input]
+    fn file_text_at_revision(&self, id: FileName, revision: u64) -> Text;
+
+    /// Like `file_tokens`, but lexes the text recorded for `revision`.
+    #[salsa::invoke(query_definitions::file_tokens_at_revision)]
+    fn file_tokens_at_revision(
+        &self,
+        id: FileName,
+        revision: u64,
+    ) -> WithError<Seq<Spanned<LexToken, FileName>>>;
+
+    /// Returns the tokens that changed between two recorded revisions
+    /// of the given file (see `TokenDiff`), e.g., so that an editor
+    /// only needs to re-highlight those tokens.
+    #[salsa::invoke(token_diff::token_diff)]
+    fn token_diff(&self, id: FileName, old_revision: u64, new_revision: u64) -> TokenDiff;
+
+    /// Returns the range `start..end` of indices into the
+    /// `file_tokens` of `entity`'s file that make up its
+    /// `entity_span`. Returns `None` for entities that are not
+    /// declared in a file.
+    #[salsa::invoke(query_definitions::entity_token_range)]
+    fn entity_token_range(&self, entity: Entity) -> Option<(usize, usize)>;
+
+    /// Returns how many tokens of each kind the given file contains,
+    /// in order of first appearance. Whitespace, newlines, and
+    /// comments are counted under their own kinds; keywords are
+    /// identifiers.
+    #[salsa::invoke(query_definitions::token_histogram)]
+    fn token_histogram(&self, id: FileName) -> FxIndexMap<LexToken, usize>;
+
     #[salsa::invoke(query_definitions::parsed_file)]
     fn parsed_file(&self, id: FileName) -> WithError<ParsedFile>;
 
@@ -97,6 +314,11 @@ pub trait ParserDatabase:
     #[salsa::invoke(query_definitions::child_entities)]
     fn child_entities(&self, entity: Entity) -> Seq<Entity>;
 
+    /// Like `child_entities`, but pairs each child with its
+    /// `entity_span`, sorted in source order.
+    #[salsa::invoke(query_definitions::child_entities_spanned)]
+    fn child_entities_spanned(&self, entity: Entity) -> Seq<(Entity, Span<FileName>)>;
+
     /// Transitive closure of `child_entities`.
     #[salsa::invoke(query_definitions::descendant_entities)]
     fn descendant_entities(&self, entity: Entity) -> Seq<Entity>;
@@ -105,10 +327,114 @@ pub trait ParserDatabase:
     #[salsa::invoke(query_definitions::fn_body)]
     fn fn_body(&self, key: Entity) -> WithError<Arc<hir::FnBody>>;
 
+    /// Lowers the fn body of every entity in `file` up front,
+    /// returning the errors from all of them. `fn_body` is otherwise
+    /// only computed when something asks for it (e.g., the language
+    /// server hovering over one function); this is for tools that
+    /// want every body anyway.
+    #[salsa::invoke(query_definitions::lower_all_bodies)]
+    fn lower_all_bodies(&self, file: FileName) -> WithError<()>;
+
+    /// Returns the variables referenced within `expression` (a part
+    /// of the fn body of `entity`) that are defined outside of it.
+    #[salsa::invoke(query_definitions::free_variables)]
+    fn free_variables(&self, entity: Entity, expression: hir::Expression) -> Seq<hir::Variable>;
+
+    /// The maximum nesting depth of the expressions in the fn body of
+    /// `entity`, counting the root expression as depth 1. Meant for
+    /// complexity metrics.
+    #[salsa::invoke(query_definitions::expression_depth)]
+    fn expression_depth(&self, entity: Entity) -> usize;
+
+    /// If `entity` declares a (non-unit) return type, reports each
+    /// place where its fn body can finish without producing a value:
+    /// e.g., an `if` without an `else` in tail position, or a block
+    /// that ends in `;` or `let`.
+    #[salsa::invoke(query_definitions::missing_return_values)]
+    fn missing_return_values(&self, entity: Entity) -> Seq<Diagnostic>;
+
+    /// Maps each expression in the fn body of `entity` to the line
+    /// and column where it starts, for emitting debug info.
+    #[salsa::invoke(query_definitions::source_map)]
+    fn source_map(&self, entity: Entity) -> SourceMap;
+
+    /// Returns the entities referenced by the fn body of `entity`
+    /// (e.g., the functions it calls and the structs it constructs),
+    /// without duplicates.
+    #[salsa::invoke(query_definitions::referenced_entities)]
+    fn referenced_entities(&self, entity: Entity) -> Seq<Entity>;
+
+    /// Returns every entity reachable from `entity` by following
+    /// `referenced_entities` through fn bodies, in the order they are
+    /// first reached (breadth-first). `entity` itself is included only
+    /// if it is reachable from itself (e.g., if it is recursive).
+    #[salsa::invoke(query_definitions::transitive_dependencies)]
+    fn transitive_dependencies(&self, entity: Entity) -> Seq<Entity>;
+
+    /// Returns the top-level fns and structs in the given file that
+    /// are not reachable from any of `roots` (e.g., `main`), and so
+    /// could be removed. Unlike `transitive_dependencies`, this also
+    /// follows the types named in signatures and struct fields.
+    #[salsa::invoke(dead_code::unused_entities)]
+    fn unused_entities(&self, id: FileName, roots: Seq<Entity>) -> Seq<Entity>;
+
+    /// If `check_dead_code` is enabled and the given file has a
+    /// `main`, returns a warning for each entity in `unused_entities`
+    /// with `main` as the root. Otherwise, returns nothing.
+    #[salsa::invoke(dead_code::dead_code_warnings)]
+    fn dead_code_warnings(&self, id: FileName) -> Seq<Diagnostic>;
+
+    /// Returns the parameters of `entity` that are never referenced
+    /// in its fn body, along with their spans. Parameters whose names
+    /// start with an underscore, as well as `self`, are exempt.
+    #[salsa::invoke(query_definitions::unused_parameters)]
+    fn unused_parameters(&self, entity: Entity) -> Seq<(hir::Variable, Span<FileName>)>;
+
+    /// Returns the name and declaration span of every variable
+    /// (parameter or `let`) in the fn body of `entity`, regardless of
+    /// scope.
+    #[salsa::invoke(query_definitions::local_bindings)]
+    fn local_bindings(&self, entity: Entity) -> Seq<(GlobalIdentifier, Span<FileName>)>;
+
+    /// Returns the initializer of the `let` that introduced
+    /// `variable` in the fn body of `entity` (the `1 + 2` in `let x =
+    /// 1 + 2`). Returns `None` for parameters and for `let`s without
+    /// an initializer.
+    #[salsa::invoke(query_definitions::initializer_of)]
+    fn initializer_of(&self, entity: Entity, variable: hir::Variable) -> Option<hir::Expression>;
+
+    /// Returns the span of the `index`th argument of `call`, a call
+    /// or method call in the fn body of `entity`. For a method call,
+    /// argument 0 is the receiver (the `a` in `a.f(b)`). Returns
+    /// `None` if there is no such argument or `call` is not a call.
+    #[salsa::invoke(query_definitions::argument_span)]
+    fn argument_span(
+        &self,
+        entity: Entity,
+        call: hir::Expression,
+        index: usize,
+    ) -> Option<Span<FileName>>;
+
     /// Given a span, find the things that it may have been referring to.
     #[salsa::invoke(query_definitions::hover_targets)]
     fn hover_targets(&self, file: FileName, index: ByteIndex) -> Seq<HoverTarget>;
 
+    /// Returns the innermost entity with a fn body (e.g., a `def`)
+    /// whose span contains `index`, if any.
+    #[salsa::invoke(query_definitions::enclosing_fn)]
+    fn enclosing_fn(&self, file: FileName, index: ByteIndex) -> Option<Entity>;
+
+    /// Returns the entity just after (or before) `entity` among the
+    /// children of its parent, in source order; `None` at either end.
+    #[salsa::invoke(query_definitions::sibling_entity)]
+    fn sibling_entity(&self, entity: Entity, direction: Direction) -> Option<Entity>;
+
+    /// True if `ancestor` is the parent of `candidate`, or the
+    /// parent of its parent, and so forth. An entity is not its own
+    /// descendant.
+    #[salsa::invoke(query_definitions::is_descendant_of)]
+    fn is_descendant_of(&self, candidate: Entity, ancestor: Entity) -> bool;
+
     /// Get the list of member names and their def-ids for a given struct.
     #[salsa::invoke(query_definitions::members)]
     fn members(&self, key: Entity) -> Result<Seq<hir::Member>, ErrorReported>;
@@ -133,6 +459,12 @@ pub trait ParserDatabase:
         key: Entity,
     ) -> WithError<Result<ty::Signature<Declaration>, ErrorReported>>;
 
+    /// Get the declared type of the `index`th parameter of a
+    /// function (for methods, `self` is parameter 0). Returns `None`
+    /// if there is no such parameter or the signature has errors.
+    #[salsa::invoke(type_conversion::parameter_type)]
+    fn parameter_type(&self, key: Entity, index: usize) -> Option<ty::Ty<Declaration>>;
+
     /// Get the generic declarations from a particular item.
     #[salsa::invoke(type_conversion::generic_declarations)]
     fn generic_declarations(
@@ -145,6 +477,13 @@ pub trait ParserDatabase:
     fn resolve_name(&self, scope: Entity, name: GlobalIdentifier) -> Option<Entity>;
 }
 
+/// The start location of each expression in a fn body; see
+/// `source_map`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceMap {
+    pub expressions: FxIndexMap<hir::Expression, Location>,
+}
+
 #[derive(Clone, Debug, DebugWith, PartialEq, Eq)]
 pub struct HoverTarget {
     pub span: Span<FileName>,
@@ -157,9 +496,23 @@ pub enum HoverTargetKind {
     MetaIndex(Entity, hir::MetaIndex),
 }
 
+/// Which way to step in `sibling_entity`.
+#[derive(Copy, Clone, Debug, DebugWith, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Next,
+    Previous,
+}
+
 pub trait ParserDatabaseExt: ParserDatabase {
     fn init_parser_db(&mut self) {
         self.set_file_names(Default::default());
+        self.set_active_cfg_names(Default::default());
+        self.set_expression_depth_limit(DEFAULT_EXPRESSION_DEPTH_LIMIT);
+        self.set_check_indentation(false);
+        self.set_check_naming(false);
+        self.set_check_dead_code(false);
+        self.set_normalize_newlines(false);
+        self.set_ascii_identifiers(false);
     }
 
     fn add_file(&mut self, path: impl IntoFileName, contents: impl Into<Text>) {
@@ -169,7 +522,17 @@ pub trait ParserDatabaseExt: ParserDatabase {
         file_names.extend(Some(file_name));
 
         self.set_file_names(file_names);
-        self.set_file_text(file_name, contents.into());
+        self.set_file_disk_text(file_name, contents.into());
+        self.set_file_overlay(file_name, None);
+    }
+
+    /// Records the current text of the given file as `revision` (for
+    /// example, the version number an editor gave it), so that it can
+    /// later be compared with other revisions using `token_diff`.
+    fn record_file_revision(&mut self, path: impl IntoFileName, revision: u64) {
+        let file_name = path.into_file_name(&self);
+        let text = self.file_text(file_name);
+        self.set_file_text_at_revision(file_name, revision, text);
     }
 
     /// Returns the "top-level" entities defined in the given file --
@@ -181,6 +544,41 @@ pub trait ParserDatabaseExt: ParserDatabase {
     }
 }
 
+/// Parses the top-level entities of `file_name` from an already
+/// lexed token stream, rather than from the db's `file_tokens`. This
+/// is useful for tools that have tokens on hand already. The tokens
+/// are expected to match what the lexer would produce for the file
+/// text; if they extend past its end, an error is reported and no
+/// entities are returned. Returns the entities along with the length
+/// of the file text.
+pub fn parse_tokens(
+    db: &impl ParserDatabase,
+    file_name: FileName,
+    tokens: &[Spanned<LexToken, FileName>],
+) -> WithError<(Seq<ParsedEntity>, usize)> {
+    let input = &db.file_text(file_name);
+
+    if let Some(last_token) = tokens.last() {
+        if last_token.span.end().to_usize() > input.len() {
+            return WithError {
+                value: (Seq::default(), input.len()),
+                errors: vec![diagnostic(
+                    "token stream extends past the end of the file",
+                    Span::eof(file_name, input),
+                )],
+            };
+        }
+    }
+
+    let file_entity = EntityData::InputFile { file: file_name }.intern(db);
+    let entity_macro_definitions = macro_definitions(&db, file_entity);
+    let parser = Parser::new(file_name, db, &entity_macro_definitions, input, tokens, 0);
+    let entity_syntax = EntitySyntax::new(file_entity, db.active_cfg_names());
+    parser
+        .parse_until_eof(SkipNewline(entity_syntax))
+        .map(|entities| (entities, input.len()))
+}
+
 fn diagnostic(message: impl Into<String>, span: Span<FileName>) -> Diagnostic {
     Diagnostic::new(message.into(), span)
 }
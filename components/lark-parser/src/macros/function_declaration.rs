@@ -5,6 +5,7 @@ use crate::syntax::entity::ParsedEntity;
 use crate::syntax::entity::ParsedEntityThunk;
 use crate::syntax::fn_signature::FunctionSignature;
 use crate::syntax::fn_signature::ParsedFunctionSignature;
+use crate::syntax::generics::{self, GenericParameters};
 use crate::syntax::identifier::SpannedGlobalIdentifier;
 use crate::syntax::skip_newline::SkipNewline;
 use crate::ParserDatabase;
@@ -28,7 +29,8 @@ use lark_ty::GenericDeclarations;
 use std::sync::Arc;
 
 /// ```ignore
-/// `def` <id> `(` <id> `:` <ty> `)` [ `->` <ty> ] <block>
+/// `def` <id> [ `[` <id> `]` ] `(` <id> `:` <ty> `)` [ `->` <ty> ]
+///     [ `where` <id> `:` <id> ] <block>
 /// ```
 #[derive(Default)]
 pub struct FunctionDeclaration;
@@ -48,6 +50,11 @@ impl EntityMacroDefinition for FunctionDeclaration {
 
         let function_name = parser.expect(SkipNewline(SpannedGlobalIdentifier))?;
 
+        let generic_parameters = match parser.parse_if_present(GenericParameters) {
+            Some(parameters) => parameters.unwrap_or_else(|ErrorReported(_)| Seq::default()),
+            None => Seq::default(),
+        };
+
         let signature = parser.expect(FunctionSignature)?;
 
         let entity = EntityData::ItemName {
@@ -64,13 +71,17 @@ impl EntityMacroDefinition for FunctionDeclaration {
             entity,
             full_span,
             characteristic_span,
-            ParsedEntityThunk::new(ParsedFunctionDeclaration { signature }),
+            ParsedEntityThunk::new(ParsedFunctionDeclaration {
+                generic_parameters,
+                signature,
+            }),
         ))
     }
 }
 
 #[derive(Clone, DebugWith)]
 pub struct ParsedFunctionDeclaration {
+    pub generic_parameters: Seq<Spanned<GlobalIdentifier, FileName>>,
     pub signature: ParsedFunctionSignature,
 }
 
@@ -85,11 +96,15 @@ impl LazyParsedEntity for ParsedFunctionDeclaration {
 
     fn parse_generic_declarations(
         &self,
-        _entity: Entity,
-        _db: &dyn ParserDatabase,
+        entity: Entity,
+        db: &dyn ParserDatabase,
     ) -> WithError<Result<Arc<GenericDeclarations>, ErrorReported>> {
-        // FIXME -- no support for generics yet
-        WithError::ok(Ok(GenericDeclarations::empty(None)))
+        generics::generic_declarations(
+            entity,
+            db,
+            &self.generic_parameters,
+            &self.signature.where_clause,
+        )
     }
 
     fn parse_type(
@@ -101,13 +116,12 @@ impl LazyParsedEntity for ParsedFunctionDeclaration {
         // Rust.
         match db.generic_declarations(entity).into_value() {
             Ok(generic_declarations) => {
-                assert!(generic_declarations.is_empty());
                 let ty = crate::type_conversion::declaration_ty_named(
                     &db,
                     entity,
                     ty::declaration::DeclaredPermKind::Own,
                     ty::ReprKind::Direct,
-                    ty::Generics::empty(),
+                    crate::type_conversion::bound_var_generics(&db, &generic_declarations),
                 );
                 WithError::ok(ty)
             }
@@ -4,6 +4,7 @@ use crate::syntax::delimited::Delimited;
 use crate::syntax::entity::{
     InvalidParsedEntity, LazyParsedEntity, ParsedEntity, ParsedEntityThunk,
 };
+use crate::syntax::generics::{self, GenericParameters};
 use crate::syntax::identifier::SpannedGlobalIdentifier;
 use crate::syntax::list::CommaList;
 use crate::syntax::member::{Member, ParsedMember};
@@ -29,7 +30,7 @@ use lark_ty::declaration::Declaration;
 use std::sync::Arc;
 
 /// ```ignore
-/// struct <id> {
+/// struct <id> [ `[` <id> `]` ] {
 ///   <id>: <ty> // separated by `,` or newline
 /// }
 /// ```
@@ -52,6 +53,11 @@ impl EntityMacroDefinition for StructDeclaration {
         log::trace!("StructDeclaration::parse: parsing name");
         let struct_name = parser.expect(SkipNewline(SpannedGlobalIdentifier))?;
 
+        let generic_parameters = match parser.parse_if_present(GenericParameters) {
+            Some(parameters) => parameters.unwrap_or_else(|ErrorReported(_)| Seq::default()),
+            None => Seq::default(),
+        };
+
         log::trace!("StructDeclaration::parse: parsing fields");
         let fields = parser
             .expect(SkipNewline(Delimited(Curlies, CommaList(Member))))
@@ -72,12 +78,16 @@ impl EntityMacroDefinition for StructDeclaration {
             entity,
             full_span,
             characteristic_span,
-            ParsedEntityThunk::new(ParsedStructDeclaration { fields }),
+            ParsedEntityThunk::new(ParsedStructDeclaration {
+                generic_parameters,
+                fields,
+            }),
         ))
     }
 }
 
 struct ParsedStructDeclaration {
+    generic_parameters: Seq<Spanned<GlobalIdentifier, FileName>>,
     fields: Seq<Spanned<ParsedMember, FileName>>,
 }
 
@@ -135,11 +145,10 @@ impl LazyParsedEntity for ParsedStructDeclaration {
 
     fn parse_generic_declarations(
         &self,
-        _entity: Entity,
-        _db: &dyn ParserDatabase,
+        entity: Entity,
+        db: &dyn ParserDatabase,
     ) -> WithError<Result<Arc<ty::GenericDeclarations>, ErrorReported>> {
-        // FIXME -- no support for generics yet
-        WithError::ok(Ok(ty::GenericDeclarations::empty(None)))
+        generics::generic_declarations(entity, db, &self.generic_parameters, &[])
     }
 
     fn parse_signature(
@@ -155,16 +164,15 @@ impl LazyParsedEntity for ParsedStructDeclaration {
         entity: Entity,
         db: &dyn ParserDatabase,
     ) -> WithError<ty::Ty<Declaration>> {
-        // For each struct `Foo`, the "type" is just `own Foo`
+        // For each struct `Foo[T]`, the "type" is just `own Foo[T]`
         match db.generic_declarations(entity).into_value() {
             Ok(generic_declarations) => {
-                assert!(generic_declarations.is_empty());
                 let ty = crate::type_conversion::declaration_ty_named(
                     &db,
                     entity,
                     ty::declaration::DeclaredPermKind::Own,
                     ty::ReprKind::Direct,
-                    ty::Generics::empty(),
+                    crate::type_conversion::bound_var_generics(&db, &generic_declarations),
                 );
                 WithError::ok(ty)
             }
@@ -0,0 +1,145 @@
+use crate::ParserDatabase;
+use lark_collections::Seq;
+use lark_entity::{EntityData, ItemKind, MemberKind};
+use lark_error::Diagnostic;
+use lark_intern::{Intern, Untern};
+use lark_span::{FileName, Span};
+
+/// Checks the names declared in the given file against the usual
+/// conventions: structs are `UpperCamelCase`, while functions,
+/// fields, methods, and variables are `snake_case`. Names with a
+/// leading underscore are exempt from the `snake_case` check.
+crate fn naming_warnings(db: &impl ParserDatabase, id: FileName) -> Seq<Diagnostic> {
+    if !db.check_naming() {
+        return Seq::default();
+    }
+
+    let mut warnings = vec![];
+    let file_entity = EntityData::InputFile { file: id }.intern(db);
+    for &entity in db.descendant_entities(file_entity).iter() {
+        let entity_data = entity.untern(db);
+        match entity_data {
+            EntityData::ItemName {
+                kind: ItemKind::Struct,
+                id: name,
+                ..
+            } => check_name(
+                &mut warnings,
+                "struct",
+                &name.untern(db),
+                db.characteristic_entity_span(entity),
+                Case::UpperCamel,
+            ),
+
+            EntityData::ItemName {
+                kind: ItemKind::Function,
+                id: name,
+                ..
+            } => check_name(
+                &mut warnings,
+                "function",
+                &name.untern(db),
+                db.characteristic_entity_span(entity),
+                Case::Snake,
+            ),
+
+            EntityData::MemberName { kind, id: name, .. } => check_name(
+                &mut warnings,
+                match kind {
+                    MemberKind::Field => "field",
+                    MemberKind::Method => "method",
+                },
+                &name.untern(db),
+                db.characteristic_entity_span(entity),
+                Case::Snake,
+            ),
+
+            EntityData::InputFile { .. } | EntityData::LangItem(_) | EntityData::Error(_) => {}
+        }
+
+        if entity_data.has_fn_body() {
+            let fn_body = db.fn_body(entity).into_value();
+            for variable in fn_body.tables.variables.indices() {
+                let name = fn_body[fn_body[variable].name].text.untern(db);
+                check_name(
+                    &mut warnings,
+                    "variable",
+                    &name,
+                    fn_body.span(variable),
+                    Case::Snake,
+                );
+            }
+        }
+    }
+
+    Seq::from(warnings)
+}
+
+#[derive(Copy, Clone)]
+enum Case {
+    UpperCamel,
+    Snake,
+}
+
+/// Reports a warning if `name` is not already in the given case.
+fn check_name(
+    warnings: &mut Vec<Diagnostic>,
+    what: &str,
+    name: &str,
+    span: Span<FileName>,
+    case: Case,
+) {
+    let expected = match case {
+        Case::UpperCamel => to_upper_camel_case(name),
+        Case::Snake if name.starts_with('_') => return,
+        Case::Snake => to_snake_case(name),
+    };
+    if expected != name {
+        let label = format!(
+            "{} name `{}` does not follow naming conventions",
+            what, name
+        );
+        warnings.push(Diagnostic::warning(label, span).with_suggestion(expected));
+    }
+}
+
+/// `my_struct` becomes `MyStruct`. Letters other than the first of
+/// each word are left alone, so that acronyms like `HTTPServer` are
+/// accepted.
+fn to_upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(|c| c.to_uppercase())
+                .chain(chars)
+        })
+        .collect()
+}
+
+/// `myFunc` becomes `my_func`, and `HTTPServer` becomes
+/// `http_server`.
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::with_capacity(name.len());
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let previous = index.checked_sub(1).and_then(|i| chars.get(i));
+            let next = chars.get(index + 1);
+            let starts_word = match previous {
+                None | Some('_') => false,
+                Some(p) if p.is_lowercase() || p.is_numeric() => true,
+                Some(p) => p.is_uppercase() && next.map_or(false, |n| n.is_lowercase()),
+            };
+            if starts_word {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
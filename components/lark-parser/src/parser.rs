@@ -28,7 +28,7 @@ pub struct Parser<'parse> {
     input: &'parse Text,
 
     /// List of all tokens.
-    tokens: &'parse Seq<Spanned<LexToken, FileName>>,
+    tokens: &'parse [Spanned<LexToken, FileName>],
 
     /// Index of the token *after* the current token.
     next_lookahead_token: usize,
@@ -54,7 +54,7 @@ impl Parser<'parse> {
             Arc<dyn EntityMacroDefinition>,
         >,
         input: &'parse Text,
-        tokens: &'parse Seq<Spanned<LexToken, FileName>>,
+        tokens: &'parse [Spanned<LexToken, FileName>],
         start_token: usize,
     ) -> Self {
         // Subtle: the start token may be whitespace etc. So we actually have to invoke
@@ -205,6 +205,39 @@ impl Parser<'parse> {
         &self.input[self.peek_span()]
     }
 
+    /// Collects the run of `///` doc comments that immediately
+    /// precede the current lookahead token, with the `///` (and one
+    /// following space) stripped from each line. A blank line ends
+    /// the run. Returns `None` if there are no such comments.
+    crate fn preceding_doc_comments(&self) -> Option<Text> {
+        let mut lines = vec![];
+        for token in self.tokens[..self.peek_index()].iter().rev() {
+            match token.value {
+                LexToken::Whitespace => continue,
+                LexToken::DocComment => lines.push(&self.input[token.span]),
+                _ => break,
+            }
+        }
+
+        if lines.is_empty() {
+            return None;
+        }
+
+        let docs: Vec<&str> = lines
+            .iter()
+            .rev()
+            .map(|line| {
+                let line = &line.trim_end()["///".len()..];
+                if line.starts_with(' ') {
+                    &line[1..]
+                } else {
+                    line
+                }
+            })
+            .collect();
+        Some(Text::from(docs.join("\n")))
+    }
+
     /// Test if the current token is of the given kind.
     crate fn is(&self, kind: LexToken) -> bool {
         kind == self.lookahead_token.value
@@ -297,6 +330,36 @@ impl Parser<'parse> {
     ) -> ErrorReported {
         report_error(&mut self.errors, message, span)
     }
+
+    /// Report an error at the given span, suggesting `suggestion` as
+    /// a replacement for the text there.
+    crate fn report_error_with_suggestion(
+        &mut self,
+        message: impl Into<String>,
+        suggestion: impl Into<String>,
+        span: Span<FileName>,
+    ) -> ErrorReported {
+        let diagnostic = crate::diagnostic(message, span).with_suggestion(suggestion);
+        self.errors.push(diagnostic);
+        ErrorReported::at_diagnostic(self.errors.last().unwrap())
+    }
+
+    /// The number of errors (and warnings) reported so far; see
+    /// `discard_errors_since`.
+    crate fn error_count(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Discards the errors reported since `error_count` returned
+    /// `count`.
+    crate fn discard_errors_since(&mut self, count: usize) {
+        self.errors.truncate(count);
+    }
+
+    /// Report a warning with the given message at the given span.
+    crate fn report_warning(&mut self, message: impl Into<String>, span: Span<FileName>) {
+        self.errors.push(Diagnostic::warning(message.into(), span));
+    }
 }
 
 impl AsRef<GlobalIdentifierTables> for Parser<'_> {
@@ -334,7 +397,7 @@ fn advance_next_token(
         // Skip over whitespace/comments automatically (but not
         // newlines).
         match token.value {
-            LexToken::Whitespace | LexToken::Comment => continue,
+            LexToken::Whitespace | LexToken::Comment | LexToken::DocComment => continue,
             _ => return token,
         }
     }
@@ -1,18 +1,24 @@
+use crate::dead_code;
 use crate::ir::ParsedFile;
 use crate::lexer::definition::LexerState;
 use crate::lexer::token::LexToken;
 use crate::lexer::tools::Tokenizer;
-use crate::parser::Parser;
-use crate::syntax::entity::{EntitySyntax, ParsedEntity, ParsedEntityThunk};
-use crate::syntax::skip_newline::SkipNewline;
+use crate::syntax::entity::{ErrorParsedEntity, ParsedEntity, ParsedEntityThunk};
+use crate::syntax::sigil;
+use crate::Direction;
+use crate::EntityAnnotation;
 use crate::HoverTarget;
 use crate::HoverTargetKind;
 use crate::ParserDatabase;
+use crate::SourceMap;
+use crate::WellKnownIdentifiers;
 
-use lark_collections::Seq;
+use lark_collections::{FxIndexMap, FxIndexSet, Seq};
 use lark_debug_with::DebugWith;
+use lark_entity::LangItem;
 use lark_entity::MemberKind;
 use lark_entity::{Entity, EntityData};
+use lark_error::Diagnostic;
 use lark_error::ErrorReported;
 use lark_error::ErrorSentinel;
 use lark_error::WithError;
@@ -20,19 +26,51 @@ use lark_hir as hir;
 use lark_intern::{Intern, Untern};
 use lark_span::{ByteIndex, FileName, Location, Span, Spanned};
 use lark_string::GlobalIdentifier;
+use lark_string::Text;
+use lark_ty::declaration::Declaration;
+use lark_ty::TypeFamily;
 use std::sync::Arc;
 
 crate fn file_tokens(
     db: &impl ParserDatabase,
     file_name: FileName,
 ) -> WithError<Seq<Spanned<LexToken, FileName>>> {
-    let input = db.file_text(file_name);
-    let mut tokenizer: Tokenizer<'_, LexerState> = Tokenizer::new(&input);
+    lex(db, file_name, &db.file_text(file_name))
+}
+
+crate fn file_tokens_at_revision(
+    db: &impl ParserDatabase,
+    file_name: FileName,
+    revision: u64,
+) -> WithError<Seq<Spanned<LexToken, FileName>>> {
+    lex(db, file_name, &db.file_text_at_revision(file_name, revision))
+}
+
+fn lex(
+    db: &impl ParserDatabase,
+    file_name: FileName,
+    input: &str,
+) -> WithError<Seq<Spanned<LexToken, FileName>>> {
+    let mut tokenizer: Tokenizer<'_, LexerState> = Tokenizer::new(input);
     let mut errors = vec![];
     let mut tokens = vec![];
     while let Some(token) = tokenizer.next() {
         match token {
-            Ok(t) => tokens.push(t.in_file_named(file_name)),
+            Ok(t) => {
+                let mut token = t.in_file_named(file_name);
+                let text = &input[token.span];
+                if token.value == LexToken::Error && text.starts_with("/*") {
+                    // Point at the opening `/*`, not the whole rest of
+                    // the file.
+                    let start = token.span.start();
+                    let opening = Span::new(file_name, start, start.to_usize() + 2);
+                    errors.push(crate::diagnostic("unterminated block comment", opening));
+                } else if let Some(message) = check_token_text(db, token.value, text) {
+                    errors.push(crate::diagnostic(message, token.span));
+                    token.value = LexToken::Error;
+                }
+                tokens.push(token);
+            }
             Err(span) => errors.push(crate::diagnostic(
                 "unrecognized token",
                 span.in_file_named(file_name),
@@ -49,17 +87,60 @@ crate fn file_tokens(
     }
 }
 
+/// Checks identifiers (and unrecognized characters) for things we
+/// want to reject with a more specific message: invisible characters,
+/// which could make two identifiers that look the same differ, and
+/// (if `ascii_identifiers` is set) non-ASCII characters.
+fn check_token_text(db: &impl ParserDatabase, token: LexToken, text: &str) -> Option<String> {
+    match token {
+        LexToken::Identifier | LexToken::Error => {}
+        _ => return None,
+    }
+
+    if let Some(c) = text.chars().find(|&c| is_invisible_char(c)) {
+        return Some(format!(
+            "invisible character U+{:04X} is not allowed here",
+            c as u32
+        ));
+    }
+
+    if token == LexToken::Identifier && db.ascii_identifiers() && !text.is_ascii() {
+        return Some(format!(
+            "identifier `{}` contains non-ASCII characters",
+            text
+        ));
+    }
+
+    None
+}
+
+/// Zero-width characters, some of which (e.g., the zero-width joiner)
+/// are allowed to continue a Unicode identifier.
+fn is_invisible_char(c: char) -> bool {
+    match c {
+        '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' => true,
+        _ => false,
+    }
+}
+
+crate fn well_known_identifiers(db: &impl ParserDatabase) -> WellKnownIdentifiers {
+    WellKnownIdentifiers::new(db)
+}
+
+crate fn token_histogram(db: &impl ParserDatabase, id: FileName) -> FxIndexMap<LexToken, usize> {
+    let mut histogram = FxIndexMap::default();
+    for token in db.file_tokens(id).into_value().iter() {
+        *histogram.entry(token.value).or_insert(0) += 1;
+    }
+    histogram
+}
+
 crate fn parsed_file(db: &impl ParserDatabase, file_name: FileName) -> WithError<ParsedFile> {
     log::debug!("parsed_file({})", file_name.debug_with(db));
 
-    let file_entity = EntityData::InputFile { file: file_name }.intern(db);
-    let entity_macro_definitions = crate::macro_definitions(&db, file_entity);
-    let input = &db.file_text(file_name);
-    let tokens = &db.file_tokens(file_name).into_value();
-    let parser = Parser::new(file_name, db, &entity_macro_definitions, input, tokens, 0);
-    parser
-        .parse_until_eof(SkipNewline(EntitySyntax::new(file_entity)))
-        .map(|entities| ParsedFile::new(file_name, entities, Span::new(file_name, 0, input.len())))
+    let tokens = db.file_tokens(file_name).into_value();
+    crate::parse_tokens(db, file_name, &tokens)
+        .map(|(entities, len)| ParsedFile::new(file_name, entities, Span::new(file_name, 0, len)))
 }
 
 crate fn child_parsed_entities(
@@ -69,7 +150,20 @@ crate fn child_parsed_entities(
     log::debug!("child_parsed_entities({})", entity.debug_with(db));
 
     match entity.untern(db) {
-        EntityData::InputFile { file } => WithError::ok(db.parsed_file(file).into_value().entities),
+        EntityData::InputFile { file } => {
+            let active_cfg_names = db.active_cfg_names();
+            let entities = db.parsed_file(file).into_value().entities;
+            WithError::ok(
+                entities
+                    .iter()
+                    .filter(|e| match e.cfg {
+                        Some(cfg) => active_cfg_names.contains(&cfg),
+                        None => true,
+                    })
+                    .cloned()
+                    .collect(),
+            )
+        }
 
         EntityData::ItemName { .. } => db
             .parsed_entity(entity)
@@ -87,12 +181,12 @@ crate fn parsed_entity(db: &impl ParserDatabase, entity: Entity) -> ParsedEntity
     match entity.untern(db) {
         EntityData::InputFile { file } => {
             let parsed_file = db.parsed_file(file).into_value();
-            ParsedEntity {
-                entity: entity,
-                full_span: parsed_file.span,
-                characteristic_span: parsed_file.span,
-                thunk: ParsedEntityThunk::new(parsed_file),
-            }
+            ParsedEntity::new(
+                entity,
+                parsed_file.span,
+                parsed_file.span,
+                ParsedEntityThunk::new(parsed_file),
+            )
         }
 
         EntityData::ItemName { base, .. } | EntityData::MemberName { base, .. } => {
@@ -101,14 +195,26 @@ crate fn parsed_entity(db: &impl ParserDatabase, entity: Entity) -> ParsedEntity
             siblings
                 .iter()
                 .find(|p| p.entity == entity)
+                .cloned()
                 .unwrap_or_else(|| {
-                    panic!(
+                    // The entity no longer exists, presumably because an
+                    // edit removed it after its id was handed out (e.g.,
+                    // to a hover request that is still in flight). Treat
+                    // it as an error rather than panicking.
+                    log::warn!(
                         "parsed_entity({}): entity not found amongst its siblings `{:?}`",
                         entity.debug_with(db),
                         siblings.debug_with(db),
+                    );
+                    let span = db.parsed_entity(base).full_span;
+                    let err = ErrorReported::at_span(span);
+                    ParsedEntity::new(
+                        entity,
+                        span,
+                        span,
+                        ParsedEntityThunk::new(ErrorParsedEntity { err }),
                     )
                 })
-                .clone()
         }
 
         EntityData::Error { .. } | EntityData::LangItem(_) => {
@@ -128,6 +234,19 @@ crate fn child_entities(db: &impl ParserDatabase, entity: Entity) -> Seq<Entity>
         .collect()
 }
 
+crate fn child_entities_spanned(
+    db: &impl ParserDatabase,
+    entity: Entity,
+) -> Seq<(Entity, Span<FileName>)> {
+    let mut children: Vec<_> = db
+        .child_entities(entity)
+        .iter()
+        .map(|&child| (child, db.entity_span(child)))
+        .collect();
+    children.sort_by_key(|(_, span)| span.start());
+    Seq::from(children)
+}
+
 crate fn fn_body(db: &impl ParserDatabase, entity: Entity) -> WithError<Arc<hir::FnBody>> {
     db.parsed_entity(entity)
         .thunk
@@ -135,22 +254,525 @@ crate fn fn_body(db: &impl ParserDatabase, entity: Entity) -> WithError<Arc<hir:
         .map(Arc::new)
 }
 
+crate fn referenced_entities(db: &impl ParserDatabase, entity: Entity) -> Seq<Entity> {
+    let fn_body = db.fn_body(entity).into_value();
+    let mut referenced = FxIndexSet::default();
+    for place in fn_body.tables.places.iter() {
+        if let hir::PlaceData::Entity(entity) = *place {
+            referenced.insert(entity);
+        }
+    }
+    for expression in fn_body.tables.expressions.iter() {
+        if let hir::ExpressionData::Aggregate { entity, .. } = *expression {
+            referenced.insert(entity);
+        }
+    }
+    referenced.into_iter().collect()
+}
+
+crate fn transitive_dependencies(db: &impl ParserDatabase, entity: Entity) -> Seq<Entity> {
+    // We walk the graph here, rather than recursing through this
+    // query, so that cycles (e.g., mutually recursive fns) terminate.
+    dead_code::walk(Some(entity), |current| {
+        if current.untern(db).has_fn_body() {
+            db.referenced_entities(current).iter().cloned().collect()
+        } else {
+            vec![]
+        }
+    })
+    .into_iter()
+    .collect()
+}
+
+crate fn unused_parameters(
+    db: &impl ParserDatabase,
+    entity: Entity,
+) -> Seq<(hir::Variable, Span<FileName>)> {
+    let fn_body = db.fn_body(entity).into_value();
+    let arguments = match fn_body.arguments {
+        Ok(arguments) => arguments,
+        Err(ErrorReported(_)) => return Seq::default(),
+    };
+
+    let used: FxIndexSet<hir::Variable> = fn_body
+        .tables
+        .places
+        .iter()
+        .filter_map(|place| match *place {
+            hir::PlaceData::Variable(variable) => Some(variable),
+            _ => None,
+        })
+        .collect();
+
+    let self_ = db.well_known_identifiers().self_;
+    arguments
+        .iter(&fn_body)
+        .filter(|variable| !used.contains(variable))
+        .filter(|&variable| {
+            let name = fn_body[fn_body[variable].name].text;
+            name != self_ && !name.untern(db).starts_with('_')
+        })
+        .map(|variable| (variable, fn_body.span(variable)))
+        .collect()
+}
+
+crate fn initializer_of(
+    db: &impl ParserDatabase,
+    entity: Entity,
+    variable: hir::Variable,
+) -> Option<hir::Expression> {
+    let fn_body = db.fn_body(entity).into_value();
+    fn_body
+        .tables
+        .expressions
+        .iter()
+        .filter_map(|expression| match *expression {
+            hir::ExpressionData::Let {
+                variable: v,
+                initializer,
+                ..
+            } if v == variable => initializer,
+            _ => None,
+        })
+        .next()
+}
+
+crate fn argument_span(
+    db: &impl ParserDatabase,
+    entity: Entity,
+    call: hir::Expression,
+    index: usize,
+) -> Option<Span<FileName>> {
+    let fn_body = db.fn_body(entity).into_value();
+    let arguments = match fn_body[call] {
+        hir::ExpressionData::Call { arguments, .. }
+        | hir::ExpressionData::MethodCall { arguments, .. } => arguments,
+        _ => return None,
+    };
+    let argument = arguments.iter(&fn_body).nth(index)?;
+    Some(fn_body.span(argument))
+}
+
+crate fn local_bindings(
+    db: &impl ParserDatabase,
+    entity: Entity,
+) -> Seq<(GlobalIdentifier, Span<FileName>)> {
+    let fn_body = db.fn_body(entity).into_value();
+    fn_body
+        .tables
+        .variables
+        .iter_enumerated()
+        .map(|(variable, data)| (fn_body.tables[data.name].text, fn_body.span(variable)))
+        .collect()
+}
+
+crate fn free_variables(
+    db: &impl ParserDatabase,
+    entity: Entity,
+    expression: hir::Expression,
+) -> Seq<hir::Variable> {
+    let fn_body = db.fn_body(entity).into_value();
+    let mut walker = VariableWalker {
+        fn_body: &fn_body,
+        defined: FxIndexSet::default(),
+        used: FxIndexSet::default(),
+    };
+    walker.walk_expression(expression);
+
+    let VariableWalker { defined, used, .. } = walker;
+    used.into_iter()
+        .filter(|variable| !defined.contains(variable))
+        .collect()
+}
+
+crate fn expression_depth(db: &impl ParserDatabase, entity: Entity) -> usize {
+    let fn_body = db.fn_body(entity).into_value();
+    depth_of_expression(&fn_body, fn_body.root_expression)
+}
+
+crate fn missing_return_values(db: &impl ParserDatabase, entity: Entity) -> Seq<Diagnostic> {
+    let output = match db.signature(entity).into_value() {
+        Ok(signature) => signature.output,
+        Err(ErrorReported(_)) => return Seq::default(),
+    };
+    if output == crate::type_conversion::unit_ty(db) || output == Declaration::error_type(db) {
+        return Seq::default();
+    }
+
+    let fn_body = db.fn_body(entity).into_value();
+    let mut spans = vec![];
+    fall_through_spans(&fn_body, fn_body.root_expression, &mut spans);
+    spans
+        .into_iter()
+        .map(|span| crate::diagnostic("not all control paths return a value", span))
+        .collect()
+}
+
+crate fn source_map(db: &impl ParserDatabase, entity: Entity) -> SourceMap {
+    let fn_body = db.fn_body(entity).into_value();
+    let expressions = fn_body
+        .tables
+        .expressions
+        .indices()
+        .map(|expression| {
+            let span = fn_body.span(expression);
+            (expression, db.location(span.file(), span.start()))
+        })
+        .collect();
+    SourceMap { expressions }
+}
+
+/// Helper for `missing_return_values`: pushes the span of each path
+/// through `expression` that ends in unit rather than a value.
+fn fall_through_spans(
+    fn_body: &hir::FnBody,
+    expression: hir::Expression,
+    spans: &mut Vec<Span<FileName>>,
+) {
+    match fn_body[expression] {
+        hir::ExpressionData::If {
+            if_true, if_false, ..
+        } => match fn_body[if_false] {
+            // No `else`: report the `if` itself, since the elided
+            // `else` has no span worth pointing at.
+            hir::ExpressionData::Unit {} => spans.push(fn_body.span(expression)),
+            _ => {
+                fall_through_spans(fn_body, if_true, spans);
+                fall_through_spans(fn_body, if_false, spans);
+            }
+        },
+
+        hir::ExpressionData::Let { body, .. } => fall_through_spans(fn_body, body, spans),
+
+        hir::ExpressionData::Sequence { second, .. } => fall_through_spans(fn_body, second, spans),
+
+        hir::ExpressionData::Unit {} => spans.push(fn_body.span(expression)),
+
+        _ => {}
+    }
+}
+
+/// Helper for `expression_depth`.
+fn depth_of_expression(fn_body: &hir::FnBody, expression: hir::Expression) -> usize {
+    let max_depth = |expressions: &mut dyn Iterator<Item = hir::Expression>| {
+        expressions
+            .map(|expression| depth_of_expression(fn_body, expression))
+            .max()
+            .unwrap_or(0)
+    };
+
+    let children_depth = match fn_body[expression] {
+        hir::ExpressionData::Let {
+            initializer, body, ..
+        } => max_depth(&mut initializer.into_iter().chain(Some(body))),
+
+        hir::ExpressionData::Place { place } => depth_of_place(fn_body, place),
+
+        hir::ExpressionData::Assignment { place, value } => {
+            depth_of_place(fn_body, place).max(depth_of_expression(fn_body, value))
+        }
+
+        hir::ExpressionData::MethodCall { arguments, .. } => {
+            max_depth(&mut arguments.iter(fn_body))
+        }
+
+        hir::ExpressionData::Call {
+            function,
+            arguments,
+        } => max_depth(&mut Some(function).into_iter().chain(arguments.iter(fn_body))),
+
+        hir::ExpressionData::Sequence { first, second } => {
+            max_depth(&mut vec![first, second].into_iter())
+        }
+
+        hir::ExpressionData::If {
+            condition,
+            if_true,
+            if_false,
+        } => max_depth(&mut vec![condition, if_true, if_false].into_iter()),
+
+        hir::ExpressionData::Binary { left, right, .. } => {
+            max_depth(&mut vec![left, right].into_iter())
+        }
+
+        hir::ExpressionData::Unary { value, .. } => depth_of_expression(fn_body, value),
+
+        hir::ExpressionData::Aggregate { fields, .. } => {
+            max_depth(&mut fields.iter_data(fn_body).map(|field| field.expression))
+        }
+
+        hir::ExpressionData::Literal { .. }
+        | hir::ExpressionData::Unit {}
+        | hir::ExpressionData::Error { .. } => 0,
+    };
+
+    1 + children_depth
+}
+
+/// Helper for `expression_depth`: a place only adds depth if it
+/// contains an expression (e.g., `foo().bar`).
+fn depth_of_place(fn_body: &hir::FnBody, place: hir::Place) -> usize {
+    match fn_body[place] {
+        hir::PlaceData::Variable(_) | hir::PlaceData::Entity(_) => 0,
+        hir::PlaceData::Temporary(expression) => depth_of_expression(fn_body, expression),
+        hir::PlaceData::Field { owner, .. } => depth_of_place(fn_body, owner),
+    }
+}
+
+/// Helper for `free_variables`: collects the variables that are
+/// defined and used within an expression.
+struct VariableWalker<'me> {
+    fn_body: &'me hir::FnBody,
+    defined: FxIndexSet<hir::Variable>,
+    used: FxIndexSet<hir::Variable>,
+}
+
+impl VariableWalker<'_> {
+    fn walk_expression(&mut self, expression: hir::Expression) {
+        let fn_body = self.fn_body;
+        match fn_body[expression] {
+            hir::ExpressionData::Let {
+                variable,
+                initializer,
+                body,
+            } => {
+                self.defined.insert(variable);
+                if let Some(initializer) = initializer {
+                    self.walk_expression(initializer);
+                }
+                self.walk_expression(body);
+            }
+
+            hir::ExpressionData::Place { place } => self.walk_place(place),
+
+            hir::ExpressionData::Assignment { place, value } => {
+                self.walk_place(place);
+                self.walk_expression(value);
+            }
+
+            hir::ExpressionData::MethodCall { arguments, .. } => {
+                for argument in arguments.iter(fn_body) {
+                    self.walk_expression(argument);
+                }
+            }
+
+            hir::ExpressionData::Call {
+                function,
+                arguments,
+            } => {
+                self.walk_expression(function);
+                for argument in arguments.iter(fn_body) {
+                    self.walk_expression(argument);
+                }
+            }
+
+            hir::ExpressionData::Sequence { first, second } => {
+                self.walk_expression(first);
+                self.walk_expression(second);
+            }
+
+            hir::ExpressionData::If {
+                condition,
+                if_true,
+                if_false,
+            } => {
+                self.walk_expression(condition);
+                self.walk_expression(if_true);
+                self.walk_expression(if_false);
+            }
+
+            hir::ExpressionData::Binary { left, right, .. } => {
+                self.walk_expression(left);
+                self.walk_expression(right);
+            }
+
+            hir::ExpressionData::Unary { value, .. } => self.walk_expression(value),
+
+            hir::ExpressionData::Aggregate { fields, .. } => {
+                for field in fields.iter_data(fn_body) {
+                    self.walk_expression(field.expression);
+                }
+            }
+
+            hir::ExpressionData::Literal { .. }
+            | hir::ExpressionData::Unit {}
+            | hir::ExpressionData::Error { .. } => {}
+        }
+    }
+
+    fn walk_place(&mut self, place: hir::Place) {
+        match self.fn_body[place] {
+            hir::PlaceData::Variable(variable) => {
+                self.used.insert(variable);
+            }
+            hir::PlaceData::Entity(_) => {}
+            hir::PlaceData::Temporary(expression) => self.walk_expression(expression),
+            hir::PlaceData::Field { owner, .. } => self.walk_place(owner),
+        }
+    }
+}
+
+crate fn file_text(db: &impl ParserDatabase, id: FileName) -> Text {
+    let text = match db.file_overlay(id) {
+        Some(text) => text,
+        None => db.file_disk_text(id),
+    };
+
+    if db.normalize_newlines() && text.contains('\r') {
+        Text::from(text.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        text
+    }
+}
+
+crate fn file_len(db: &impl ParserDatabase, id: FileName) -> usize {
+    db.file_text(id).len()
+}
+
+crate fn declaring_file(db: &impl ParserDatabase, entity: Entity) -> Option<FileName> {
+    match entity.untern(db) {
+        EntityData::InputFile { file } => Some(file),
+        EntityData::ItemName { base, .. } | EntityData::MemberName { base, .. } => {
+            db.declaring_file(base)
+        }
+        EntityData::LangItem(_) | EntityData::Error(_) => None,
+    }
+}
+
+crate fn qualified_name(db: &impl ParserDatabase, entity: Entity, include_file: bool) -> String {
+    match entity.untern(db) {
+        EntityData::Error(_) => String::from("<error>"),
+        EntityData::LangItem(lang_item) => String::from(match lang_item {
+            LangItem::Boolean => "bool",
+            LangItem::Int => "int",
+            LangItem::Uint => "uint",
+            LangItem::Tuple(_) => "tuple",
+            LangItem::String => "String",
+            LangItem::True => "true",
+            LangItem::False => "false",
+            LangItem::Debug => "debug",
+        }),
+        EntityData::InputFile { file } => file.untern(db).to_string(),
+        EntityData::ItemName { base, id, .. } | EntityData::MemberName { base, id, .. } => {
+            let name = id.untern(db);
+            match base.untern(db) {
+                EntityData::InputFile { .. } if !include_file => name.to_string(),
+                _ => format!("{}::{}", db.qualified_name(base, include_file), name),
+            }
+        }
+    }
+}
+
 crate fn entity_span(db: &impl ParserDatabase, entity: Entity) -> Span<FileName> {
     db.parsed_entity(entity).full_span.in_file_named(
-        entity
-            .input_file(db)
+        db.declaring_file(entity)
             .expect("Unexpected entity_span for LangItem or Error"),
     )
 }
 
+crate fn entity_indent(db: &impl ParserDatabase, entity: Entity) -> u32 {
+    match entity.untern(db) {
+        EntityData::ItemName { .. } | EntityData::MemberName { .. } => {}
+        EntityData::InputFile { .. } | EntityData::LangItem(_) | EntityData::Error(_) => {
+            return 0;
+        }
+    }
+
+    let span = db.entity_span(entity);
+    let file = span.file();
+    let line = db.location(file, span.start()).line;
+    let line_start = db.line_offsets(file)[line];
+    let text = db.file_text(file);
+    text[line_start..]
+        .chars()
+        .take_while(|&c| c == ' ' || c == '\t')
+        .count() as u32
+}
+
+crate fn entity_full_spans(
+    db: &impl ParserDatabase,
+    file: FileName,
+) -> Seq<(Entity, Span<FileName>)> {
+    let file_entity = EntityData::InputFile { file }.intern(db);
+    let entities = db.child_entities(file_entity);
+
+    let mut start = 0;
+    let mut spans: Vec<_> = entities
+        .iter()
+        .map(|&entity| {
+            let end = db.entity_span(entity).end().to_usize();
+            let span = Span::new(file, start, end);
+            start = end;
+            (entity, span)
+        })
+        .collect();
+
+    if let Some((_, last)) = spans.last_mut() {
+        *last = Span::new(file, last.start(), db.file_len(file));
+    }
+
+    Seq::from(spans)
+}
+
 crate fn characteristic_entity_span(db: &impl ParserDatabase, entity: Entity) -> Span<FileName> {
     db.parsed_entity(entity).characteristic_span.in_file_named(
-        entity
-            .input_file(db)
+        db.declaring_file(entity)
             .expect("Unexpected entity_span for LangItem or Error"),
     )
 }
 
+crate fn entity_keyword_span(db: &impl ParserDatabase, entity: Entity) -> Option<Span<FileName>> {
+    match entity.untern(db) {
+        // Items begin with their keyword (`def`, `struct`); members
+        // have no keyword at all.
+        EntityData::ItemName { .. } => {
+            let full_span = db.entity_span(entity);
+            let tokens = db.file_tokens(full_span.file()).into_value();
+            tokens
+                .iter()
+                .find(|token| token.span.start() == full_span.start())
+                .map(|token| token.span)
+        }
+
+        EntityData::MemberName { .. }
+        | EntityData::InputFile { .. }
+        | EntityData::LangItem(_)
+        | EntityData::Error(_) => None,
+    }
+}
+
+crate fn entity_docs(db: &impl ParserDatabase, entity: Entity) -> Option<String> {
+    match entity.untern(db) {
+        EntityData::ItemName { .. } | EntityData::MemberName { .. } => {
+            db.parsed_entity(entity).docs.map(|docs| docs.to_string())
+        }
+
+        EntityData::InputFile { .. } | EntityData::LangItem(_) | EntityData::Error(_) => None,
+    }
+}
+
+crate fn entity_deprecation(db: &impl ParserDatabase, entity: Entity) -> Option<Text> {
+    match entity.untern(db) {
+        EntityData::ItemName { .. } | EntityData::MemberName { .. } => {
+            db.parsed_entity(entity).deprecated
+        }
+
+        EntityData::InputFile { .. } | EntityData::LangItem(_) | EntityData::Error(_) => None,
+    }
+}
+
+crate fn entity_annotations(db: &impl ParserDatabase, entity: Entity) -> Seq<EntityAnnotation> {
+    match entity.untern(db) {
+        EntityData::ItemName { .. } | EntityData::MemberName { .. } => {
+            db.parsed_entity(entity).annotations
+        }
+
+        EntityData::InputFile { .. } | EntityData::LangItem(_) | EntityData::Error(_) => {
+            Seq::default()
+        }
+    }
+}
+
 crate fn line_offsets(db: &impl ParserDatabase, id: FileName) -> Seq<usize> {
     let text: &str = &db.file_text(id);
     let mut accumulator = 0;
@@ -165,11 +787,15 @@ crate fn line_offsets(db: &impl ParserDatabase, id: FileName) -> Seq<usize> {
             }
             line_start
         })
-        .chain(std::iter::once(text.len()))
+        .chain(std::iter::once(db.file_len(id)))
         .collect()
 }
 
 crate fn location(db: &impl ParserDatabase, id: FileName, index: ByteIndex) -> Location {
+    // Clamp indices past the end of the file (e.g., any nonzero
+    // index into an empty file) to the end.
+    let index = ByteIndex::from(index.to_usize().min(db.file_len(id)));
+
     let line_offsets = db.line_offsets(id);
     match line_offsets.binary_search(&index.to_usize()) {
         Ok(line) => {
@@ -195,8 +821,131 @@ crate fn byte_index(db: &impl ParserDatabase, id: FileName, line: u64, column: u
     let line = line as usize;
     let column = column as usize;
     let line_offsets = db.line_offsets(id);
-    let line_start = line_offsets[line];
-    ByteIndex::from(line_start + column)
+
+    // `line_offsets` always has at least one entry (`[0]` for an
+    // empty file), so positions beyond the end clamp to the end.
+    let line_start = line_offsets[line.min(line_offsets.len() - 1)];
+    ByteIndex::from((line_start + column).min(db.file_len(id)))
+}
+
+crate fn token_before(
+    db: &impl ParserDatabase,
+    id: FileName,
+    index: ByteIndex,
+    skip_trivia: bool,
+) -> Option<Spanned<LexToken, FileName>> {
+    let tokens = db.file_tokens(id).into_value();
+    tokens
+        .iter()
+        .take_while(|token| token.span.end() <= index)
+        .filter(|token| match token.value {
+            LexToken::Whitespace
+            | LexToken::Newline
+            | LexToken::Comment
+            | LexToken::DocComment => !skip_trivia,
+            _ => true,
+        })
+        .last()
+        .cloned()
+}
+
+crate fn token_at(
+    db: &impl ParserDatabase,
+    id: FileName,
+    index: ByteIndex,
+) -> Spanned<LexToken, FileName> {
+    let tokens = db.file_tokens(id).into_value();
+    tokens
+        .iter()
+        .find(|token| token.span.contains_index(index))
+        .cloned()
+        .unwrap_or_else(|| {
+            let file_len = db.file_len(id);
+            Spanned {
+                value: LexToken::EOF,
+                span: Span::new(id, file_len, file_len),
+            }
+        })
+}
+
+crate fn entity_token_range(db: &impl ParserDatabase, entity: Entity) -> Option<(usize, usize)> {
+    match entity.untern(db) {
+        EntityData::ItemName { .. } | EntityData::MemberName { .. } => {}
+
+        EntityData::InputFile { .. } | EntityData::LangItem(_) | EntityData::Error(_) => {
+            return None;
+        }
+    }
+
+    let file = db.declaring_file(entity)?;
+    let span = db.entity_span(entity);
+    let tokens = db.file_tokens(file).into_value();
+    let index_from = |index: ByteIndex| {
+        tokens
+            .iter()
+            .position(|token| token.span.start() >= index)
+            .unwrap_or(tokens.len())
+    };
+    Some((index_from(span.start()), index_from(span.end())))
+}
+
+crate fn identifier_span_at(
+    db: &impl ParserDatabase,
+    id: FileName,
+    index: ByteIndex,
+) -> Option<Span<FileName>> {
+    let token = db.token_at(id, index);
+    match token.value {
+        LexToken::Identifier if !sigil::is_keyword(&db.file_text(id)[token.span]) => {
+            Some(token.span)
+        }
+        _ => None,
+    }
+}
+
+crate fn indentation_levels(db: &impl ParserDatabase, id: FileName) -> Seq<(usize, u32)> {
+    let text: &str = &db.file_text(id);
+    let tokens = db.file_tokens(id).into_value();
+    let line_offsets = db.line_offsets(id);
+    let mut tokens = tokens.iter().peekable();
+    let mut depth: u32 = 0;
+
+    // The final line offset is the end of the file, not a line.
+    (0..line_offsets.len() - 1)
+        .map(|line| {
+            let line_end = line_offsets[line + 1];
+
+            // The depth of a line is the depth at its first
+            // significant token. Comments and string literals are
+            // single tokens, so braces within them are never counted.
+            let mut line_depth = None;
+            while let Some(&token) = tokens.peek() {
+                if token.span.start().to_usize() >= line_end {
+                    break;
+                }
+                tokens.next();
+
+                match token.value {
+                    LexToken::Whitespace | LexToken::Newline => continue,
+                    LexToken::Sigil => {
+                        let token_text = &text[token.span];
+                        if token_text == "}" {
+                            depth = depth.saturating_sub(1);
+                        }
+                        line_depth.get_or_insert(depth);
+                        if token_text == "{" {
+                            depth += 1;
+                        }
+                    }
+                    _ => {
+                        line_depth.get_or_insert(depth);
+                    }
+                }
+            }
+
+            (line, line_depth.unwrap_or(depth))
+        })
+        .collect()
 }
 
 crate fn descendant_entities(db: &impl ParserDatabase, root: Entity) -> Seq<Entity> {
@@ -213,6 +962,19 @@ crate fn descendant_entities(db: &impl ParserDatabase, root: Entity) -> Seq<Enti
     Seq::from(entities)
 }
 
+crate fn lower_all_bodies(db: &impl ParserDatabase, file: FileName) -> WithError<()> {
+    let mut errors = vec![];
+
+    let file_entity = EntityData::InputFile { file }.intern(db);
+    for &entity in db.descendant_entities(file_entity).iter() {
+        if entity.untern(db).has_fn_body() {
+            let _ = db.fn_body(entity).accumulate_errors_into(&mut errors);
+        }
+    }
+
+    WithError { value: (), errors }
+}
+
 crate fn members(
     db: &impl ParserDatabase,
     owner: Entity,
@@ -260,6 +1022,46 @@ crate fn member_entity(
     }
 }
 
+crate fn enclosing_fn(
+    db: &impl ParserDatabase,
+    file: FileName,
+    index: ByteIndex,
+) -> Option<Entity> {
+    let file_entity = EntityData::InputFile { file }.intern(db);
+    db.descendant_entities(file_entity)
+        .iter()
+        .cloned()
+        .filter(|entity| entity.untern(db).has_fn_body())
+        .filter(|&entity| db.entity_span(entity).contains_index(index))
+        .min_by_key(|&entity| db.entity_span(entity).len())
+}
+
+crate fn sibling_entity(
+    db: &impl ParserDatabase,
+    entity: Entity,
+    direction: Direction,
+) -> Option<Entity> {
+    let parent = entity.untern(db).parent()?;
+    let siblings = db.child_entities(parent);
+    let index = siblings.iter().position(|&sibling| sibling == entity)?;
+    match direction {
+        Direction::Next => siblings.get(index + 1).cloned(),
+        Direction::Previous => index.checked_sub(1).map(|index| siblings[index]),
+    }
+}
+
+crate fn is_descendant_of(db: &impl ParserDatabase, candidate: Entity, ancestor: Entity) -> bool {
+    // Lang items, errors, and files have no parent, so this ends there.
+    let mut current = candidate.untern(db).parent();
+    while let Some(entity) = current {
+        if entity == ancestor {
+            return true;
+        }
+        current = entity.untern(db).parent();
+    }
+    false
+}
+
 crate fn hover_targets(
     db: &impl ParserDatabase,
     file: FileName,
@@ -28,27 +28,21 @@ crate fn resolve_name(
                 .next()
                 .or_else(|| {
                     // Implicit root scope:
-                    let bool_id = "bool".intern(db);
-                    let int_id = "int".intern(db);
-                    let uint_id = "uint".intern(db);
-                    let false_id = "false".intern(db);
-                    let true_id = "true".intern(db);
-                    let debug_id = "debug".intern(db);
-                    let string_id = "String".intern(db);
+                    let well_known = db.well_known_identifiers();
 
-                    if name == bool_id {
+                    if name == well_known.bool {
                         Some(EntityData::LangItem(LangItem::Boolean).intern(db))
-                    } else if name == int_id {
+                    } else if name == well_known.int {
                         Some(EntityData::LangItem(LangItem::Int).intern(db))
-                    } else if name == uint_id {
+                    } else if name == well_known.uint {
                         Some(EntityData::LangItem(LangItem::Uint).intern(db))
-                    } else if name == false_id {
+                    } else if name == well_known.false_ {
                         Some(EntityData::LangItem(LangItem::False).intern(db))
-                    } else if name == true_id {
+                    } else if name == well_known.true_ {
                         Some(EntityData::LangItem(LangItem::True).intern(db))
-                    } else if name == string_id {
+                    } else if name == well_known.string {
                         Some(EntityData::LangItem(LangItem::String).intern(db))
-                    } else if name == debug_id {
+                    } else if name == well_known.debug {
                         Some(EntityData::LangItem(LangItem::Debug).intern(db))
                     } else {
                         None
@@ -4,11 +4,13 @@ use crate::parser::Parser;
 use lark_debug_with::DebugWith;
 use lark_error::ErrorReported;
 
+pub mod attribute;
 pub mod delimited;
 pub mod entity;
 pub mod expression;
 pub mod fn_body;
 pub mod fn_signature;
+pub mod generics;
 pub mod guard;
 pub mod identifier;
 pub mod list;
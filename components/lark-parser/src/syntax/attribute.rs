@@ -0,0 +1,147 @@
+use crate::lexer::token::LexToken;
+use crate::parser::Parser;
+use crate::syntax::delimited::Delimited;
+use crate::syntax::identifier::SpannedGlobalIdentifier;
+use crate::syntax::sigil::{At, Cfg, CloseParenthesis, Deprecated, OpenParenthesis, Parentheses};
+use crate::syntax::{NonEmptySyntax, Syntax};
+
+use lark_debug_derive::DebugWith;
+use lark_error::ErrorReported;
+use lark_span::{FileName, Span, Spanned};
+use lark_string::{GlobalIdentifier, Text};
+
+/// An attribute attached to an entity.
+#[derive(Clone, Debug, DebugWith, PartialEq, Eq)]
+pub enum Attribute {
+    /// `@cfg(name)`: the entity is only included when `name` is one
+    /// of the db's `active_cfg_names`.
+    Cfg(GlobalIdentifier),
+
+    /// `@deprecated("message")`: uses of the entity are reported
+    /// with the given message.
+    Deprecated(Text),
+
+    /// Any other attribute, which the compiler ignores but preserves
+    /// for other tools; see `entity_annotations`.
+    Other(EntityAnnotation),
+}
+
+/// An attribute that the compiler does not interpret itself, e.g.
+/// `@custom(1)`.
+#[derive(Clone, Debug, DebugWith, PartialEq, Eq)]
+pub struct EntityAnnotation {
+    pub name: GlobalIdentifier,
+
+    /// The source text between the parentheses, if there were any
+    /// (`1` in the example above).
+    pub arguments: Option<Text>,
+
+    /// The span of the whole attribute, from the `@` on.
+    pub span: Span<FileName>,
+}
+
+/// Parses an attribute, which is one of:
+///
+/// ```ignore
+/// @cfg(name)
+/// @deprecated("message")
+/// @<id> [ `(` <tokens> `)` ] // with balanced parentheses
+/// ```
+#[derive(DebugWith)]
+pub struct AttributeSyntax;
+
+impl Syntax<'parse> for AttributeSyntax {
+    type Data = Attribute;
+
+    fn test(&mut self, parser: &Parser<'parse>) -> bool {
+        parser.test(At)
+    }
+
+    fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
+        let at = parser.expect(At)?;
+
+        if parser.parse_if_present(Cfg).is_some() {
+            let name = parser.expect(Delimited(Parentheses, SpannedGlobalIdentifier))?;
+            return Ok(Attribute::Cfg(name.value));
+        }
+
+        if parser.parse_if_present(Deprecated).is_some() {
+            let message = parser.expect(Delimited(Parentheses, StringLiteral))?;
+            return Ok(Attribute::Deprecated(message.value));
+        }
+
+        let name = parser.expect(SpannedGlobalIdentifier)?;
+        let arguments = match parser.parse_if_present(Delimited(Parentheses, RawTokens)) {
+            Some(arguments) => Some(arguments?),
+            None => None,
+        };
+        Ok(Attribute::Other(EntityAnnotation {
+            name: name.value,
+            arguments,
+            span: at.span.extended_until_end_of(parser.last_span()),
+        }))
+    }
+}
+
+impl NonEmptySyntax<'parse> for AttributeSyntax {}
+
+/// Skips over any tokens up to (but not including) an unmatched `)`,
+/// yielding their source text.
+#[derive(DebugWith)]
+struct RawTokens;
+
+impl Syntax<'parse> for RawTokens {
+    type Data = Text;
+
+    fn test(&mut self, _parser: &Parser<'parse>) -> bool {
+        true // we never produce an error
+    }
+
+    fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
+        let start_span = parser.peek_span();
+        let mut span = None;
+        let mut depth = 0;
+        while !parser.is(LexToken::EOF) {
+            if parser.test(OpenParenthesis) {
+                depth += 1;
+            } else if parser.test(CloseParenthesis) {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+            parser.shift();
+            span = Some(start_span.extended_until_end_of(parser.last_span()));
+        }
+
+        Ok(match span {
+            Some(span) => Text::from(&parser.input()[span]),
+            None => Text::from(""),
+        })
+    }
+}
+
+/// Parses a string literal, yielding its contents without the quotes.
+#[derive(DebugWith)]
+struct StringLiteral;
+
+impl Syntax<'parse> for StringLiteral {
+    type Data = Spanned<Text, FileName>;
+
+    fn test(&mut self, parser: &Parser<'parse>) -> bool {
+        parser.is(LexToken::String)
+    }
+
+    fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
+        if !self.test(parser) {
+            return Err(parser.report_error("expected a string", parser.peek_span()));
+        }
+
+        let text = parser.peek_str();
+        let token = parser.shift();
+        let contents = text.trim_start_matches('"').trim_end_matches('"');
+        Ok(token.map(|_| Text::from(contents)))
+    }
+}
+
+impl NonEmptySyntax<'parse> for StringLiteral {}
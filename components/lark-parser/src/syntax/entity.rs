@@ -1,4 +1,7 @@
+use crate::edit_distance;
+use crate::lexer::token::LexToken;
 use crate::parser::Parser;
+use crate::syntax::attribute::{Attribute, AttributeSyntax, EntityAnnotation};
 use crate::syntax::identifier::SpannedGlobalIdentifier;
 use crate::syntax::NonEmptySyntax;
 use crate::syntax::Syntax;
@@ -11,8 +14,12 @@ use lark_error::ErrorReported;
 use lark_error::ErrorSentinel;
 use lark_error::WithError;
 use lark_hir as hir;
+use lark_intern::{Intern, Untern};
 use lark_span::FileName;
 use lark_span::Span;
+use lark_span::Spanned;
+use lark_string::GlobalIdentifier;
+use lark_string::Text;
 use lark_ty as ty;
 use lark_ty::declaration::Declaration;
 use std::sync::Arc;
@@ -20,11 +27,18 @@ use std::sync::Arc;
 #[derive(DebugWith)]
 pub struct EntitySyntax {
     parent_entity: Entity,
+
+    /// Names that are active for `@cfg(name)`; errors within an
+    /// entity whose cfg name is not among them are not reported.
+    active_cfg_names: Seq<GlobalIdentifier>,
 }
 
 impl EntitySyntax {
-    pub fn new(parent_entity: Entity) -> Self {
-        EntitySyntax { parent_entity }
+    pub fn new(parent_entity: Entity, active_cfg_names: Seq<GlobalIdentifier>) -> Self {
+        EntitySyntax {
+            parent_entity,
+            active_cfg_names,
+        }
     }
 }
 
@@ -32,10 +46,56 @@ impl Syntax<'parse> for EntitySyntax {
     type Data = ParsedEntity;
 
     fn test(&mut self, parser: &Parser<'_>) -> bool {
-        parser.test(SpannedGlobalIdentifier)
+        parser.test(AttributeSyntax) || parser.test(SpannedGlobalIdentifier)
     }
 
     fn expect(&mut self, parser: &mut Parser<'_>) -> Result<Self::Data, ErrorReported> {
+        // Any `///` comments directly above the entity are its docs.
+        let docs = parser.preceding_doc_comments();
+
+        // Attributes (e.g., `@cfg(name)`) may precede the entity,
+        // each possibly on a line of its own.
+        let mut cfg = None;
+        let mut deprecated = None;
+        let mut annotations = vec![];
+        while let Some(attribute) = parser.parse_if_present(AttributeSyntax) {
+            match attribute? {
+                Attribute::Cfg(name) => cfg = Some(name),
+                Attribute::Deprecated(message) => deprecated = Some(message),
+                Attribute::Other(annotation) => annotations.push(annotation),
+            }
+            parser.skip_newlines();
+        }
+
+        // An entity that is compiled out is still parsed, so that we
+        // know where it ends, but any errors within it are dropped.
+        let first_error = parser.error_count();
+        let result = self.expect_entity(parser);
+        let active = match cfg {
+            Some(name) => self.active_cfg_names.contains(&name),
+            None => true,
+        };
+        if !active {
+            if result.is_err() {
+                skip_rest_of_entity(parser);
+            }
+            parser.discard_errors_since(first_error);
+        }
+
+        let mut parsed_entity = result?;
+        parsed_entity.cfg = cfg;
+        parsed_entity.deprecated = deprecated;
+        parsed_entity.docs = docs;
+        parsed_entity.annotations = Seq::from(annotations);
+        Ok(parsed_entity)
+    }
+}
+
+impl NonEmptySyntax<'parse> for EntitySyntax {}
+
+impl EntitySyntax {
+    /// Parses the entity that follows its attributes.
+    fn expect_entity(&self, parser: &mut Parser<'_>) -> Result<ParsedEntity, ErrorReported> {
         // Parse the macro keyword, which we must find first. So something like
         //
         // ```
@@ -52,16 +112,66 @@ impl Syntax<'parse> for EntitySyntax {
             macro_name.debug_with(parser),
         );
 
-        let macro_definition = match parser.entity_macro_definitions().get(&macro_name.value) {
-            Some(m) => m.clone(),
-            None => Err(parser.report_error("no macro with this name", macro_name.span))?,
+        let macro_name = match parser.entity_macro_definitions().get(&macro_name.value) {
+            Some(_) => macro_name,
+            None => recover_macro_name(parser, macro_name)?,
         };
+        let macro_definition = parser.entity_macro_definitions()[&macro_name.value].clone();
 
-        Ok(macro_definition.expect(parser, self.parent_entity, macro_name)?)
+        macro_definition.expect(parser, self.parent_entity, macro_name)
     }
 }
 
-impl NonEmptySyntax<'parse> for EntitySyntax {}
+/// Invoked when an entity that is compiled out fails to parse: skips
+/// to the `}` that closes it (every entity ends with a `{ ... }`
+/// block), so that its remaining tokens are not parsed as further
+/// entities.
+fn skip_rest_of_entity(parser: &mut Parser<'_>) {
+    let mut depth = 0;
+    while !parser.is(LexToken::EOF) {
+        let text = parser.peek_str();
+        parser.shift();
+        match text {
+            "{" | "(" | "[" => depth += 1,
+            ")" | "]" => depth -= 1,
+            "}" => {
+                depth -= 1;
+                if depth <= 0 {
+                    return;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Invoked when `macro_name` is not a known macro. If it is a likely
+/// typo for one (e.g., `dfe` for `def`), reports an error but returns
+/// the intended name, so that the entity can still be parsed.
+fn recover_macro_name(
+    parser: &mut Parser<'_>,
+    macro_name: Spanned<GlobalIdentifier, FileName>,
+) -> Result<Spanned<GlobalIdentifier, FileName>, ErrorReported> {
+    let name = macro_name.value.untern(parser);
+    let keywords: Vec<Text> = parser
+        .entity_macro_definitions()
+        .keys()
+        .map(|keyword| keyword.untern(parser))
+        .collect();
+
+    match edit_distance::closest_match(&name, keywords.iter().map(|k| &k[..]), 1) {
+        Some(keyword) => {
+            parser.report_error_with_suggestion(
+                "no macro with this name",
+                keyword.to_string(),
+                macro_name.span,
+            );
+            Ok(macro_name.map(|_| keyword.intern(parser)))
+        }
+
+        None => Err(parser.report_error("no macro with this name", macro_name.span)),
+    }
+}
 
 #[derive(Clone, Debug, DebugWith, PartialEq, Eq)]
 pub struct ParsedEntity {
@@ -79,6 +189,23 @@ pub struct ParsedEntity {
 
     /// Thunk to extract contents
     pub thunk: ParsedEntityThunk,
+
+    /// The name from a `@cfg(name)` attribute, if any. The entity is
+    /// only included when `name` is one of the db's
+    /// `active_cfg_names`.
+    pub cfg: Option<GlobalIdentifier>,
+
+    /// The message from a `@deprecated("message")` attribute, if
+    /// any; each use of the entity is reported with this message.
+    pub deprecated: Option<Text>,
+
+    /// The text of the `///` doc comments directly preceding the
+    /// entity, if any.
+    pub docs: Option<Text>,
+
+    /// Any attributes that the compiler does not itself understand
+    /// (e.g., `@custom(1)`), in the order they appear.
+    pub annotations: Seq<EntityAnnotation>,
 }
 
 impl ParsedEntity {
@@ -93,6 +220,10 @@ impl ParsedEntity {
             full_span,
             characteristic_span,
             thunk,
+            cfg: None,
+            deprecated: None,
+            docs: None,
+            annotations: Seq::default(),
         }
     }
 }
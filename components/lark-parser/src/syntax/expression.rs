@@ -100,6 +100,24 @@ impl Syntax<'parse> for Expression<'me, 'parse> {
     }
 
     fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
+        // Nested expressions are parsed recursively, so bail out
+        // before pathological input can overflow the stack.
+        if self.scope.depth >= self.scope.db.expression_depth_limit() {
+            return Err(parser.report_error("nesting too deep", parser.peek_span()));
+        }
+
+        self.scope.depth += 1;
+        let result = self.expect_nested(parser);
+        self.scope.depth -= 1;
+        result
+    }
+}
+
+impl Expression<'me, 'parse> {
+    fn expect_nested(
+        &mut self,
+        parser: &mut Parser<'parse>,
+    ) -> Result<ParsedExpression, ErrorReported> {
         // Parse `Expression5`
         let expression = parser.expect(Expression5::new(self.scope))?;
 
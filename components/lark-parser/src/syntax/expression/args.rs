@@ -3,7 +3,7 @@ use crate::syntax::delimited::Delimited;
 use crate::syntax::expression::scope::ExpressionScope;
 use crate::syntax::expression::ParsedExpression;
 use crate::syntax::expression::{HirExpression, IdentifiedExpression};
-use crate::syntax::list::CommaList;
+use crate::syntax::list::{ArgumentList, CommaList};
 use crate::syntax::sigil::{OpenParenthesis, Parentheses};
 use crate::syntax::Syntax;
 use derive_new::new;
@@ -28,7 +28,7 @@ impl Syntax<'parse> for CallArguments<'me, 'parse> {
     fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
         let expressions = parser.expect(Delimited(
             Parentheses,
-            CommaList(HirExpression::new(self.scope)),
+            ArgumentList(HirExpression::new(self.scope)),
         ))?;
 
         let arg0 = self.arg0.map(|p| p.to_hir_expression(self.scope));
@@ -50,10 +50,8 @@ where
                 let right = parser
                     .expect(SkipNewline(&mut self.expr))?
                     .to_hir_expression(self.scope());
-                let span = self
-                    .scope()
-                    .span(left)
-                    .extended_until_end_of(parser.last_span());
+                let left_span = self.scope().span(left);
+                let span = left_span.extended_until_end_of(parser.last_span());
                 left = self.scope().add(
                     span,
                     hir::ExpressionData::Binary {
@@ -65,7 +63,32 @@ where
 
                 match operator {
                     hir::BinaryOperator::Equals | hir::BinaryOperator::NotEquals => {
-                        // Do not parse `a == b == c` etc
+                        // Do not parse `a == b == c` etc; report it
+                        // here rather than leaving `== c` behind.
+                        if let Some(next_operator) = parser.parse_if_present(&mut self.op) {
+                            let next_operator = next_operator?;
+                            let right_span = self.scope().span(right);
+                            let next = parser
+                                .expect(SkipNewline(&mut self.expr))?
+                                .to_hir_expression(self.scope());
+                            let next_span = self.scope().span(next);
+                            let input = parser.input();
+                            let message = format!(
+                                "chained comparison is not supported; \
+                                 compare `{} {} {}` and `{} {} {}` separately",
+                                &input[left_span],
+                                hir::operator_symbol(operator),
+                                &input[right_span],
+                                &input[right_span],
+                                hir::operator_symbol(next_operator),
+                                &input[next_span],
+                            );
+                            let span = span.extended_until_end_of(next_span);
+                            parser.report_error(message, span);
+                            left = self
+                                .scope()
+                                .already_reported_error_expression(span, hir::ErrorData::Misc);
+                        }
                         break;
                     }
 
@@ -1,15 +1,20 @@
 use crate::parser::Parser;
 use crate::syntax::delimited::Delimited;
+use crate::syntax::expression::binary::{
+    BinaryOperator, BINARY_OPERATORS_EXPR3, BINARY_OPERATORS_EXPR4, BINARY_OPERATORS_EXPR5,
+};
 use crate::syntax::expression::scope::ExpressionScope;
 use crate::syntax::expression::ParsedStatement;
 use crate::syntax::fn_body::Statement;
-use crate::syntax::list::SeparatedList;
 use crate::syntax::sigil::{Curlies, Semicolon};
 use crate::syntax::Syntax;
 use derive_new::new;
+use lark_collections::Seq;
 use lark_debug_derive::DebugWith;
 use lark_error::ErrorReported;
 use lark_hir as hir;
+use lark_span::FileName;
+use lark_span::Span;
 
 #[derive(new, DebugWith)]
 crate struct Block<'me, 'parse> {
@@ -17,13 +22,8 @@ crate struct Block<'me, 'parse> {
 }
 
 impl Block<'me, 'parse> {
-    fn definition(
-        &'a mut self,
-    ) -> Delimited<Curlies, SeparatedList<Statement<'a, 'parse>, Semicolon>> {
-        Delimited(
-            Curlies,
-            SeparatedList(Statement::new(self.scope), Semicolon),
-        )
+    fn definition(&'a mut self) -> Delimited<Curlies, BlockStatements<'a, 'parse>> {
+        Delimited(Curlies, BlockStatements::new(self.scope))
     }
 }
 
@@ -39,7 +39,7 @@ impl Syntax<'parse> for Block<'me, 'parse> {
         let variables_on_entry = self.scope.save_scope();
 
         let start_span = parser.peek_span();
-        let statements = parser.expect(self.definition())?;
+        let (statements, trailing_semicolon) = parser.expect(self.definition())?;
 
         if statements.is_empty() {
             // FIXME -- it'd be better if `Delimited` gave back a
@@ -53,7 +53,21 @@ impl Syntax<'parse> for Block<'me, 'parse> {
         let mut statements_iter = statements.into_iter().rev().cloned();
 
         let mut result = match statements_iter.next().unwrap() {
-            ParsedStatement::Expression(e) => e,
+            // A block whose final expression is followed by a `;`
+            // produces unit, not the value of that expression.
+            ParsedStatement::Expression(e) => match trailing_semicolon {
+                Some(semicolon_span) => {
+                    let unit = self.scope.unit_expression(semicolon_span);
+                    self.scope.add(
+                        self.scope.span(e),
+                        hir::ExpressionData::Sequence {
+                            first: e,
+                            second: unit,
+                        },
+                    )
+                }
+                None => e,
+            },
             ParsedStatement::Let(span, variable, initializer) => {
                 // If a `let` appears as the last statement, then its associated
                 // value is just a unit expression.
@@ -95,3 +109,75 @@ impl Syntax<'parse> for Block<'me, 'parse> {
         Ok(result)
     }
 }
+
+/// The statements within a block. This is equivalent to
+/// `SeparatedList(Statement, Semicolon)`, except that it also returns
+/// the span of the `;` that terminates the final statement, if
+/// any. This tells us whether the block produces a value or unit.
+///
+/// As in `SeparatedList`, a newline also ends a statement, so
+/// semicolons are optional. To continue an expression onto the next
+/// line, end the line with a binary operator:
+///
+/// ```ignore
+/// a +
+///   b // one statement, `a + b`
+/// ```
+///
+/// A binary operator at the *start* of a line does not continue the
+/// previous line, since (e.g.) `- b` could as well be meant as a new
+/// statement. We report an error rather than guess.
+#[derive(new, DebugWith)]
+crate struct BlockStatements<'me, 'parse> {
+    scope: &'me mut ExpressionScope<'parse>,
+}
+
+impl Syntax<'parse> for BlockStatements<'me, 'parse> {
+    type Data = (Seq<ParsedStatement>, Option<Span<FileName>>);
+
+    fn test(&mut self, _parser: &Parser<'parse>) -> bool {
+        true // we never produce an error
+    }
+
+    fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
+        let mut statements = vec![];
+        let mut trailing_semicolon = None;
+        parser.skip_newlines();
+        while let Some(statement) = parser.parse_if_present(Statement::new(self.scope)) {
+            statements.push(statement?);
+
+            if let Some(semicolon) = parser.parse_if_present(Semicolon) {
+                trailing_semicolon = Some(semicolon?.span);
+                parser.skip_newlines();
+            } else if parser.skip_newlines() {
+                trailing_semicolon = None;
+                reject_leading_operator(parser);
+            } else {
+                trailing_semicolon = None;
+                break;
+            }
+        }
+
+        Ok((Seq::from(statements), trailing_semicolon))
+    }
+}
+
+/// Reports an error for (and skips) a binary operator at the start of
+/// a line; see `BlockStatements`.
+fn reject_leading_operator(parser: &mut Parser<'_>) {
+    let is_operator = [
+        BINARY_OPERATORS_EXPR3,
+        BINARY_OPERATORS_EXPR4,
+        BINARY_OPERATORS_EXPR5,
+    ]
+    .iter()
+    .any(|&operators| parser.test(BinaryOperator::new(operators)));
+
+    if is_operator {
+        let operator = parser.shift();
+        parser.report_error(
+            "a binary operator cannot start a line; move it to the end of the previous line",
+            operator.span,
+        );
+    }
+}
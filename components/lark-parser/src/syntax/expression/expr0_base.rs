@@ -6,7 +6,7 @@ use crate::syntax::expression::scope::ExpressionScope;
 use crate::syntax::expression::ParsedExpression;
 use crate::syntax::expression::{Expression, HirExpression};
 use crate::syntax::identifier::SpannedLocalIdentifier;
-use crate::syntax::sigil::Parentheses;
+use crate::syntax::sigil::{Else, If, Parentheses};
 use crate::syntax::skip_newline::SkipNewline;
 use crate::syntax::Syntax;
 use derive_new::new;
@@ -14,12 +14,50 @@ use lark_debug_derive::DebugWith;
 use lark_error::ErrorReported;
 use lark_hir as hir;
 use lark_intern::Intern;
+use lark_span::{FileName, Span};
 
 #[derive(new, DebugWith)]
 crate struct Expression0<'me, 'parse> {
     scope: &'me mut ExpressionScope<'parse>,
 }
 
+impl Expression0<'me, 'parse> {
+    /// Parses the rest of an `if` expression, once the `if` keyword
+    /// (at `if_span`) has been consumed. In an `else if` chain, each
+    /// `if` becomes the `if_false` of the previous one directly,
+    /// rather than being wrapped in a block of its own.
+    fn expect_if(
+        &mut self,
+        parser: &mut Parser<'parse>,
+        if_span: Span<FileName>,
+    ) -> Result<hir::Expression, ErrorReported> {
+        let condition = parser.expect(HirExpression::new(self.scope))?;
+        let if_true = parser.expect(Block::new(self.scope))?;
+        let if_false = if let Some(else_keyword) = parser.parse_if_present(Else) {
+            else_keyword?;
+            if let Some(if_keyword) = parser.parse_if_present(If) {
+                let if_keyword = if_keyword?;
+                self.expect_if(parser, if_keyword.span)?
+            } else {
+                parser.expect(Block::new(self.scope))?
+            }
+        } else if let Some(b) = parser.parse_if_present(Block::new(self.scope)) {
+            b?
+        } else {
+            self.scope.unit_expression(parser.elided_span())
+        };
+
+        Ok(self.scope.add(
+            if_span,
+            hir::ExpressionData::If {
+                condition,
+                if_true,
+                if_false,
+            },
+        ))
+    }
+}
+
 impl Syntax<'parse> for Expression0<'me, 'parse> {
     type Data = ParsedExpression;
 
@@ -30,28 +68,13 @@ impl Syntax<'parse> for Expression0<'me, 'parse> {
     fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
         // Expression0 = Identifier
         // Expression0 = "if" Expression Block [ "else" Block ]
+        // Expression0 = "if" Expression Block "else" "if" ...
         if parser.test(SpannedLocalIdentifier) {
             let text = parser.expect(SpannedLocalIdentifier)?;
 
             // FIXME generalize this to any macro
             if text.value == "if" {
-                let condition = parser.expect(HirExpression::new(self.scope))?;
-                let if_true = parser.expect(Block::new(self.scope))?;
-                let if_false = if let Some(b) = parser.parse_if_present(Block::new(self.scope)) {
-                    b?
-                } else {
-                    self.scope.unit_expression(parser.elided_span())
-                };
-
-                let expression = self.scope.add(
-                    text.span,
-                    hir::ExpressionData::If {
-                        condition,
-                        if_true,
-                        if_false,
-                    },
-                );
-
+                let expression = self.expect_if(parser, text.span)?;
                 return Ok(ParsedExpression::Expression(expression));
             }
 
@@ -64,6 +87,13 @@ impl Syntax<'parse> for Expression0<'me, 'parse> {
 
             let id = text.value.intern(&self.scope.db);
             if let Some(entity) = self.scope.db.resolve_name(self.scope.item_entity, id) {
+                if let Some(message) = self.scope.db.entity_deprecation(entity) {
+                    parser.report_warning(
+                        format!("`{}` is deprecated: {}", text.value, message),
+                        text.span,
+                    );
+                }
+
                 let place = self.scope.add(text.span, hir::PlaceData::Entity(entity));
                 return Ok(ParsedExpression::Place(place));
             }
@@ -7,6 +7,8 @@ use lark_debug_derive::DebugWith;
 use lark_error::ErrorReported;
 use lark_hir as hir;
 use lark_intern::Intern;
+use lark_span::Span;
+use std::ops::Range;
 
 #[derive(new, DebugWith)]
 crate struct Literal<'me, 'parse> {
@@ -28,10 +30,107 @@ impl Syntax<'parse> for Literal<'me, 'parse> {
             LexToken::String => hir::LiteralKind::String,
             _ => return Err(parser.report_error("expected a literal", token.span)),
         };
-        let value = text.intern(parser);
+
+        let value = match kind {
+            hir::LiteralKind::String => match decode_string(text) {
+                Ok(decoded) => decoded.as_str().intern(parser),
+                Err((range, message)) => {
+                    let start = token.span.start().to_usize();
+                    let escape_span =
+                        Span::new(token.span.file(), start + range.start, start + range.end);
+                    parser.report_error(message, escape_span);
+                    return Ok(self
+                        .scope
+                        .already_reported_error_expression(token.span, hir::ErrorData::Misc));
+                }
+            },
+            hir::LiteralKind::UnsignedInteger => text.intern(parser),
+        };
         let data = hir::LiteralData { kind, value };
         Ok(self
             .scope
             .add(token.span, hir::ExpressionData::Literal { data }))
     }
 }
+
+/// Decodes the text of a string literal (including its quotes) into
+/// the string it denotes: the quotes are removed and each escape
+/// (`\n`, `\t`, `\r`, `\0`, `\\`, `\'`, `\"`, or `\u{...}`) is
+/// replaced with the character it stands for. On error, returns the
+/// range of the bad escape within `text`.
+fn decode_string(text: &str) -> Result<String, (Range<usize>, String)> {
+    // The lexer only produces string tokens that are quoted at both ends.
+    let contents = 1..text.len() - 1;
+    let mut decoded = String::with_capacity(contents.len());
+    let mut chars = text[contents.clone()]
+        .char_indices()
+        .map(|(index, c)| (index + contents.start, c))
+        .peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some((_, 'u')) => {}
+            Some((next_index, next)) => {
+                decoded.push(match next {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    '\\' | '\'' | '"' => next,
+                    _ => {
+                        let end = next_index + next.len_utf8();
+                        return Err((
+                            start..end,
+                            format!("unknown escape `{}`", &text[start..end]),
+                        ));
+                    }
+                });
+                continue;
+            }
+            None => {
+                return Err((start..start + 1, "incomplete escape".to_string()));
+            }
+        }
+
+        let malformed = |end| (start..end, "malformed unicode escape".to_string());
+
+        // Just past the `u`.
+        let open = start + 2;
+        if !text[open..contents.end].starts_with('{') {
+            return Err(malformed(open));
+        }
+        let end = match text[open..contents.end].find('}') {
+            Some(close) => open + close + 1,
+            None => return Err(malformed(open + 1)),
+        };
+
+        let digits = &text[open + 1..end - 1];
+        if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|d| d.is_ascii_hexdigit()) {
+            return Err(malformed(end));
+        }
+
+        let value = u32::from_str_radix(digits, 16).unwrap();
+        match std::char::from_u32(value) {
+            Some(escaped) => decoded.push(escaped),
+            None => {
+                return Err((
+                    start..end,
+                    format!("invalid unicode code point `{}`", &text[start..end]),
+                ));
+            }
+        }
+
+        while let Some(&(index, _)) = chars.peek() {
+            if index >= end {
+                break;
+            }
+            chars.next();
+        }
+    }
+
+    Ok(decoded)
+}
@@ -1,13 +1,15 @@
+use crate::edit_distance;
 use crate::parser::Parser;
 use crate::ParserDatabase;
 use lark_collections::FxIndexMap;
 use lark_debug_with::DebugWith;
 use lark_entity::Entity;
+use lark_entity::EntityData;
 use lark_hir as hir;
 use lark_intern::{Intern, Untern};
 use lark_span::FileName;
 use lark_span::Span;
-use lark_string::{GlobalIdentifier, GlobalIdentifierTables};
+use lark_string::{GlobalIdentifier, GlobalIdentifierTables, Text};
 use std::rc::Rc;
 
 crate struct ExpressionScope<'parse> {
@@ -21,6 +23,10 @@ crate struct ExpressionScope<'parse> {
     crate variables: Rc<FxIndexMap<GlobalIdentifier, hir::Variable>>,
 
     crate fn_body_tables: hir::FnBodyTables,
+
+    /// How many expressions we are currently nested inside of; see
+    /// the `expression_depth_limit` input.
+    crate depth: usize,
 }
 
 impl ExpressionScope<'parse> {
@@ -66,22 +72,63 @@ impl ExpressionScope<'parse> {
         span: Span<FileName>,
         data: hir::ErrorData,
     ) -> hir::Expression {
-        let message = match data {
-            hir::ErrorData::Misc => "error".to_string(),
-            hir::ErrorData::Unimplemented => "unimplemented".to_string(),
-            hir::ErrorData::CanOnlyConstructStructs => {
-                "can only supply named arguments when constructing structs".to_string()
-            }
+        let (message, suggestion) = match data {
+            hir::ErrorData::Misc => ("error".to_string(), None),
+            hir::ErrorData::Unimplemented => ("unimplemented".to_string(), None),
+            hir::ErrorData::CanOnlyConstructStructs => (
+                "can only supply named arguments when constructing structs".to_string(),
+                None,
+            ),
             hir::ErrorData::UnknownIdentifier { text } => {
-                format!("unknown identifier `{}`", text.untern(&self.db))
+                let text = text.untern(&self.db);
+                (
+                    format!("unknown identifier `{}`", text),
+                    self.similar_name(&text),
+                )
             }
         };
 
-        parser.report_error(message, span);
+        match suggestion {
+            Some(suggestion) => {
+                parser.report_error_with_suggestion(message, suggestion.to_string(), span);
+            }
+            None => {
+                parser.report_error(message, span);
+            }
+        }
 
         self.already_reported_error_expression(span, data)
     }
 
+    /// Finds a name that is in scope and "close" to `text`, for use
+    /// in "did you mean" suggestions. Considers the variables in scope
+    /// as well as the items declared in the enclosing file.
+    fn similar_name(&self, text: &str) -> Option<Text> {
+        let mut candidates: Vec<Text> = self
+            .variables
+            .keys()
+            .map(|name| name.untern(&self.db))
+            .collect();
+
+        if let Some(file) = self.db.declaring_file(self.item_entity) {
+            let file_entity = EntityData::InputFile { file }.intern(&self.db);
+            for entity in self.db.child_entities(file_entity).iter() {
+                match entity.untern(&self.db) {
+                    EntityData::ItemName { id, .. } | EntityData::MemberName { id, .. } => {
+                        candidates.push(id.untern(&self.db));
+                    }
+
+                    EntityData::LangItem(_)
+                    | EntityData::Error(_)
+                    | EntityData::InputFile { .. } => {}
+                }
+            }
+        }
+
+        edit_distance::closest_match(text, candidates.iter().map(|c| &c[..]), 2)
+            .map(Text::from)
+    }
+
     crate fn already_reported_error_expression(
         &mut self,
         span: Span<FileName>,
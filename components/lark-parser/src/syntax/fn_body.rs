@@ -84,12 +84,15 @@ use std::sync::Arc;
 //   `(` \n* Expression \n* `)`,  // Should we allow newlines *anywhere* here?
 //   Block,
 //   "if" Expression Block [ "else" Block ]
+//   "if" Expression Block "else" "if" ...  // `else if` chain
 // }
 //
 // Block = {
 //   `{` Statement* \n* `}`
 // }
 //
+// (If the final statement is terminated by `;`, the block is unit.)
+//
 // Statement = {
 //   \n* Expression Terminator,
 //   \n* `let` Identifier [`:` Ty ] `=` Expression Terminator,
@@ -118,6 +121,7 @@ crate fn parse_fn_body(
         item_entity,
         variables: Default::default(),
         fn_body_tables: Default::default(),
+        depth: 0,
     };
 
     let arguments: Vec<_> = self_argument
@@ -137,7 +141,7 @@ crate fn parse_fn_body(
         .collect();
     let arguments = hir::List::from_iterator(&mut scope.fn_body_tables, arguments);
 
-    let file_name = item_entity.input_file(&db).unwrap();
+    let file_name = db.declaring_file(item_entity).unwrap();
     let mut parser = Parser::new(file_name, db, entity_macro_definitions, input, tokens, 0);
 
     let root_expression = match parser.expect(HirExpression::new(&mut scope)) {
@@ -3,6 +3,7 @@ use crate::syntax::delimited::Delimited;
 use crate::syntax::entity::ErrorParsedEntity;
 use crate::syntax::entity::LazyParsedEntity;
 use crate::syntax::fn_body;
+use crate::syntax::generics::{ParsedConstraint, WhereClause};
 use crate::syntax::guard::Guard;
 use crate::syntax::identifier::SpannedGlobalIdentifier;
 use crate::syntax::list::CommaList;
@@ -22,7 +23,6 @@ use lark_error::ErrorReported;
 use lark_error::ResultExt;
 use lark_error::WithError;
 use lark_hir as hir;
-use lark_intern::Untern;
 use lark_span::FileName;
 use lark_span::Spanned;
 use lark_string::GlobalIdentifier;
@@ -36,6 +36,7 @@ pub struct FunctionSignature;
 pub struct ParsedFunctionSignature {
     pub parameters: Seq<Spanned<ParsedField, FileName>>,
     pub return_type: ParsedTypeReference,
+    pub where_clause: Seq<ParsedConstraint>,
     pub body: Result<Spanned<ParsedMatch, FileName>, ErrorReported>,
 }
 
@@ -58,11 +59,17 @@ impl Syntax<'parse> for FunctionSignature {
             None => ParsedTypeReference::Elided(parser.elided_span()),
         };
 
+        let where_clause = match parser.parse_if_present(SkipNewline(WhereClause)) {
+            Some(constraints) => constraints.unwrap_or_else(|ErrorReported(_)| Seq::default()),
+            None => Seq::default(),
+        };
+
         let body = parser.expect(SkipNewline(Matched(Curlies)));
 
         Ok(ParsedFunctionSignature {
             parameters,
             return_type,
+            where_clause,
             body,
         })
     }
@@ -113,7 +120,7 @@ impl ParsedFunctionSignature {
                         end_token,
                     },
             }) => {
-                let file_name = entity.untern(&db).file_name(&db).unwrap();
+                let file_name = db.declaring_file(entity).unwrap();
                 let input = db.file_text(file_name);
                 let tokens = db
                     .file_tokens(file_name)
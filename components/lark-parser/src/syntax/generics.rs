@@ -0,0 +1,150 @@
+use crate::parser::Parser;
+use crate::syntax::delimited::Delimited;
+use crate::syntax::guard::Guard;
+use crate::syntax::identifier::SpannedGlobalIdentifier;
+use crate::syntax::list::CommaList;
+use crate::syntax::sigil::{Colon, Squares, Where};
+use crate::syntax::skip_newline::SkipNewline;
+use crate::syntax::{NonEmptySyntax, Syntax};
+use crate::ParserDatabase;
+
+use lark_collections::{FxIndexMap, IndexVec, Seq};
+use lark_debug_derive::DebugWith;
+use lark_entity::Entity;
+use lark_error::{ErrorReported, WithError};
+use lark_intern::Untern;
+use lark_span::{FileName, Spanned};
+use lark_string::GlobalIdentifier;
+use lark_ty as ty;
+use std::sync::Arc;
+
+/// Parses the (optional) type parameters of an item:
+///
+/// ```ignore
+/// `[` <id> `]` // separated by `,` or newline
+/// ```
+#[derive(DebugWith)]
+pub struct GenericParameters;
+
+impl Syntax<'parse> for GenericParameters {
+    type Data = Seq<Spanned<GlobalIdentifier, FileName>>;
+
+    fn test(&mut self, parser: &Parser<'parse>) -> bool {
+        parser.test(Delimited(Squares, CommaList(SpannedGlobalIdentifier)))
+    }
+
+    fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
+        parser.expect(Delimited(Squares, CommaList(SpannedGlobalIdentifier)))
+    }
+}
+
+impl NonEmptySyntax<'parse> for GenericParameters {}
+
+/// Parses the (optional) constraints on the type parameters of an
+/// item:
+///
+/// ```ignore
+/// `where` <id> `:` <id> // separated by `,` or newline
+/// ```
+#[derive(DebugWith)]
+pub struct WhereClause;
+
+impl Syntax<'parse> for WhereClause {
+    type Data = Seq<ParsedConstraint>;
+
+    fn test(&mut self, parser: &Parser<'parse>) -> bool {
+        parser.test(Where)
+    }
+
+    fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
+        parser.expect(Guard(Where, CommaList(Constraint)))
+    }
+}
+
+impl NonEmptySyntax<'parse> for WhereClause {}
+
+/// A single `T: Bound` entry in a `where` clause.
+#[derive(Copy, Clone, DebugWith)]
+pub struct ParsedConstraint {
+    pub parameter: Spanned<GlobalIdentifier, FileName>,
+    pub bound: Spanned<GlobalIdentifier, FileName>,
+}
+
+#[derive(DebugWith)]
+struct Constraint;
+
+impl Syntax<'parse> for Constraint {
+    type Data = ParsedConstraint;
+
+    fn test(&mut self, parser: &Parser<'parse>) -> bool {
+        parser.test(SpannedGlobalIdentifier)
+    }
+
+    fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Self::Data, ErrorReported> {
+        let parameter = parser.expect(SpannedGlobalIdentifier)?;
+        let bound = parser.expect(SkipNewline(Guard(
+            Colon,
+            SkipNewline(SpannedGlobalIdentifier),
+        )))?;
+        Ok(ParsedConstraint { parameter, bound })
+    }
+}
+
+/// Converts the parsed type parameters and `where` clause of `entity`
+/// into its generic declarations, reporting any duplicated names and
+/// any constraints on names that are not type parameters.
+crate fn generic_declarations(
+    entity: Entity,
+    db: &dyn ParserDatabase,
+    parameters: &[Spanned<GlobalIdentifier, FileName>],
+    where_clause: &[ParsedConstraint],
+) -> WithError<Result<Arc<ty::GenericDeclarations>, ErrorReported>> {
+    let mut errors = vec![];
+    let mut declarations = IndexVec::default();
+    let mut bound_vars = FxIndexMap::default();
+
+    for parameter in parameters {
+        if bound_vars.contains_key(&parameter.value) {
+            errors.push(crate::diagnostic(
+                format!(
+                    "duplicate type parameter `{}`",
+                    parameter.value.untern(&db)
+                ),
+                parameter.span,
+            ));
+            continue;
+        }
+
+        let bound_var = declarations.push(ty::GenericKind::Ty(ty::GenericTyDeclaration {
+            def_id: entity,
+            name: parameter.value,
+        }));
+        bound_vars.insert(parameter.value, bound_var);
+    }
+
+    let mut constraints = vec![];
+    for constraint in where_clause {
+        match bound_vars.get(&constraint.parameter.value) {
+            Some(&parameter) => constraints.push(ty::GenericConstraint {
+                parameter,
+                bound: constraint.bound.value,
+            }),
+            None => errors.push(crate::diagnostic(
+                format!(
+                    "unknown type parameter `{}`",
+                    constraint.parameter.value.untern(&db)
+                ),
+                constraint.parameter.span,
+            )),
+        }
+    }
+
+    WithError {
+        value: Ok(Arc::new(ty::GenericDeclarations {
+            parent_item: None,
+            declarations,
+            constraints: Seq::from(constraints),
+        })),
+        errors,
+    }
+}
@@ -4,6 +4,7 @@ use crate::syntax::Syntax;
 use lark_collections::Seq;
 use lark_debug_derive::DebugWith;
 use lark_error::ErrorReported;
+use lark_span::Span;
 
 #[derive(DebugWith)]
 pub struct CommaList<T>(pub T);
@@ -29,6 +30,28 @@ where
     }
 }
 
+/// Like `CommaList`, but for the arguments to a call: a missing comma
+/// between two arguments (e.g., `f(1 2)`) is reported and otherwise
+/// ignored, so that both arguments are still parsed.
+#[derive(DebugWith)]
+pub struct ArgumentList<T>(pub T);
+
+impl<T> Syntax<'parse> for ArgumentList<T>
+where
+    T: Syntax<'parse>,
+{
+    type Data = Seq<T::Data>;
+
+    fn test(&mut self, _parser: &Parser<'parse>) -> bool {
+        true // we never produce an error
+    }
+
+    fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Seq<T::Data>, ErrorReported> {
+        let missing_comma = Some("expected `,` between arguments");
+        parse_separated(parser, &mut self.0, Comma, missing_comma)
+    }
+}
+
 /// Parses a "list" of things. In general, lists in Lark can be
 /// separated either by some given sigil (the `S`) *or* by a newline
 /// (or both). Expects to be called immediately after the "opening
@@ -73,26 +96,50 @@ where
 
     fn expect(&mut self, parser: &mut Parser<'parse>) -> Result<Seq<T::Data>, ErrorReported> {
         let SeparatedList(element, delimiter) = self;
+        parse_separated(parser, element, delimiter, None)
+    }
+}
+
+/// The guts of `SeparatedList`. If `missing_delimiter` is given, an
+/// element that directly follows another (with neither a delimiter nor
+/// a newline between them) is accepted, and the message is reported
+/// at the gap between the two.
+fn parse_separated<T, S>(
+    parser: &mut Parser<'parse>,
+    mut element: T,
+    mut delimiter: S,
+    missing_delimiter: Option<&'static str>,
+) -> Result<Seq<T::Data>, ErrorReported>
+where
+    T: Syntax<'parse>,
+    S: Syntax<'parse>,
+{
+    let mut result = vec![];
+    parser.skip_newlines();
+    loop {
+        if let Some(data) = parser.parse_if_present(&mut element) {
+            result.push(data?);
 
-        let mut result = vec![];
-        parser.skip_newlines();
-        loop {
-            if let Some(element) = parser.parse_if_present(&mut *element) {
-                result.push(element?);
-
-                if let Some(_) = parser.parse_if_present(&mut *delimiter) {
-                    parser.skip_newlines();
-                    continue;
-                } else if parser.skip_newlines() {
-                    continue;
-                } else {
-                    break;
-                }
+            if let Some(_) = parser.parse_if_present(&mut delimiter) {
+                parser.skip_newlines();
+                continue;
+            } else if parser.skip_newlines() {
+                continue;
+            } else if let Some(message) = missing_delimiter.filter(|_| parser.test(&mut element)) {
+                let gap = Span::new(
+                    parser.last_span().file(),
+                    parser.last_span().end(),
+                    parser.peek_span().start(),
+                );
+                parser.report_error(message, gap);
+                continue;
             } else {
                 break;
             }
+        } else {
+            break;
         }
-
-        Ok(Seq::from(result))
     }
+
+    Ok(Seq::from(result))
 }
@@ -4,6 +4,7 @@ use crate::syntax::entity::LazyParsedEntity;
 use crate::syntax::entity::ParsedEntity;
 use crate::syntax::fn_signature::FunctionSignature;
 use crate::syntax::fn_signature::ParsedFunctionSignature;
+use crate::syntax::generics;
 use crate::syntax::guard::Guard;
 use crate::syntax::identifier::SpannedGlobalIdentifier;
 use crate::syntax::sigil::Colon;
@@ -19,7 +20,6 @@ use lark_error::ErrorSentinel;
 use lark_error::ResultExt;
 use lark_error::WithError;
 use lark_hir as hir;
-use lark_intern::Intern;
 use lark_intern::Untern;
 use lark_span::FileName;
 use lark_span::Spanned;
@@ -112,10 +112,12 @@ impl LazyParsedEntity for ParsedMethod {
 
     fn parse_generic_declarations(
         &self,
-        _entity: Entity,
-        _db: &dyn ParserDatabase,
+        entity: Entity,
+        db: &dyn ParserDatabase,
     ) -> WithError<Result<Arc<ty::GenericDeclarations>, ErrorReported>> {
-        WithError::ok(Ok(ty::GenericDeclarations::empty(None)))
+        // Methods have no type parameters of their own, so any `where`
+        // clause constraints are reported as unknown.
+        generics::generic_declarations(entity, db, &[], &self.signature.where_clause)
     }
 
     fn parse_type(
@@ -152,7 +154,7 @@ impl LazyParsedEntity for ParsedMethod {
     }
 
     fn parse_fn_body(&self, entity: Entity, db: &dyn ParserDatabase) -> WithError<hir::FnBody> {
-        let self_argument: GlobalIdentifier = "self".intern(&db);
+        let self_argument = db.well_known_identifiers().self_;
         let spanned_self_argument = Spanned {
             value: self_argument,
             span: self.name.span,
@@ -163,7 +165,7 @@ impl LazyParsedEntity for ParsedMethod {
 }
 
 /// Represents a parse of something like `foo: Type`
-#[derive(Copy, Clone, DebugWith)]
+#[derive(Clone, DebugWith)]
 pub struct ParsedField {
     pub name: Spanned<GlobalIdentifier, FileName>,
     pub ty: ParsedTypeReference,
@@ -54,12 +54,28 @@ sigil_type! {
     pub struct RightArrow = (LexToken::Sigil, "->");
     pub struct Dot = (LexToken::Sigil, ".");
     pub struct Let = (LexToken::Identifier, "let");
+    pub struct If = (LexToken::Identifier, "if");
+    pub struct Else = (LexToken::Identifier, "else");
+    pub struct Where = (LexToken::Identifier, "where");
     pub struct ExclamationPoint = (LexToken::Sigil, "!");
     pub struct Plus = (LexToken::Sigil, "+");
     pub struct Minus = (LexToken::Sigil, "-");
     pub struct Star = (LexToken::Sigil, "*");
     pub struct Slash = (LexToken::Sigil, "/");
     pub struct Equals = (LexToken::Sigil, "=");
+    pub struct At = (LexToken::Sigil, "@");
+    pub struct Cfg = (LexToken::Identifier, "cfg");
+    pub struct Deprecated = (LexToken::Identifier, "deprecated");
+}
+
+/// True if `text` is reserved by the language: one of the keyword
+/// sigils above, or the name of a built-in entity macro. (Attribute
+/// names like `cfg` are not reserved.)
+crate fn is_keyword(text: &str) -> bool {
+    match text {
+        Let::TEXT | If::TEXT | Else::TEXT | Where::TEXT | "def" | "struct" => true,
+        _ => false,
+    }
 }
 
 #[derive(DebugWith)]
@@ -93,3 +109,19 @@ impl Delimiter<'parse> for Parentheses {
         CloseParenthesis
     }
 }
+
+#[derive(DebugWith)]
+pub struct Squares;
+
+impl Delimiter<'parse> for Squares {
+    type Open = OpenSquare;
+    type Close = CloseSquare;
+
+    fn open_syntax(&self) -> Self::Open {
+        OpenSquare
+    }
+
+    fn close_syntax(&self) -> Self::Close {
+        CloseSquare
+    }
+}
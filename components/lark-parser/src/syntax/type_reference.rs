@@ -1,7 +1,11 @@
+use crate::lexer::token::LexToken;
 use crate::parser::Parser;
 use crate::syntax::identifier::SpannedGlobalIdentifier;
-use crate::syntax::Syntax;
+use crate::syntax::list::CommaList;
+use crate::syntax::sigil::{CloseSquare, OpenSquare};
+use crate::syntax::{NonEmptySyntax, Syntax};
 use crate::ParserDatabase;
+use lark_collections::Seq;
 use lark_debug_derive::DebugWith;
 use lark_entity::Entity;
 use lark_error::{ErrorReported, ErrorSentinel, WithError};
@@ -27,14 +31,50 @@ impl Syntax<'parse> for TypeReference {
         parser: &mut Parser<'parse>,
     ) -> Result<ParsedTypeReference, ErrorReported> {
         let identifier = parser.expect(SpannedGlobalIdentifier)?;
+
+        // Optional type arguments, like `[Int]` in `List[Int]`. If
+        // they are malformed, we report an error, skip past them, and
+        // keep the head alone.
+        let arguments = match parser.parse_if_present(OpenSquare) {
+            Some(open) => {
+                open?;
+                let arguments = parser.expect(CommaList(TypeReference));
+                if parser.parse_if_present(CloseSquare).is_some() {
+                    arguments.unwrap_or_else(|ErrorReported(_)| Seq::default())
+                } else {
+                    if arguments.is_ok() {
+                        parser.report_error("expected `]`", parser.peek_span());
+                    }
+                    skip_past_close_square(parser);
+                    Seq::default()
+                }
+            }
+            None => Seq::default(),
+        };
+
         Ok(ParsedTypeReference::Named(NamedTypeReference {
             identifier,
+            arguments,
         }))
     }
 }
 
+impl NonEmptySyntax<'parse> for TypeReference {}
+
+/// Skips tokens through the next `]`, stopping early at the end of
+/// the line.
+fn skip_past_close_square(parser: &mut Parser<'_>) {
+    while !parser.is(LexToken::EOF) && !parser.is(LexToken::Newline) {
+        let text = parser.peek_str();
+        parser.shift();
+        if text == CloseSquare::TEXT {
+            break;
+        }
+    }
+}
+
 /// Parsed form of a type.
-#[derive(Copy, Clone, DebugWith)]
+#[derive(Clone, DebugWith)]
 pub enum ParsedTypeReference {
     Named(NamedTypeReference),
     Elided(Span<FileName>),
@@ -63,10 +103,14 @@ impl<Cx> ErrorSentinel<Cx> for ParsedTypeReference {
     }
 }
 
-/// Named type like `String` or (eventually) `Vec<u32>`
-#[derive(Copy, Clone, DebugWith)]
+/// Named type like `String` or `List[Int]`
+#[derive(Clone, DebugWith)]
 pub struct NamedTypeReference {
+    /// The name of the type (the "head"), e.g. `List`.
     pub identifier: Spanned<GlobalIdentifier, FileName>,
+
+    /// The type arguments, e.g. `[Int]`; empty if there are none.
+    pub arguments: Seq<ParsedTypeReference>,
 }
 
 impl NamedTypeReference {
@@ -75,8 +119,41 @@ impl NamedTypeReference {
         entity: Entity,
         db: &dyn ParserDatabase,
     ) -> WithError<ty::Ty<Declaration>> {
+        // Type parameters in scope shadow items of the same name.
+        if let Some(bv) =
+            crate::type_conversion::resolve_type_parameter(db, entity, self.identifier.value)
+        {
+            if !self.arguments.is_empty() {
+                let msg = format!(
+                    "type parameter `{}` does not take type arguments",
+                    self.identifier.untern(&db)
+                );
+                return WithError::report_error(&db, msg, self.identifier.span);
+            }
+
+            return WithError::ok(ty::Ty {
+                base: Declaration::intern_bound_var(&db, bv),
+                repr: ty::ReprKind::Direct,
+                perm: Declaration::own_perm(&db),
+            });
+        }
+
         match db.resolve_name(entity, self.identifier.value) {
-            Some(entity) => {
+            Some(head) => {
+                // Type arguments are resolved in the same scope as
+                // the head.
+                let mut errors = vec![];
+                let generics: ty::Generics<Declaration> = self
+                    .arguments
+                    .iter()
+                    .map(|argument| {
+                        let ty = argument
+                            .parse_type(entity, db)
+                            .accumulate_errors_into(&mut errors);
+                        ty::GenericKind::Ty(ty)
+                    })
+                    .collect();
+
                 // FIXME(ndm) -- eventually, we will want some way to
                 // represent types with other permissions/reprs. We'll
                 // need fields on `NamedTypeReference`, and we'll need
@@ -85,12 +162,12 @@ impl NamedTypeReference {
                 // (the former might be more convenient).
                 let ty = crate::type_conversion::declaration_ty_named(
                     &db,
-                    entity,
+                    head,
                     ty::declaration::DeclaredPermKind::Own,
                     ty::ReprKind::Direct,
-                    ty::Generics::empty(),
+                    generics,
                 );
-                WithError::ok(ty)
+                WithError { value: ty, errors }
             }
             None => {
                 let msg = format!("unknown type: `{}`", self.identifier.untern(&db));
@@ -0,0 +1,71 @@
+use crate::LexToken;
+use crate::ParserDatabase;
+use lark_span::{FileName, Spanned};
+use std::ops::Range;
+
+/// The tokens that changed between two versions of a file: the
+/// tokens in `removed` (indices into the old tokens) were replaced by
+/// those in `inserted` (indices into the new tokens). Either range may
+/// be empty; both are empty if nothing changed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TokenDiff {
+    pub removed: Range<usize>,
+    pub inserted: Range<usize>,
+}
+
+crate fn token_diff(
+    db: &impl ParserDatabase,
+    file_name: FileName,
+    old_revision: u64,
+    new_revision: u64,
+) -> TokenDiff {
+    let old_tokens = db.file_tokens_at_revision(file_name, old_revision);
+    let new_tokens = db.file_tokens_at_revision(file_name, new_revision);
+    TokenDiff::between(
+        &db.file_text_at_revision(file_name, old_revision),
+        &old_tokens.value,
+        &db.file_text_at_revision(file_name, new_revision),
+        &new_tokens.value,
+    )
+}
+
+impl TokenDiff {
+    /// Compares two lexings of a file (along with the text they were
+    /// lexed from).
+    ///
+    /// Tokens are compared by kind and text, ignoring their spans, so
+    /// that an edit doesn't count as changing all the tokens after it.
+    /// The diff is the single range between the longest common prefix
+    /// and suffix, which is minimal for a typical edit (e.g., typing a
+    /// character).
+    fn between(
+        old_text: &str,
+        old_tokens: &[Spanned<LexToken, FileName>],
+        new_text: &str,
+        new_tokens: &[Spanned<LexToken, FileName>],
+    ) -> TokenDiff {
+        let same = |old: &Spanned<LexToken, FileName>, new: &Spanned<LexToken, FileName>| {
+            old.value == new.value && old_text[old.span] == new_text[new.span]
+        };
+
+        let prefix = old_tokens
+            .iter()
+            .zip(new_tokens)
+            .take_while(|(old, new)| same(old, new))
+            .count();
+
+        let max_suffix = old_tokens.len().min(new_tokens.len()) - prefix;
+        let suffix = old_tokens
+            .iter()
+            .rev()
+            .zip(new_tokens.iter().rev())
+            .take(max_suffix)
+            .take_while(|(old, new)| same(old, new))
+            .count();
+
+        TokenDiff {
+            removed: prefix..old_tokens.len() - suffix,
+            inserted: prefix..new_tokens.len() - suffix,
+        }
+    }
+}
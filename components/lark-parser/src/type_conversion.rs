@@ -3,6 +3,7 @@ use lark_debug_with::DebugWith;
 use lark_entity::{Entity, EntityData, LangItem};
 use lark_error::{ErrorReported, ErrorSentinel, WithError};
 use lark_intern::{Intern, Untern};
+use lark_string::GlobalIdentifier;
 use lark_ty as ty;
 use lark_ty::declaration::Declaration;
 use lark_ty::declaration::DeclarationTables;
@@ -122,6 +123,15 @@ crate fn signature(
     }
 }
 
+crate fn parameter_type(
+    db: &impl ParserDatabase,
+    entity: Entity,
+    index: usize,
+) -> Option<ty::Ty<Declaration>> {
+    let signature = db.signature(entity).into_value().ok()?;
+    signature.inputs.get(index).cloned()
+}
+
 crate fn unit_ty(db: &dyn ParserDatabase) -> ty::Ty<Declaration> {
     declaration_ty_named(
         &db,
@@ -148,6 +158,68 @@ crate fn declaration_ty_named(
     }
 }
 
+/// The generics to use when referring to an item from within its own
+/// definition: each of its type parameters, in order.
+crate fn bound_var_generics(
+    db: &dyn AsRef<DeclarationTables>,
+    generic_declarations: &ty::GenericDeclarations,
+) -> ty::Generics<Declaration> {
+    generic_declarations
+        .declarations
+        .indices()
+        .map(|bv| {
+            ty::GenericKind::Ty(ty::Ty {
+                base: Declaration::intern_bound_var(db, bv),
+                repr: ty::ReprKind::Direct,
+                perm: Declaration::own_perm(db),
+            })
+        })
+        .collect()
+}
+
+/// If `name` is a type parameter declared on `entity` or on one of
+/// the items enclosing it, returns the corresponding bound variable.
+crate fn resolve_type_parameter(
+    db: &dyn ParserDatabase,
+    entity: Entity,
+    name: GlobalIdentifier,
+) -> Option<ty::BoundVar> {
+    let mut scope = Some(entity);
+    while let Some(entity) = scope {
+        let entity_data = entity.untern(&db);
+        match entity_data {
+            EntityData::ItemName { .. } | EntityData::MemberName { .. } => {
+                if let Ok(generic_declarations) = db.generic_declarations(entity).into_value() {
+                    let bound_var = generic_declarations
+                        .declarations
+                        .iter_enumerated()
+                        .filter_map(|(bv, declaration)| match declaration {
+                            ty::GenericKind::Ty(declaration) => {
+                                if declaration.name == name {
+                                    Some(bv)
+                                } else {
+                                    None
+                                }
+                            }
+                        })
+                        .next();
+                    if bound_var.is_some() {
+                        return bound_var;
+                    }
+                }
+            }
+
+            EntityData::InputFile { .. } | EntityData::LangItem(_) | EntityData::Error(_) => {
+                return None;
+            }
+        }
+
+        scope = entity_data.parent();
+    }
+
+    None
+}
+
 //fn declaration_ty_from_ast_ty(
 //    db: &impl ParserDatabase,
 //    scope_entity: Entity,
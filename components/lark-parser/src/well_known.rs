@@ -0,0 +1,55 @@
+use lark_debug_derive::DebugWith;
+use lark_intern::Intern;
+use lark_string::GlobalIdentifier;
+use lark_string::GlobalIdentifierTables;
+
+/// Interned identifiers for names that the parser and name resolution
+/// compare against frequently (keywords and builtin types). Obtain
+/// these via the `well_known_identifiers` query rather than interning
+/// the strings anew each time; the ids are the same either way.
+#[derive(Copy, Clone, Debug, DebugWith, PartialEq, Eq, Hash)]
+pub struct WellKnownIdentifiers {
+    pub self_: GlobalIdentifier,
+    pub def: GlobalIdentifier,
+    pub struct_: GlobalIdentifier,
+    pub let_: GlobalIdentifier,
+    pub bool: GlobalIdentifier,
+    pub int: GlobalIdentifier,
+    pub uint: GlobalIdentifier,
+    pub false_: GlobalIdentifier,
+    pub true_: GlobalIdentifier,
+    pub debug: GlobalIdentifier,
+    pub string: GlobalIdentifier,
+
+    // Names of attributes checked for via `entity_annotations`.
+    pub must_use: GlobalIdentifier,
+
+    // Names of the methods that overload the arithmetic operators.
+    pub add: GlobalIdentifier,
+    pub sub: GlobalIdentifier,
+    pub mul: GlobalIdentifier,
+    pub div: GlobalIdentifier,
+}
+
+impl WellKnownIdentifiers {
+    crate fn new(db: &dyn AsRef<GlobalIdentifierTables>) -> Self {
+        WellKnownIdentifiers {
+            self_: "self".intern(db),
+            def: "def".intern(db),
+            struct_: "struct".intern(db),
+            let_: "let".intern(db),
+            bool: "bool".intern(db),
+            int: "int".intern(db),
+            uint: "uint".intern(db),
+            false_: "false".intern(db),
+            true_: "true".intern(db),
+            debug: "debug".intern(db),
+            string: "String".intern(db),
+            must_use: "must_use".intern(db),
+            add: "add".intern(db),
+            sub: "sub".intern(db),
+            mul: "mul".intern(db),
+            div: "div".intern(db),
+        }
+    }
+}
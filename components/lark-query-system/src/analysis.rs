@@ -0,0 +1,94 @@
+//! Runs the whole compiler pipeline over a set of files at once, for
+//! profiling. See `analyze_files`.
+
+use crate::ls_ops::{Cancelled, LsDatabase};
+use crate::LarkDatabase;
+use lark_entity::EntityData;
+use lark_intern::Intern;
+use lark_parser::{ParserDatabase, ParserDatabaseExt};
+use lark_span::IntoFileName;
+use lark_type_check::TypeCheckDatabase;
+use std::time::{Duration, Instant};
+
+/// The outcome of `analyze_files`: how long each phase took across
+/// all the files, and how many diagnostics it reported.
+#[derive(Debug)]
+pub struct ProjectAnalysis {
+    /// The database that was used, so that callers can inspect the
+    /// results further.
+    pub db: LarkDatabase,
+
+    pub parse_time: Duration,
+    pub parse_diagnostics: usize,
+
+    /// Converting each entity's declarations and fn body into types
+    /// and HIR.
+    pub lower_time: Duration,
+    pub lower_diagnostics: usize,
+
+    pub type_check_time: Duration,
+    pub type_check_diagnostics: usize,
+}
+
+impl ProjectAnalysis {
+    pub fn total_time(&self) -> Duration {
+        self.parse_time + self.lower_time + self.type_check_time
+    }
+
+    pub fn total_diagnostics(&self) -> usize {
+        self.parse_diagnostics + self.lower_diagnostics + self.type_check_diagnostics
+    }
+}
+
+/// Loads the given `(file name, contents)` pairs into a single
+/// database and parses, lowers, and type-checks all of them, one
+/// phase at a time. Since the files share a database (and hence its
+/// interners), references between them resolve as they would in a
+/// real project.
+pub fn analyze_files(files: &[(String, String)]) -> ProjectAnalysis {
+    let mut db = LarkDatabase::default();
+    let file_names: Vec<_> = files
+        .iter()
+        .map(|(name, contents)| {
+            let file_name = name.into_file_name(&db);
+            db.add_file(file_name, contents.as_str());
+            file_name
+        })
+        .collect();
+
+    let start = Instant::now();
+    let mut parse_diagnostics = 0;
+    for &file_name in &file_names {
+        parse_diagnostics += db.parsed_file(file_name).errors.len();
+    }
+    let parse_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut lower_errors = vec![];
+    for &file_name in &file_names {
+        let file_entity = EntityData::InputFile { file: file_name }.intern(&db);
+        for &entity in db.descendant_entities(file_entity).iter() {
+            db.accumulate_errors_for_entity(entity, &mut lower_errors)
+                .unwrap_or_else(|Cancelled| panic!("cancelled"));
+        }
+    }
+    let lower_diagnostics = lower_errors.len();
+    let lower_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut type_check_diagnostics = 0;
+    for &file_name in &file_names {
+        type_check_diagnostics += db.type_check_file(file_name).errors.len();
+    }
+    let type_check_time = start.elapsed();
+
+    ProjectAnalysis {
+        db,
+        parse_time,
+        parse_diagnostics,
+        lower_time,
+        lower_diagnostics,
+        type_check_time,
+        type_check_diagnostics,
+    }
+}
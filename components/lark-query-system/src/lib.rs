@@ -1,20 +1,27 @@
 use language_reporting as l_r;
-use lark_actor::{Actor, LspResponse, QueryRequest};
+use languageserver_types::{DiagnosticSeverity, Range};
+use lark_actor::{Actor, LspResponse, Priority, QueryRequest};
 use lark_entity::EntityTables;
+use lark_error::Severity;
 use lark_intern::{Intern, Untern};
 use lark_parser::{ParserDatabase, ParserDatabaseExt};
 use lark_pretty_print::PrettyPrintDatabase;
 use lark_span::{ByteIndex, FileName, Span};
 use lark_string::{GlobalIdentifier, GlobalIdentifierTables, Text};
+use parking_lot::Mutex;
 use salsa::{Database, ParallelDatabase, Snapshot};
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
 use url::Url;
 
+mod analysis;
 pub mod ls_ops;
 use self::ls_ops::{Cancelled, LsDatabase};
 
+pub use self::analysis::{analyze_files, ProjectAnalysis};
+
 #[salsa::database(lark_parser::ParserStorage, lark_type_check::TypeCheckStorage)]
 pub struct LarkDatabase {
     runtime: salsa::Runtime<LarkDatabase>,
@@ -23,6 +30,14 @@ pub struct LarkDatabase {
     declaration_tables: Arc<lark_ty::declaration::DeclarationTables>,
     base_inferred_tables: Arc<lark_ty::base_inferred::BaseInferredTables>,
     full_inferred_tables: Arc<lark_ty::full_inferred::FullInferredTables>,
+
+    /// When `Some`, the queries executed so far; see
+    /// `record_executed_queries`.
+    executed_queries: Arc<Mutex<Option<Vec<String>>>>,
+
+    /// True while `executed_queries` is `Some`, so that `salsa_event`
+    /// need not take the lock when nothing is being recorded.
+    recording_executed_queries: Arc<AtomicBool>,
 }
 
 impl std::fmt::Debug for LarkDatabase {
@@ -41,6 +56,27 @@ impl LarkDatabase {
     pub fn untern_string(&self, id: GlobalIdentifier) -> Text {
         id.untern(self)
     }
+
+    /// Starts recording (the `Debug` representations of) the queries
+    /// that get executed -- that is, that are computed anew rather
+    /// than reused from a previous revision. Used by tests to check
+    /// what gets invalidated by an edit. Note that this records
+    /// executions (salsa's `WillExecute` events), not invalidations:
+    /// a query whose inputs changed is only recorded once something
+    /// asks for it again.
+    pub fn record_executed_queries(&self) {
+        *self.executed_queries.lock() = Some(vec![]);
+        self.recording_executed_queries
+            .store(true, Ordering::SeqCst);
+    }
+
+    /// Stops recording executed queries, returning those recorded
+    /// since `record_executed_queries` was called.
+    pub fn take_executed_queries(&self) -> Vec<String> {
+        self.recording_executed_queries
+            .store(false, Ordering::SeqCst);
+        self.executed_queries.lock().take().unwrap_or_default()
+    }
 }
 
 impl Default for LarkDatabase {
@@ -52,8 +88,12 @@ impl Default for LarkDatabase {
             declaration_tables: Default::default(),
             base_inferred_tables: Default::default(),
             full_inferred_tables: Default::default(),
+            executed_queries: Default::default(),
+            recording_executed_queries: Default::default(),
         };
         db.init_parser_db();
+        db.query_mut(lark_type_check::TypeCheckOpsLimitQuery)
+            .set((), lark_type_check::DEFAULT_TYPE_CHECK_OPS_LIMIT);
         db
     }
 }
@@ -62,6 +102,18 @@ impl Database for LarkDatabase {
     fn salsa_runtime(&self) -> &salsa::Runtime<LarkDatabase> {
         &self.runtime
     }
+
+    fn salsa_event(&self, event_fn: impl Fn() -> salsa::Event<Self>) {
+        if !self.recording_executed_queries.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if let Some(executed_queries) = &mut *self.executed_queries.lock() {
+            if let salsa::EventKind::WillExecute { database_key } = event_fn().kind {
+                executed_queries.push(format!("{:?}", database_key));
+            }
+        }
+    }
 }
 
 impl ParallelDatabase for LarkDatabase {
@@ -73,6 +125,8 @@ impl ParallelDatabase for LarkDatabase {
             declaration_tables: self.declaration_tables.clone(),
             base_inferred_tables: self.base_inferred_tables.clone(),
             full_inferred_tables: self.full_inferred_tables.clone(),
+            executed_queries: self.executed_queries.clone(),
+            recording_executed_queries: self.recording_executed_queries.clone(),
         })
     }
 }
@@ -165,16 +219,95 @@ pub struct QuerySystem {
     send_channel: Sender<LspResponse>,
     lark_db: LarkDatabase,
     needs_error_check: bool,
+
+    /// Counts the queries currently being answered on their own
+    /// thread; see `spawn_task`.
+    in_flight: InFlightLimit,
 }
 
+/// The default for `QuerySystem::with_max_in_flight`.
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 32;
+
 impl QuerySystem {
     pub fn new(send_channel: Sender<LspResponse>) -> QuerySystem {
+        QuerySystem::with_max_in_flight(send_channel, DEFAULT_MAX_IN_FLIGHT)
+    }
+
+    /// Creates a query system that answers at most `max_in_flight`
+    /// queries at a time (see `InFlightLimit`). Edits and
+    /// diagnostics are never limited, since they don't pile up: each
+    /// edit cancels the queries that were in flight, freeing their
+    /// slots.
+    pub fn with_max_in_flight(
+        send_channel: Sender<LspResponse>,
+        max_in_flight: usize,
+    ) -> QuerySystem {
         QuerySystem {
             send_channel,
             lark_db: LarkDatabase::default(),
             needs_error_check: false,
+            in_flight: InFlightLimit::new(max_in_flight),
         }
     }
+
+    /// Runs `task` on a new thread, counting it as in flight until it
+    /// completes.
+    fn spawn_task(&self, task: impl FnOnce() + Send + 'static) {
+        let in_flight = self.in_flight.start();
+        std::thread::spawn(move || {
+            let _in_flight = in_flight;
+            task()
+        });
+    }
+}
+
+/// Counts the queries in flight, so as to limit them. Low-priority
+/// queries may only fill half of the slots; once that many queries
+/// are in flight, they are turned away, leaving the remaining slots
+/// for high-priority ones.
+#[derive(Debug)]
+pub struct InFlightLimit {
+    count: Arc<AtomicUsize>,
+    max: usize,
+}
+
+impl InFlightLimit {
+    pub fn new(max: usize) -> InFlightLimit {
+        InFlightLimit {
+            count: Default::default(),
+            max,
+        }
+    }
+
+    /// True if there is no room for another query of the given
+    /// priority.
+    pub fn is_full(&self, priority: Priority) -> bool {
+        let max = match priority {
+            Priority::Low => self.max / 2,
+            Priority::High => self.max,
+        };
+        self.count.load(Ordering::SeqCst) >= max
+    }
+
+    /// Counts a query as in flight until the result is dropped.
+    pub fn start(&self) -> InFlight {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InFlight {
+            count: self.count.clone(),
+        }
+    }
+}
+
+/// Counts a query as in flight for as long as it is alive; see
+/// `InFlightLimit::start`.
+pub struct InFlight {
+    count: Arc<AtomicUsize>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl Actor for QuerySystem {
@@ -224,8 +357,16 @@ impl QuerySystem {
                         for (key, value) in errors {
                             let send_channel = send_channel.clone();
                             let url = Url::parse(&key).unwrap();
-                            let ranges_with_default =
-                                value.iter().map(|x| (x.range, x.label.clone())).collect();
+                            let ranges_with_default = value
+                                .iter()
+                                .map(|x| {
+                                    let severity = match x.severity {
+                                        Severity::Error => DiagnosticSeverity::Error,
+                                        Severity::Warning => DiagnosticSeverity::Warning,
+                                    };
+                                    (x.range, x.label.clone(), severity)
+                                })
+                                .collect();
                             send(
                                 send_channel,
                                 LspResponse::Diagnostics(url, ranges_with_default),
@@ -244,10 +385,29 @@ impl QuerySystem {
         let _killme = KillTheProcess;
         log::info!("process_message(message={:#?})", message);
 
+        if let Some(task_id) = message.task_id() {
+            let is_initialize = match message {
+                QueryRequest::Initialize(..) => true,
+                _ => false,
+            };
+            if !is_initialize && self.in_flight.is_full(message.priority()) {
+                log::warn!("rejecting task {}: too many queries in flight", task_id);
+                let send_channel = self.send_channel.clone();
+                send(
+                    send_channel,
+                    LspResponse::Error(task_id, "server busy".to_string()),
+                );
+                return;
+            }
+        }
+
         match message {
-            QueryRequest::Initialize(task_id) => {
+            QueryRequest::Initialize(task_id, work_done_progress) => {
                 let send_channel = self.send_channel.clone();
-                send(send_channel, LspResponse::Initialized(task_id));
+                send(
+                    send_channel,
+                    LspResponse::Initialized(task_id, work_done_progress),
+                );
             }
 
             QueryRequest::OpenFile(url, contents) => {
@@ -290,13 +450,13 @@ impl QuerySystem {
                     current_contents.insert_str(start_offset.to_usize(), &change.1);
                 }
 
+                // Edits reflect the editor's (possibly unsaved) buffer,
+                // so record them as an overlay on top of the file.
                 let text = Text::from(current_contents);
-                self.lark_db
-                    .query_mut(lark_parser::FileTextQuery)
-                    .set(file_name, text);
+                self.lark_db.set_file_overlay(file_name, Some(text));
             }
             QueryRequest::RenameAtPosition(task_id, url, position, new_name) => {
-                std::thread::spawn({
+                self.spawn_task({
                     let db = self.lark_db.snapshot();
                     let send_channel = self.send_channel.clone();
                     move || {
@@ -322,13 +482,25 @@ impl QuerySystem {
                 });
             }
             QueryRequest::ReferencesAtPosition(task_id, url, position, _include_declaration) => {
-                std::thread::spawn({
+                self.spawn_task({
                     let db = self.lark_db.snapshot();
                     let send_channel = self.send_channel.clone();
                     move || {
                         let _killme = KillTheProcess;
 
-                        match db.find_all_references_at_position(url.as_str(), position) {
+                        let progress_channel = send_channel.clone();
+                        let mut progress = |done, total| {
+                            send(
+                                progress_channel.clone(),
+                                LspResponse::Progress(task_id, done, total),
+                            );
+                        };
+
+                        match db.find_all_references_at_position(
+                            url.as_str(),
+                            position,
+                            &mut progress,
+                        ) {
                             Ok(v) => {
                                 let result = v
                                     .iter()
@@ -343,8 +515,147 @@ impl QuerySystem {
                     }
                 });
             }
+            QueryRequest::DocumentHighlight(task_id, url, position) => {
+                self.spawn_task({
+                    let db = self.lark_db.snapshot();
+                    let send_channel = self.send_channel.clone();
+                    move || {
+                        let _killme = KillTheProcess;
+
+                        match db.document_highlights_at_position(url.as_str(), position) {
+                            Ok(v) => {
+                                send(send_channel, LspResponse::Highlights(task_id, v));
+                            }
+                            _ => {
+                                send(send_channel, LspResponse::Nothing(task_id));
+                            }
+                        }
+                    }
+                });
+            }
+            QueryRequest::InlayHints(task_id, url, range) => {
+                self.spawn_task({
+                    let db = self.lark_db.snapshot();
+                    let send_channel = self.send_channel.clone();
+                    move || {
+                        let _killme = KillTheProcess;
+
+                        match db.inlay_hints_in_range(url.as_str(), range) {
+                            Ok(v) => {
+                                send(send_channel, LspResponse::InlayHints(task_id, v));
+                            }
+                            _ => {
+                                send(send_channel, LspResponse::Nothing(task_id));
+                            }
+                        }
+                    }
+                });
+            }
+            QueryRequest::WorkspaceSymbols(task_id, query) => {
+                self.spawn_task({
+                    let db = self.lark_db.snapshot();
+                    let send_channel = self.send_channel.clone();
+                    move || {
+                        let _killme = KillTheProcess;
+
+                        match db.workspace_symbols(&query) {
+                            Ok(v) => {
+                                let result = v
+                                    .into_iter()
+                                    .map(|(name, kind, file, range)| {
+                                        (name, kind, Url::parse(&file).unwrap(), range)
+                                    })
+                                    .collect();
+                                send(send_channel, LspResponse::Symbols(task_id, result));
+                            }
+                            _ => {
+                                send(send_channel, LspResponse::Nothing(task_id));
+                            }
+                        }
+                    }
+                });
+            }
+            QueryRequest::IncomingCalls(task_id, url, position) => {
+                self.spawn_task({
+                    let db = self.lark_db.snapshot();
+                    let send_channel = self.send_channel.clone();
+                    move || {
+                        let _killme = KillTheProcess;
+
+                        match db.incoming_calls_at_position(url.as_str(), position) {
+                            Ok(v) => {
+                                let result = v
+                                    .into_iter()
+                                    .map(|(name, file, range)| {
+                                        (name, Url::parse(&file).unwrap(), range)
+                                    })
+                                    .collect();
+                                send(send_channel, LspResponse::IncomingCalls(task_id, result));
+                            }
+                            _ => {
+                                send(send_channel, LspResponse::Nothing(task_id));
+                            }
+                        }
+                    }
+                });
+            }
+            QueryRequest::SelectionRange(task_id, url, positions) => {
+                self.spawn_task({
+                    let db = self.lark_db.snapshot();
+                    let send_channel = self.send_channel.clone();
+                    move || {
+                        let _killme = KillTheProcess;
+
+                        // LSP wants a range for every position, so if
+                        // nothing contains one, we give its empty range.
+                        let ranges: Result<Vec<_>, Cancelled> = positions
+                            .into_iter()
+                            .map(|position| {
+                                let mut ranges =
+                                    db.selection_ranges_at_position(url.as_str(), position)?;
+                                if ranges.is_empty() {
+                                    ranges.push(Range::new(position, position));
+                                }
+                                Ok(ranges)
+                            })
+                            .collect();
+                        match ranges {
+                            Ok(ranges) => {
+                                send(send_channel, LspResponse::SelectionRanges(task_id, ranges));
+                            }
+                            _ => {
+                                send(send_channel, LspResponse::Nothing(task_id));
+                            }
+                        }
+                    }
+                });
+            }
+            QueryRequest::CodeAction(task_id, url, range) => {
+                self.spawn_task({
+                    let db = self.lark_db.snapshot();
+                    let send_channel = self.send_channel.clone();
+                    move || {
+                        let _killme = KillTheProcess;
+
+                        match db.code_actions_in_range(url.as_str(), range) {
+                            Ok(actions) => {
+                                let result = actions
+                                    .into_iter()
+                                    .map(|(title, range, new_text)| {
+                                        (title, vec![(url.clone(), range, new_text)])
+                                    })
+                                    .collect();
+                                send(send_channel, LspResponse::CodeActions(task_id, result));
+                            }
+                            _ => {
+                                send(send_channel, LspResponse::Nothing(task_id));
+                            }
+                        }
+                    }
+                });
+            }
             QueryRequest::DefinitionAtPosition(task_id, url, position) => {
-                std::thread::spawn({
+                self.spawn_task({
                     let db = self.lark_db.snapshot();
                     let send_channel = self.send_channel.clone();
                     move || {
@@ -364,8 +675,26 @@ impl QuerySystem {
                     }
                 });
             }
+            QueryRequest::Hover(task_id, url, position) => {
+                self.spawn_task({
+                    let db = self.lark_db.snapshot();
+                    let send_channel = self.send_channel.clone();
+                    move || {
+                        let _killme = KillTheProcess;
+
+                        match db.hover_at_position(url.as_str(), position) {
+                            Ok(content) => {
+                                send(send_channel, LspResponse::Hover(task_id, content));
+                            }
+                            Err(Cancelled) => {
+                                send(send_channel, LspResponse::Nothing(task_id));
+                            }
+                        }
+                    }
+                });
+            }
             QueryRequest::TypeAtPosition(task_id, url, position) => {
-                std::thread::spawn({
+                self.spawn_task({
                     let db = self.lark_db.snapshot();
                     let send_channel = self.send_channel.clone();
                     move || {
@@ -4,29 +4,58 @@
 //! (e.g. `&uri`) that wouldn't be possible otherwise, which is
 //! convenient.
 
-use languageserver_types::{Position, Range};
+use languageserver_types::{DocumentHighlightKind, Position, Range, SymbolKind};
+use lark_actor::HoverContent;
 use lark_entity::{Entity, EntityData, ItemKind, MemberKind};
-use lark_error::Diagnostic;
+use lark_error::{Diagnostic, Severity};
 use lark_intern::{Intern, Untern};
-use lark_parser::HoverTargetKind;
+use lark_parser::{HoverTargetKind, LexToken};
 use lark_pretty_print::PrettyPrint;
-use lark_span::{ByteIndex, FileName, IntoFileName, Span};
+use lark_span::{ByteIndex, FileName, IntoFileName, Span, Spanned};
+use serde::Serialize;
 use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct RangedDiagnostic {
     pub label: String,
     pub range: Range,
+    pub severity: Severity,
 }
 
 impl RangedDiagnostic {
-    pub fn new(label: String, range: Range) -> RangedDiagnostic {
-        RangedDiagnostic { label, range }
+    pub fn new(label: String, range: Range, severity: Severity) -> RangedDiagnostic {
+        RangedDiagnostic {
+            label,
+            range,
+            severity,
+        }
     }
 }
 
+/// A diagnostic in a stable JSON shape, for tools that want lark's
+/// diagnostics without speaking LSP. See
+/// `LsDatabase::diagnostics_to_json`.
+#[derive(Debug, Serialize)]
+pub struct JsonDiagnostic {
+    pub file: String,
+
+    /// As in LSP, columns are measured in UTF-16 code units.
+    pub range: Range,
+
+    /// Either `"error"` or `"warning"`.
+    pub severity: &'static str,
+
+    pub message: String,
+
+    /// The replacement offered by a "did you mean" message, if any.
+    pub suggestion: Option<String>,
+}
+
 pub struct Cancelled;
 
+/// The maximum number of results returned by `workspace_symbols`.
+pub const WORKSPACE_SYMBOLS_LIMIT: usize = 100;
+
 pub type Cancelable<T> = Result<T, Cancelled>;
 
 pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
@@ -50,16 +79,22 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
             let _ = self
                 .parsed_file(input_file)
                 .accumulate_errors_into(&mut errors);
+            errors.extend(self.indentation_warnings(input_file).iter().cloned());
+            errors.extend(self.naming_warnings(input_file).iter().cloned());
+            errors.extend(self.dead_code_warnings(input_file).iter().cloned());
 
             // Next, check entities in file for type-safety
             let file_entity = EntityData::InputFile { file: input_file }.intern(self);
             for &entity in self.descendant_entities(file_entity).iter() {
                 self.accumulate_errors_for_entity(entity, &mut errors)?;
             }
+            let _ = self
+                .type_check_file(input_file)
+                .accumulate_errors_into(&mut errors);
 
             let error_ranges = errors
                 .iter()
-                .map(|x| RangedDiagnostic::new(x.label.clone(), self.range(x.span)))
+                .map(|x| RangedDiagnostic::new(x.message(), self.span_to_range(x.span), x.severity))
                 .collect();
 
             file_errors.insert(input_file.id.untern(self).to_string(), error_ranges);
@@ -68,10 +103,44 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
         Ok(file_errors)
     }
 
-    fn range(&self, span: Span<FileName>) -> languageserver_types::Range {
-        let left = self.location(span.file(), span.start()).as_position();
-        let right = self.location(span.file(), span.end()).as_position();
-        languageserver_types::Range::new(left, right)
+    /// Renders `diagnostics` as a JSON array of `JsonDiagnostic`.
+    fn diagnostics_to_json(&self, diagnostics: &[Diagnostic]) -> String {
+        let diagnostics: Vec<JsonDiagnostic> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let severity = match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                };
+
+                JsonDiagnostic {
+                    file: diagnostic.span.file().id.untern(self).to_string(),
+                    range: self.span_to_range(diagnostic.span),
+                    severity,
+                    message: diagnostic.label.clone(),
+                    suggestion: diagnostic.suggestion.clone(),
+                }
+            })
+            .collect();
+        serde_json::to_string(&diagnostics).unwrap()
+    }
+
+    /// Converts a span into an LSP range. Note that LSP columns are
+    /// measured in UTF-16 code units, not bytes or characters.
+    fn span_to_range(&self, span: Span<FileName>) -> Range {
+        let start = self.byte_index_to_position(span.file(), span.start());
+        let end = self.byte_index_to_position(span.file(), span.end());
+        Range::new(start, end)
+    }
+
+    /// Converts a byte index into an LSP position (whose column is
+    /// measured in UTF-16 code units).
+    fn byte_index_to_position(&self, file: FileName, index: ByteIndex) -> Position {
+        let location = self.location(file, index);
+        let line_start = self.line_offsets(file)[location.line];
+        let text: &str = &self.file_text(file);
+        let column = text[line_start..index.to_usize()].encode_utf16().count();
+        Position::new(location.line as u64, column as u64)
     }
 
     fn accumulate_errors_for_entity(
@@ -113,7 +182,7 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
                 let _ = self.ty(entity).accumulate_errors_into(errors);
                 let _ = self.signature(entity).accumulate_errors_into(errors);
                 let _ = self.fn_body(entity).accumulate_errors_into(errors);
-                let _ = self.full_type_check(entity).accumulate_errors_into(errors);
+                errors.extend(self.missing_return_values(entity).iter().cloned());
             }
             EntityData::MemberName {
                 kind: MemberKind::Method,
@@ -125,18 +194,18 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
                 let _ = self.ty(entity).accumulate_errors_into(errors);
                 let _ = self.signature(entity).accumulate_errors_into(errors);
                 let _ = self.fn_body(entity).accumulate_errors_into(errors);
-                let _ = self.full_type_check(entity).accumulate_errors_into(errors);
+                errors.extend(self.missing_return_values(entity).iter().cloned());
             }
         }
 
         Ok(())
     }
 
-    fn find_all_references_to_definition(&self, definition_entity: Entity) -> Vec<(String, Range)> {
+    /// Returns every entity in the project that has a fn body -- the
+    /// places where references to other entities can occur.
+    fn entities_with_fn_bodies(&self) -> Vec<Entity> {
         let input_files = self.file_names();
-        let mut uses = vec![];
-
-        let p = lark_hir::PlaceData::Entity(definition_entity);
+        let mut entities = vec![];
 
         for &input_file in &*input_files {
             let _ = self.parsed_file(input_file);
@@ -144,19 +213,39 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
             let file_entity = EntityData::InputFile { file: input_file }.intern(self);
             for &entity in self.descendant_entities(file_entity).iter() {
                 if entity.untern(self).has_fn_body() {
-                    let fn_body = self.fn_body(entity).into_value();
-                    for (key, value) in fn_body.tables.places.iter_enumerated() {
-                        if *value == p {
-                            let span = fn_body.span(key);
-                            let range = self.range(span);
-                            let filename = span.file().id.untern(self).to_string();
-                            uses.push((filename, range));
-                        }
-                    }
+                    entities.push(entity);
                 }
             }
         }
 
+        entities
+    }
+
+    fn find_all_references_to_definition(
+        &self,
+        definition_entity: Entity,
+        progress: &mut dyn FnMut(u32, u32),
+    ) -> Vec<(String, Range)> {
+        let entities = self.entities_with_fn_bodies();
+        let total = entities.len() as u32;
+        let mut uses = vec![];
+
+        let p = lark_hir::PlaceData::Entity(definition_entity);
+
+        for (done, &entity) in entities.iter().enumerate() {
+            let fn_body = self.fn_body(entity).into_value();
+            for (key, value) in fn_body.tables.places.iter_enumerated() {
+                if *value == p {
+                    let span = fn_body.span(key);
+                    let range = self.span_to_range(span);
+                    let filename = span.file().id.untern(self).to_string();
+                    uses.push((filename, range));
+                }
+            }
+
+            progress(done as u32 + 1, total);
+        }
+
         uses
     }
 
@@ -172,7 +261,7 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
         for (key, value) in fn_body.tables.places.iter_enumerated() {
             if *value == p {
                 let span = fn_body.span(key);
-                let range = self.range(span);
+                let range = self.span_to_range(span);
                 let filename = span.file().id.untern(self).to_string();
                 uses.push((filename, range));
             }
@@ -181,58 +270,392 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
         uses
     }
 
-    fn find_all_references_to_field(&self, field_entity: Entity) -> Vec<(String, Range)> {
-        let input_files = self.file_names();
+    fn find_all_references_to_field(
+        &self,
+        field_entity: Entity,
+        progress: &mut dyn FnMut(u32, u32),
+    ) -> Vec<(String, Range)> {
+        let entities = self.entities_with_fn_bodies();
+        let total = entities.len() as u32;
         let mut uses = vec![];
 
-        for &input_file in &*input_files {
-            let _ = self.parsed_file(input_file);
+        for (done, &entity) in entities.iter().enumerate() {
+            let fn_body = self.fn_body(entity).into_value();
+            let possible_match_types = &self.full_type_check(entity).into_value();
 
-            let file_entity = EntityData::InputFile { file: input_file }.intern(self);
-            for &entity in self.descendant_entities(file_entity).iter() {
-                if entity.untern(self).has_fn_body() {
-                    let fn_body = self.fn_body(entity).into_value();
-                    let possible_match_types = &self.full_type_check(entity).into_value();
-
-                    for value in fn_body.tables.places.iter() {
-                        match value {
-                            lark_hir::PlaceData::Field {
-                                name: value_name, ..
-                            } => {
-                                if possible_match_types.entities[&(*value_name).into()]
-                                    == field_entity
-                                {
-                                    let span = fn_body.span(*value_name);
-                                    let range = self.range(span);
-                                    let filename = span.file().id.untern(self).to_string();
-                                    uses.push((filename, range));
-                                }
-                            }
-                            _ => {}
+            for value in fn_body.tables.places.iter() {
+                match value {
+                    lark_hir::PlaceData::Field {
+                        name: value_name, ..
+                    } => {
+                        if possible_match_types.entities[&(*value_name).into()] == field_entity {
+                            let span = fn_body.span(*value_name);
+                            let range = self.span_to_range(span);
+                            let filename = span.file().id.untern(self).to_string();
+                            uses.push((filename, range));
                         }
                     }
+                    _ => {}
+                }
+            }
 
-                    for identified_expression in fn_body.tables.identified_expressions.iter() {
-                        match &identified_expression {
-                            lark_hir::IdentifiedExpressionData { identifier, .. } => {
-                                if possible_match_types.entities[&(*identifier).into()]
-                                    == field_entity
-                                {
-                                    let span = fn_body.span(*identifier);
-                                    let range = self.range(span);
-                                    let filename = span.file().id.untern(self).to_string();
-                                    uses.push((filename, range));
-                                }
-                            }
+            for identified_expression in fn_body.tables.identified_expressions.iter() {
+                match &identified_expression {
+                    lark_hir::IdentifiedExpressionData { identifier, .. } => {
+                        if possible_match_types.entities[&(*identifier).into()] == field_entity {
+                            let span = fn_body.span(*identifier);
+                            let range = self.span_to_range(span);
+                            let filename = span.file().id.untern(self).to_string();
+                            uses.push((filename, range));
                         }
                     }
                 }
             }
+
+            progress(done as u32 + 1, total);
         }
 
         uses
     }
 
+    /// Returns the occurrences, within the file `url`, of the variable
+    /// or entity at the given position -- that is, a file-local subset
+    /// of `find_all_references_at_position`. Each occurrence is tagged
+    /// as a read or a write where that distinction makes sense.
+    fn document_highlights_at_position(
+        &self,
+        url: &str,
+        position: Position,
+    ) -> Cancelable<Vec<(Range, DocumentHighlightKind)>> {
+        let url_file_name = url.into_file_name(self);
+        let byte_index = self.position_to_byte_index(url, position);
+        let targets = self.hover_targets(url_file_name, byte_index);
+        self.check_for_cancellation()?;
+
+        let results = targets
+            .iter()
+            .rev()
+            .filter_map(|target| match target.kind {
+                HoverTargetKind::Entity(entity) => {
+                    Some(self.document_highlights_for_entity(url_file_name, entity))
+                }
+                HoverTargetKind::MetaIndex(entity, mi) => {
+                    let fn_body = self.fn_body(entity).into_value();
+                    match mi {
+                        lark_hir::MetaIndex::Variable(variable) => {
+                            Some(self.document_highlights_for_variable(&fn_body, variable))
+                        }
+                        lark_hir::MetaIndex::Place(place_idx) => match fn_body.tables[place_idx] {
+                            lark_hir::PlaceData::Variable(variable) => {
+                                Some(self.document_highlights_for_variable(&fn_body, variable))
+                            }
+                            lark_hir::PlaceData::Entity(entity) => {
+                                Some(self.document_highlights_for_entity(url_file_name, entity))
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    }
+                }
+            })
+            .next();
+
+        Ok(results.unwrap_or_default())
+    }
+
+    fn document_highlights_for_variable(
+        &self,
+        fn_body: &lark_hir::FnBody,
+        variable: lark_hir::Variable,
+    ) -> Vec<(Range, DocumentHighlightKind)> {
+        // The declaration of the variable (e.g., the `x` in `let x =
+        // ...`) counts as a write.
+        let mut highlights = vec![(
+            self.span_to_range(fn_body.span(variable)),
+            DocumentHighlightKind::Write,
+        )];
+
+        let assigned_places: Vec<lark_hir::Place> = fn_body
+            .tables
+            .expressions
+            .iter()
+            .filter_map(|expression| match expression {
+                lark_hir::ExpressionData::Assignment { place, .. } => Some(*place),
+                _ => None,
+            })
+            .collect();
+
+        let p = lark_hir::PlaceData::Variable(variable);
+        for (key, value) in fn_body.tables.places.iter_enumerated() {
+            if *value == p {
+                let kind = if assigned_places.contains(&key) {
+                    DocumentHighlightKind::Write
+                } else {
+                    DocumentHighlightKind::Read
+                };
+                highlights.push((self.span_to_range(fn_body.span(key)), kind));
+            }
+        }
+
+        highlights
+    }
+
+    fn document_highlights_for_entity(
+        &self,
+        file: FileName,
+        definition_entity: Entity,
+    ) -> Vec<(Range, DocumentHighlightKind)> {
+        let mut highlights = vec![];
+
+        if let Some(span) = self.get_entity_span_if_possible(definition_entity, true) {
+            if span.file() == file {
+                highlights.push((self.span_to_range(span), DocumentHighlightKind::Text));
+            }
+        }
+
+        let p = lark_hir::PlaceData::Entity(definition_entity);
+        let file_entity = EntityData::InputFile { file }.intern(self);
+        for &entity in self.descendant_entities(file_entity).iter() {
+            if entity.untern(self).has_fn_body() {
+                let fn_body = self.fn_body(entity).into_value();
+                for (key, value) in fn_body.tables.places.iter_enumerated() {
+                    if *value == p {
+                        let range = self.span_to_range(fn_body.span(key));
+                        highlights.push((range, DocumentHighlightKind::Read));
+                    }
+                }
+            }
+        }
+
+        highlights
+    }
+
+    /// Returns inlay hints for the `let`-bound variables declared
+    /// within `range` of the file `url`. Each hint is anchored just
+    /// after the variable name and gives its inferred type (e.g.,
+    /// `: uint`). Variables whose type could not be inferred get no
+    /// hint.
+    fn inlay_hints_in_range(&self, url: &str, range: Range) -> Cancelable<Vec<(Position, String)>> {
+        let url_file_name = url.into_file_name(self);
+        let start = self.position_to_byte_index(url, range.start);
+        let end = self.position_to_byte_index(url, range.end);
+        let range_span = Span::new(url_file_name, start, end);
+
+        let mut hints = vec![];
+
+        let file_entity = EntityData::InputFile {
+            file: url_file_name,
+        }
+        .intern(self);
+        for &entity in self.descendant_entities(file_entity).iter() {
+            self.check_for_cancellation()?;
+
+            if !entity.untern(self).has_fn_body() {
+                continue;
+            }
+
+            let fn_body = self.fn_body(entity).into_value();
+            let fn_body_types = self.full_type_check(entity).into_value();
+            for expression in fn_body.tables.expressions.iter() {
+                if let lark_hir::ExpressionData::Let { variable, .. } = *expression {
+                    let span = fn_body.span(variable);
+                    if !range_span.contains_index(span.start()) {
+                        continue;
+                    }
+
+                    if let Some(ty) = fn_body_types.opt_ty(variable) {
+                        let position = self.byte_index_to_position(url_file_name, span.end());
+                        hints.push((position, format!(": {}", ty.pretty_print(self))));
+                    }
+                }
+            }
+        }
+
+        Ok(hints)
+    }
+
+    /// Finds the quick fixes that apply within `range` of the file
+    /// `url`. For now, that means one fix for each struct construction
+    /// that is missing some fields (e.g., `Point(x: 1)`), which
+    /// inserts the missing fields with a placeholder value for the
+    /// user to replace (`Point(x: 1, y: 0)`). Returns the title of each
+    /// fix along with the range it replaces and the new text.
+    fn code_actions_in_range(
+        &self,
+        url: &str,
+        range: Range,
+    ) -> Cancelable<Vec<(String, Range, String)>> {
+        let url_file_name = url.into_file_name(self);
+        let start = self.position_to_byte_index(url, range.start);
+        let end = self.position_to_byte_index(url, range.end);
+
+        let mut actions = vec![];
+
+        let file_entity = EntityData::InputFile {
+            file: url_file_name,
+        }
+        .intern(self);
+        for &entity in self.descendant_entities(file_entity).iter() {
+            self.check_for_cancellation()?;
+
+            if !entity.untern(self).has_fn_body() {
+                continue;
+            }
+
+            let fn_body = self.fn_body(entity).into_value();
+            for expression in fn_body.tables.expressions.indices() {
+                let (struct_entity, given) = match fn_body[expression] {
+                    lark_hir::ExpressionData::Aggregate { entity, fields } => {
+                        let given: Vec<_> = fields
+                            .iter_data(&fn_body)
+                            .map(|field| fn_body[field.identifier].text)
+                            .collect();
+                        (entity, given)
+                    }
+
+                    // `Point()` has no named arguments, so it parses
+                    // as a call.
+                    lark_hir::ExpressionData::Call {
+                        function,
+                        arguments,
+                    } if arguments.is_empty() => match fn_body[function] {
+                        lark_hir::ExpressionData::Place { place } => match fn_body[place] {
+                            lark_hir::PlaceData::Entity(entity) => (entity, vec![]),
+                            _ => continue,
+                        },
+                        _ => continue,
+                    },
+
+                    _ => continue,
+                };
+                match struct_entity.untern(self) {
+                    EntityData::ItemName {
+                        kind: ItemKind::Struct,
+                        ..
+                    } => {}
+                    _ => continue,
+                }
+
+                let span = fn_body.span(expression);
+                if span.end() < start || end < span.start() {
+                    continue;
+                }
+
+                let members = match self.members(struct_entity) {
+                    Ok(members) => members,
+                    Err(_) => continue,
+                };
+                let missing: Vec<String> = members
+                    .iter()
+                    .filter(|member| match member.kind {
+                        MemberKind::Field => !given.contains(&member.name),
+                        MemberKind::Method => false,
+                    })
+                    .map(|member| member.name.untern(self).to_string())
+                    .collect();
+                if missing.is_empty() {
+                    continue;
+                }
+
+                // Insert just before the closing `)`. We need a comma
+                // first unless there are no fields yet or one trails
+                // them (e.g., `Point(x: 1,)`).
+                let text: &str = &self.file_text(url_file_name);
+                let tokens = self.file_tokens(url_file_name).into_value();
+                let close = tokens.iter().find(|token| {
+                    token.span.end() == span.end()
+                        && token.value == LexToken::Sigil
+                        && &text[token.span] == ")"
+                });
+                let insert_at = match close {
+                    Some(close) => close.span.start(),
+                    None => continue,
+                };
+                let position = self.byte_index_to_position(url_file_name, insert_at);
+                let preceding = &text[Span::new(url_file_name, span.start(), insert_at)];
+                let separator = match preceding.trim_end().chars().last() {
+                    Some('(') => "",
+                    Some(',') if preceding.ends_with(',') => " ",
+                    Some(',') => "",
+                    _ => ", ",
+                };
+
+                // Each field gets a placeholder value, so that the
+                // result still parses.
+                let fields: Vec<String> =
+                    missing.iter().map(|name| format!("{}: 0", name)).collect();
+                let new_text = format!("{}{}", separator, fields.join(", "));
+                actions.push((
+                    format!("Fill in missing fields: {}", missing.join(", ")),
+                    Range::new(position, position),
+                    new_text,
+                ));
+            }
+        }
+
+        Ok(actions)
+    }
+
+    /// Searches all files for entities whose name contains the
+    /// characters of `query`, in order (ignoring case). Returns the
+    /// name, kind, file and range of each match, stopping after
+    /// `WORKSPACE_SYMBOLS_LIMIT` results.
+    fn workspace_symbols(
+        &self,
+        query: &str,
+    ) -> Cancelable<Vec<(String, SymbolKind, String, Range)>> {
+        let query = query.to_lowercase();
+        let mut symbols = vec![];
+
+        for &input_file in &*self.file_names() {
+            self.check_for_cancellation()?;
+
+            let file_entity = EntityData::InputFile { file: input_file }.intern(self);
+            for &entity in self.descendant_entities(file_entity).iter() {
+                let (id, kind) = match entity.untern(self) {
+                    EntityData::ItemName {
+                        kind: ItemKind::Struct,
+                        id,
+                        ..
+                    } => (id, SymbolKind::Struct),
+                    EntityData::ItemName {
+                        kind: ItemKind::Function,
+                        id,
+                        ..
+                    } => (id, SymbolKind::Function),
+                    EntityData::MemberName {
+                        kind: MemberKind::Field,
+                        id,
+                        ..
+                    } => (id, SymbolKind::Field),
+                    EntityData::MemberName {
+                        kind: MemberKind::Method,
+                        id,
+                        ..
+                    } => (id, SymbolKind::Method),
+                    EntityData::InputFile { .. }
+                    | EntityData::LangItem(_)
+                    | EntityData::Error(_) => continue,
+                };
+
+                let name = id.untern(self).to_string();
+                if !is_subsequence(&query, &name.to_lowercase()) {
+                    continue;
+                }
+
+                let span = self.characteristic_entity_span(entity);
+                let filename = span.file().id.untern(self).to_string();
+                symbols.push((name, kind, filename, self.span_to_range(span)));
+
+                if symbols.len() >= WORKSPACE_SYMBOLS_LIMIT {
+                    return Ok(symbols);
+                }
+            }
+        }
+
+        Ok(symbols)
+    }
+
     fn rename_all_references_at_position(
         &self,
         url: &str,
@@ -241,18 +664,36 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
     ) -> Cancelable<Vec<(String, Range, String)>> {
         self.check_for_cancellation()?;
 
-        let references = self.find_all_references_at_position(url, position)?;
+        let url_file_name = url.into_file_name(self);
+        let byte_index = self.position_to_byte_index(url, position);
+        if self.identifier_span_at(url_file_name, byte_index).is_none() {
+            return Ok(vec![]);
+        }
+
+        let references = self.find_all_references_at_position(url, position, &mut |_, _| ())?;
 
+        // A reference may span more than the name itself (e.g., the
+        // whole of `a.b` for the field `b`), so narrow each edit to
+        // the identifier that ends the reference.
         Ok(references
             .into_iter()
-            .map(|(x, y)| (x, y, new_name.to_string()))
+            .filter_map(|(file, range)| {
+                let end = self.position_to_byte_index(&file, range.end).to_usize();
+                let last_byte = ByteIndex::from(end.checked_sub(1)?);
+                let span = self.identifier_span_at(file.into_file_name(self), last_byte)?;
+                Some((file, self.span_to_range(span), new_name.to_string()))
+            })
             .collect())
     }
 
+    /// Finds all references to the thing at the given position. Searches
+    /// that span the whole project invoke `progress` with the number of
+    /// fn bodies searched so far and the total number to search.
     fn find_all_references_at_position(
         &self,
         url: &str,
         position: Position,
+        progress: &mut dyn FnMut(u32, u32),
     ) -> Cancelable<Vec<(String, Range)>> {
         // First, let's add the definition site, as this is one of the references
         let definition_position = self.definition_range_at_position(url, position, true)?;
@@ -271,8 +712,8 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
                     EntityData::MemberName {
                         kind: MemberKind::Field,
                         ..
-                    } => Some(self.find_all_references_to_field(hovered_entity)),
-                    _ => Some(self.find_all_references_to_definition(hovered_entity)),
+                    } => Some(self.find_all_references_to_field(hovered_entity, progress)),
+                    _ => Some(self.find_all_references_to_definition(hovered_entity, progress)),
                 },
                 HoverTargetKind::MetaIndex(entity, mi) => match mi {
                     lark_hir::MetaIndex::Variable(variable) => {
@@ -285,7 +726,7 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
 
                         match p {
                             lark_hir::PlaceData::Entity(entity) => {
-                                Some(self.find_all_references_to_definition(entity))
+                                Some(self.find_all_references_to_definition(entity, progress))
                             }
                             lark_hir::PlaceData::Variable(variable) => {
                                 Some(self.find_all_references_to_variable(&fn_body, variable))
@@ -294,7 +735,7 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
                                 let source_types = &self.full_type_check(entity).into_value();
                                 let hovered_entity = source_types.entities[&name.into()];
 
-                                Some(self.find_all_references_to_field(hovered_entity))
+                                Some(self.find_all_references_to_field(hovered_entity, progress))
                             }
                             _ => None,
                         }
@@ -321,20 +762,132 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
         }
     }
 
+    /// Finds the ranges to step through when expanding the selection
+    /// outward from the given position: the expressions, blocks, and
+    /// entities that contain it, innermost first. Each range strictly
+    /// contains the one before it.
+    fn selection_ranges_at_position(
+        &self,
+        url: &str,
+        position: Position,
+    ) -> Cancelable<Vec<Range>> {
+        let url_file_name = url.into_file_name(self);
+        let byte_index = self.position_to_byte_index(url, position);
+        let targets = self.hover_targets(url_file_name, byte_index);
+        self.check_for_cancellation()?;
+
+        let mut spans: Vec<Span<FileName>> = targets
+            .iter()
+            .filter(|target| match target.kind {
+                HoverTargetKind::Entity(entity) => match entity.untern(self) {
+                    EntityData::InputFile { .. } => false,
+                    _ => true,
+                },
+                HoverTargetKind::MetaIndex(..) => true,
+            })
+            .map(|target| target.span)
+            .collect();
+
+        // A block whose only statement is an expression has no span
+        // of its own in the HIR, so find the enclosing `{ ... }`
+        // pairs from the tokens instead.
+        let text = self.file_text(url_file_name);
+        let tokens = self.file_tokens(url_file_name).into_value();
+        spans.extend(enclosing_curlies(&text, &tokens, byte_index));
+
+        spans.sort_by_key(|span| span.end().to_usize() - span.start().to_usize());
+
+        let mut ranges = vec![];
+        let mut previous: Option<Span<FileName>> = None;
+        for span in spans {
+            if let Some(previous) = previous {
+                let contains_previous =
+                    span.start() <= previous.start() && previous.end() <= span.end();
+                if span == previous || !contains_previous {
+                    continue;
+                }
+            }
+            ranges.push(self.span_to_range(span));
+            previous = Some(span);
+        }
+
+        Ok(ranges)
+    }
+
+    /// Finds the fns that refer to (e.g., call) the entity at the
+    /// given position. Returns the name of each caller along with the
+    /// file and range of each call site within it.
+    fn incoming_calls_at_position(
+        &self,
+        url: &str,
+        position: Position,
+    ) -> Cancelable<Vec<(String, String, Range)>> {
+        let url_file_name = url.into_file_name(self);
+        let byte_index = self.position_to_byte_index(url, position);
+        let targets = self.hover_targets(url_file_name, byte_index);
+        self.check_for_cancellation()?;
+
+        let target_entity = targets
+            .iter()
+            .rev()
+            .filter_map(|target| match target.kind {
+                HoverTargetKind::Entity(entity) => Some(entity),
+                HoverTargetKind::MetaIndex(entity, lark_hir::MetaIndex::Place(place)) => {
+                    match self.fn_body(entity).into_value().tables[place] {
+                        lark_hir::PlaceData::Entity(entity) => Some(entity),
+                        _ => None,
+                    }
+                }
+                HoverTargetKind::MetaIndex(..) => None,
+            })
+            .next();
+        let target_entity = match target_entity {
+            Some(entity) => entity,
+            None => return Ok(vec![]),
+        };
+
+        let target_place = lark_hir::PlaceData::Entity(target_entity);
+        let mut calls = vec![];
+        for caller in self.entities_with_fn_bodies() {
+            self.check_for_cancellation()?;
+
+            if !self.referenced_entities(caller).contains(&target_entity) {
+                continue;
+            }
+
+            let caller_name = match caller.untern(self) {
+                EntityData::ItemName { id, .. } | EntityData::MemberName { id, .. } => {
+                    id.untern(self).to_string()
+                }
+                EntityData::InputFile { .. } | EntityData::LangItem(_) | EntityData::Error(_) => {
+                    continue;
+                }
+            };
+
+            let fn_body = self.fn_body(caller).into_value();
+            for (place, data) in fn_body.tables.places.iter_enumerated() {
+                if *data == target_place {
+                    let span = fn_body.span(place);
+                    let filename = span.file().id.untern(self).to_string();
+                    calls.push((caller_name.clone(), filename, self.span_to_range(span)));
+                }
+            }
+        }
+
+        Ok(calls)
+    }
+
     fn get_entity_span_if_possible(
         &self,
         entity: Entity,
         use_minimal_span: bool,
     ) -> Option<Span<FileName>> {
-        match entity.untern(self) {
-            EntityData::Error(..) | EntityData::LangItem { .. } => None,
-            _ => {
-                if use_minimal_span {
-                    Some(self.characteristic_entity_span(entity))
-                } else {
-                    Some(self.entity_span(entity))
-                }
-            }
+        self.declaring_file(entity)?;
+
+        if use_minimal_span {
+            Some(self.characteristic_entity_span(entity))
+        } else {
+            Some(self.entity_span(entity))
         }
     }
 
@@ -355,7 +908,7 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
             .filter_map(|target| match target.kind {
                 HoverTargetKind::Entity(entity) => {
                     if let Some(span) = self.get_entity_span_if_possible(entity, minimal_span) {
-                        let range = self.range(span);
+                        let range = self.span_to_range(span);
                         let filename = span.file().id.untern(self).to_string();
                         Some((filename, range))
                     } else {
@@ -369,7 +922,7 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
                             if let Some(span) =
                                 self.get_entity_span_if_possible(*target_entity, minimal_span)
                             {
-                                let range = self.range(span);
+                                let range = self.span_to_range(span);
                                 let filename = span.file().id.untern(self).to_string();
                                 Some((filename, range))
                             } else {
@@ -382,7 +935,7 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
                     lark_hir::MetaIndex::Variable(variable) => {
                         let fn_body = self.fn_body(entity).into_value();
                         let span = fn_body.span(variable);
-                        let range = self.range(span);
+                        let range = self.span_to_range(span);
                         let filename = span.file().id.untern(self).to_string();
                         Some((filename, range))
                     }
@@ -395,19 +948,19 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
                                 if let Some(span) =
                                     self.get_entity_span_if_possible(entity, minimal_span)
                                 {
-                                    let range = self.range(span);
+                                    let range = self.span_to_range(span);
                                     let filename = span.file().id.untern(self).to_string();
                                     Some((filename, range))
                                 } else {
                                     let span = fn_body.span(place_idx);
-                                    let range = self.range(span);
+                                    let range = self.span_to_range(span);
                                     let filename = span.file().id.untern(self).to_string();
                                     Some((filename, range))
                                 }
                             }
                             lark_hir::PlaceData::Variable(variable) => {
                                 let span = fn_body.span(variable);
-                                let range = self.range(span);
+                                let range = self.span_to_range(span);
                                 let filename = span.file().id.untern(self).to_string();
                                 Some((filename, range))
                             }
@@ -420,7 +973,7 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
                                             *child_entity,
                                             minimal_span,
                                         ) {
-                                            let range = self.range(span);
+                                            let range = self.span_to_range(span);
                                             let filename = span.file().id.untern(self).to_string();
                                             Some((filename, range))
                                         } else {
@@ -496,8 +1049,129 @@ pub trait LsDatabase: lark_type_check::TypeCheckDatabase + salsa::Database {
             .next())
     }
 
+    /// Returns the content to display when hovering over a given
+    /// position (if any). This is the type or signature of whatever
+    /// is there, along with the doc comments if it is an entity.
+    fn hover_at_position(&self, url: &str, position: Position) -> Cancelable<Option<HoverContent>> {
+        let url_file_name = url.into_file_name(self);
+        let byte_index = self.position_to_byte_index(url, position);
+        let targets = self.hover_targets(url_file_name, byte_index);
+        self.check_for_cancellation()?;
+
+        Ok(targets
+            .iter()
+            .rev()
+            .filter_map(|target| match target.kind {
+                HoverTargetKind::Entity(entity) => self.entity_hover_content(entity),
+
+                HoverTargetKind::MetaIndex(entity, mi) => {
+                    let fn_body = self.fn_body(entity).into_value();
+                    let fn_body_types = self.full_type_check(entity).into_value();
+
+                    let variable = match mi {
+                        lark_hir::MetaIndex::Variable(variable) => Some(variable),
+                        lark_hir::MetaIndex::Place(place) => match fn_body.tables[place] {
+                            lark_hir::PlaceData::Variable(variable) => Some(variable),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+
+                    if let Some(variable) = variable {
+                        let name = fn_body.tables[fn_body.tables[variable].name].text;
+                        let ty = fn_body_types.opt_ty(variable)?;
+                        return Some(HoverContent {
+                            signature: format!(
+                                "let {}: {}",
+                                name.untern(self),
+                                ty.pretty_print(self)
+                            ),
+                            docs: None,
+                        });
+                    }
+
+                    match mi {
+                        lark_hir::MetaIndex::Identifier(identifier) => {
+                            let target_entity = fn_body_types.entities.get(&identifier.into())?;
+                            self.entity_hover_content(*target_entity)
+                        }
+                        lark_hir::MetaIndex::Place(place) => match fn_body.tables[place] {
+                            lark_hir::PlaceData::Entity(target_entity) => {
+                                self.entity_hover_content(target_entity)
+                            }
+                            _ => fn_body_types.opt_ty(mi).map(|ty| HoverContent {
+                                signature: ty.pretty_print(self),
+                                docs: None,
+                            }),
+                        },
+                        _ => fn_body_types.opt_ty(mi).map(|ty| HoverContent {
+                            signature: ty.pretty_print(self),
+                            docs: None,
+                        }),
+                    }
+                }
+            })
+            .next())
+    }
+
+    /// The hover content for a particular entity: its signature plus
+    /// any doc comments.
+    fn entity_hover_content(&self, entity: Entity) -> Option<HoverContent> {
+        let signature = match entity.untern(self) {
+            EntityData::InputFile { .. } | EntityData::LangItem(_) | EntityData::Error(_) => {
+                return None;
+            }
+            EntityData::ItemName {
+                kind: ItemKind::Struct,
+                ..
+            } => format!("struct {}", entity.pretty_print(self)),
+            EntityData::ItemName {
+                kind: ItemKind::Function,
+                ..
+            } => format!("def {}", entity.pretty_print(self)),
+            _ => entity.pretty_print(self),
+        };
+
+        Some(HoverContent {
+            signature,
+            docs: self.entity_docs(entity),
+        })
+    }
+
     fn position_to_byte_index(&self, url: &str, position: Position) -> ByteIndex {
         let url_id = url.intern(self);
         self.byte_index(FileName { id: url_id }, position.line, position.character)
     }
 }
+
+/// Returns the spans of the matching `{` and `}` pairs that enclose
+/// `index`, from the tokens of a file.
+fn enclosing_curlies(
+    text: &str,
+    tokens: &[Spanned<LexToken, FileName>],
+    index: ByteIndex,
+) -> Vec<Span<FileName>> {
+    let mut open = vec![];
+    let mut enclosing = vec![];
+    for token in tokens.iter().filter(|token| token.value == LexToken::Sigil) {
+        match &text[token.span] {
+            "{" => open.push(token.span),
+            "}" => {
+                if let Some(start) = open.pop() {
+                    let span = start.extended_until_end_of(token.span);
+                    if span.contains_index(index) {
+                        enclosing.push(span);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    enclosing
+}
+
+/// True if the characters of `needle` appear, in order, in `haystack`.
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
@@ -74,6 +74,45 @@ pub fn db_with_test(file_name: impl IntoFileName, text: &str) -> LarkDatabase {
     db
 }
 
+/// Builds a lark database containing any number of in-memory files,
+/// for tests that span several files:
+///
+/// ```ignore
+/// let db = TestDatabase::builder()
+///     .file("a.lark", "def a() {}")
+///     .file("b.lark", "def b() {}")
+///     .build();
+/// ```
+pub struct TestDatabase;
+
+impl TestDatabase {
+    pub fn builder() -> TestDatabaseBuilder {
+        TestDatabaseBuilder::default()
+    }
+}
+
+#[derive(Default)]
+pub struct TestDatabaseBuilder {
+    files: Vec<(String, String)>,
+}
+
+impl TestDatabaseBuilder {
+    /// Adds a file named `file_name` with the contents `text`.
+    pub fn file(mut self, file_name: &str, text: &str) -> Self {
+        self.files.push((file_name.to_string(), text.to_string()));
+        self
+    }
+
+    /// Creates the database, with the files added in order.
+    pub fn build(self) -> LarkDatabase {
+        let mut db = LarkDatabase::default();
+        for (file_name, text) in self.files {
+            db.add_file(file_name, text);
+        }
+        db
+    }
+}
+
 pub fn run_test(text: &str, error_spec: impl ErrorSpec) {
     let file_name_str = "input.lark";
     let db = db_with_test(file_name_str, text);
@@ -112,7 +151,8 @@ pub fn lark_parser_db(text: impl AsRef<str>) -> (FileName, LarkDatabase) {
     let text = Text::from(text);
     db.query_mut(lark_parser::FileNamesQuery)
         .set((), seq![path1]);
-    db.query_mut(lark_parser::FileTextQuery).set(path1, text);
+    db.query_mut(lark_parser::FileDiskTextQuery).set(path1, text);
+    db.query_mut(lark_parser::FileOverlayQuery).set(path1, None);
 
     (path1, db)
 }
@@ -328,6 +328,7 @@ impl<F: TypeFamily> Signature<F> {
 pub struct GenericDeclarations {
     pub parent_item: Option<Entity>,
     pub declarations: IndexVec<BoundVar, GenericKind<GenericTyDeclaration>>,
+    pub constraints: Seq<GenericConstraint>,
 }
 
 impl GenericDeclarations {
@@ -335,6 +336,7 @@ impl GenericDeclarations {
         Arc::new(GenericDeclarations {
             parent_item,
             declarations: IndexVec::default(),
+            constraints: Seq::default(),
         })
     }
 
@@ -350,6 +352,15 @@ pub struct GenericTyDeclaration {
     pub name: GlobalIdentifier,
 }
 
+/// A constraint on a generic type parameter from a `where` clause,
+/// e.g., `where T: Display`. Lark has no traits yet, so the bound is
+/// recorded by name only and is not checked.
+#[derive(Clone, Debug, DebugWith, PartialEq, Eq, Hash)]
+pub struct GenericConstraint {
+    pub parameter: BoundVar,
+    pub bound: GlobalIdentifier,
+}
+
 #[derive(Copy, Clone, Debug, DebugWith, PartialEq, Eq, Hash)]
 pub enum PermKind {
     Own,
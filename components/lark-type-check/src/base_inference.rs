@@ -158,13 +158,13 @@ impl TypeCheckerFamilyDependentExt<BaseInference>
 
     fn request_variable_ty(&mut self, var: hir::Variable) -> Ty<BaseInference> {
         self.storage.opt_ty(var).unwrap_or_else(|| {
-            let ty = self.new_variable();
+            let ty = self.new_variable_for(var);
             self.storage.record_max_ty(var, ty);
             ty
         })
     }
 
-    fn record_entity(&mut self, index: hir::Identifier, entity: Entity) {
+    fn record_entity(&mut self, index: impl Into<hir::MetaIndex>, entity: Entity) {
         self.storage.record_entity(index, entity);
     }
 
@@ -230,13 +230,11 @@ impl TypeCheckerVariableExt<BaseInference, Ty<BaseInference>>
                 }
 
                 if data1.kind != data2.kind {
-                    self.record_error(
-                        format!(
-                            "mismatched types ({} vs {})",
-                            data1.kind.pretty_print(self.db),
-                            data2.kind.pretty_print(self.db)
-                        ),
+                    self.record_type_mismatch(
                         cause,
+                        location,
+                        data1.kind.pretty_print(self.db),
+                        data2.kind.pretty_print(self.db),
                     );
                     return;
                 }
@@ -5,34 +5,26 @@ use crate::TypeCheckDatabase;
 use crate::TypeChecker;
 use crate::UniverseBinder;
 use generational_arena::Arena;
-use lark_collections::{FxIndexMap, IndexVec};
+use lark_collections::{FxIndexMap, FxIndexSet, IndexVec, Seq};
 use lark_entity::Entity;
-use lark_error::Diagnostic;
 use lark_error::WithError;
+use lark_hir as hir;
+use lark_intern::Untern;
+use lark_span::{FileName, Span};
 use lark_ty::base_inferred::BaseInferred;
 use lark_ty::map_family::Map;
+use lark_ty::BaseKind;
+use lark_ty::Ty;
+use lark_unify::InferVar;
 use lark_unify::UnificationTable;
+use std::cmp::Reverse;
 use std::sync::Arc;
 
 crate fn base_type_check(
     db: &impl TypeCheckDatabase,
     fn_entity: Entity,
 ) -> WithError<Arc<TypeCheckResults<BaseInferred>>> {
-    let fn_body = db.fn_body(fn_entity).into_value();
-    let interners = BaseInferenceTables::default();
-    let mut base_type_checker: TypeChecker<'_, BaseInference, _> = TypeChecker {
-        db,
-        fn_entity,
-        f_tables: interners.clone(),
-        hir: fn_body.clone(),
-        ops_arena: Arena::new(),
-        ops_blocked: FxIndexMap::default(),
-        unify: UnificationTable::new(interners.clone()),
-        storage: TypeCheckResults::default(),
-        universe_binders: IndexVec::from(vec![UniverseBinder::Root]),
-        errors: vec![],
-    };
-
+    let mut base_type_checker = new_base_type_checker(db, fn_entity);
     let mut unresolved_variables = base_type_checker.check_fn_body();
 
     // Record the final results. If any unresolved type variables are
@@ -45,18 +37,93 @@ crate fn base_type_check(
             &mut unresolved_variables,
         ));
 
-    let mut errors = base_type_checker.errors;
-    for _ in unresolved_variables {
-        // FIXME: Decent diagnostics for unresolved inference
-        // variables.
-        errors.push(Diagnostic::new(
-            "Unresolved variable".into(),
-            fn_body.span(fn_body.root_expression),
-        ));
-    }
+    base_type_checker.report_unresolved_variables(unresolved_variables);
 
     WithError {
         value: Arc::new(inferred_results),
-        errors,
+        errors: base_type_checker.errors,
+    }
+}
+
+/// Runs base inference on the body of `fn_entity` and returns the
+/// inference variables it stalled on, along with how many operations
+/// are blocked on each (see `TypeChecker::blocked_vars`). This is a
+/// debugging aid, and so is not memoized.
+pub fn blocked_vars(db: &impl TypeCheckDatabase, fn_entity: Entity) -> Vec<(InferVar, usize)> {
+    let mut base_type_checker = new_base_type_checker(db, fn_entity);
+    base_type_checker.check_fn_body();
+    base_type_checker.blocked_vars()
+}
+
+fn new_base_type_checker(
+    db: &impl TypeCheckDatabase,
+    fn_entity: Entity,
+) -> TypeChecker<'_, BaseInference, TypeCheckResults<BaseInference>> {
+    let fn_body = db.fn_body(fn_entity).into_value();
+    let interners = BaseInferenceTables::default();
+    TypeChecker {
+        db,
+        fn_entity,
+        f_tables: interners.clone(),
+        hir: fn_body,
+        ops_arena: Arena::new(),
+        ops_blocked: FxIndexMap::default(),
+        unify: UnificationTable::new(interners.clone()),
+        storage: TypeCheckResults::default(),
+        universe_binders: IndexVec::from(vec![UniverseBinder::Root]),
+        var_origins: FxIndexMap::default(),
+        discarded_calls: FxIndexSet::default(),
+        errors: vec![],
+        ops_limit_exceeded: false,
+    }
+}
+
+crate fn variable_type(
+    db: &impl TypeCheckDatabase,
+    fn_entity: Entity,
+    variable: hir::Variable,
+) -> WithError<Ty<BaseInferred>> {
+    let fn_body = db.fn_body(fn_entity).into_value();
+    let results = db.base_type_check(fn_entity).into_value();
+    match results.opt_ty(variable) {
+        Some(ty) if ty.base.untern(db).kind != BaseKind::Error => WithError::ok(ty),
+        _ => WithError::report_error(
+            db,
+            "could not infer the type of this variable".into(),
+            fn_body.span(variable),
+        ),
     }
 }
+
+crate fn call_sites(
+    db: &impl TypeCheckDatabase,
+    fn_entity: Entity,
+) -> Seq<(Option<Entity>, Span<FileName>)> {
+    let fn_body = db.fn_body(fn_entity).into_value();
+    let results = db.base_type_check(fn_entity).into_value();
+    let mut call_sites: Vec<_> = fn_body
+        .tables
+        .expressions
+        .iter_enumerated()
+        .filter_map(|(expression, data)| {
+            let target = match *data {
+                hir::ExpressionData::Call { function, .. } => {
+                    match results.opt_ty(function).map(|ty| ty.base.untern(db).kind) {
+                        Some(BaseKind::Named(entity)) => Some(entity),
+                        _ => None,
+                    }
+                }
+                hir::ExpressionData::MethodCall { method, .. } => {
+                    results.entities.get(&hir::MetaIndex::from(method)).cloned()
+                }
+                _ => return None,
+            };
+            Some((target, fn_body.span(expression)))
+        })
+        .collect();
+
+    // Sort into source order, with each call before any calls
+    // nested within it.
+    call_sites.sort_by_key(|&(_, span)| (span.start(), Reverse(span.end())));
+    Seq::from(call_sites)
+}
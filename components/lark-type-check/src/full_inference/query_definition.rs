@@ -5,14 +5,18 @@ use crate::full_inference::type_checker::FullInferenceStorage;
 use crate::full_inference::FullInference;
 use crate::full_inference::FullInferenceTables;
 use crate::results::TypeCheckResults;
+use crate::Resolution;
 use crate::TypeCheckDatabase;
 use crate::TypeChecker;
 use crate::UniverseBinder;
 use generational_arena::Arena;
-use lark_collections::{FxIndexMap, IndexVec};
-use lark_entity::Entity;
-use lark_error::Diagnostic;
+use lark_collections::{FxIndexMap, FxIndexSet, IndexVec};
+use lark_entity::{Entity, EntityData};
 use lark_error::WithError;
+use lark_hir as hir;
+use lark_intern::{Intern, Untern};
+use lark_parser::HoverTargetKind;
+use lark_span::{ByteIndex, FileName};
 use lark_ty::full_inferred::FullInferred;
 use lark_ty::map_family::Map;
 use lark_unify::UnificationTable;
@@ -34,7 +38,10 @@ crate fn full_type_check(
         unify: UnificationTable::new(interners.clone()),
         storage: FullInferenceStorage::default(),
         universe_binders: IndexVec::from(vec![UniverseBinder::Root]),
+        var_origins: FxIndexMap::default(),
+        discarded_calls: FxIndexSet::default(),
         errors: vec![],
+        ops_limit_exceeded: false,
     };
 
     type_checker.check_fn_body();
@@ -63,18 +70,67 @@ crate fn full_type_check(
             &perm_kinds,
         ));
 
+    type_checker.report_unresolved_variables(unresolved_variables);
     errors.extend(type_checker.errors);
-    for _ in unresolved_variables {
-        // FIXME: Decent diagnostics for unresolved inference
-        // variables.
-        errors.push(Diagnostic::new(
-            "Unresolved variable".into(),
-            fn_body.span(fn_body.root_expression),
-        ));
-    }
 
     WithError {
         value: Arc::new(inferred_results),
         errors,
     }
 }
+
+crate fn type_check_file(db: &impl TypeCheckDatabase, file: FileName) -> WithError<()> {
+    let mut errors = vec![];
+
+    let file_entity = EntityData::InputFile { file }.intern(db);
+    for &entity in db.descendant_entities(file_entity).iter() {
+        if entity.untern(db).has_fn_body() {
+            let _ = db.full_type_check(entity).accumulate_errors_into(&mut errors);
+        }
+    }
+
+    WithError { value: (), errors }
+}
+
+crate fn type_check_file_passes(db: &impl TypeCheckDatabase, file: FileName) -> bool {
+    let file_entity = EntityData::InputFile { file }.intern(db);
+    db.descendant_entities(file_entity)
+        .iter()
+        .filter(|entity| entity.untern(db).has_fn_body())
+        .all(|&entity| {
+            let errors = db.full_type_check(entity).errors;
+            errors.iter().all(|error| !error.is_error())
+        })
+}
+
+crate fn resolve_at(db: &impl TypeCheckDatabase, file: FileName, index: ByteIndex) -> Resolution {
+    let targets = db.hover_targets(file, index);
+
+    // The targets go from outermost to innermost.
+    let expression = targets.iter().rev().find_map(|target| match target.kind {
+        HoverTargetKind::MetaIndex(entity, hir::MetaIndex::Expression(expression)) => {
+            let ty = db.full_type_check(entity).into_value().opt_ty(expression)?;
+            Some(Resolution::Expression {
+                entity,
+                expression,
+                ty,
+            })
+        }
+        _ => None,
+    });
+    if let Some(resolution) = expression {
+        return resolution;
+    }
+
+    targets
+        .iter()
+        .rev()
+        .find_map(|target| match target.kind {
+            HoverTargetKind::Entity(entity) => match entity.untern(db) {
+                EntityData::InputFile { .. } => None,
+                _ => Some(Resolution::Entity(entity)),
+            },
+            HoverTargetKind::MetaIndex(..) => None,
+        })
+        .unwrap_or(Resolution::Nothing)
+}
@@ -7,15 +7,16 @@ use lark_collections::FxIndexSet;
 use lark_debug_derive::DebugWith;
 use lark_debug_with::DebugWith;
 use lark_entity::{Entity, EntityData, ItemKind, LangItem, MemberKind};
+use lark_error::Diagnostic;
 use lark_error::ErrorReported;
 use lark_error::ErrorSentinel;
 use lark_hir as hir;
-use lark_intern::Untern;
+use lark_intern::{Intern, Untern};
 use lark_pretty_print::PrettyPrint;
 use lark_ty::declaration::Declaration;
 use lark_ty::Signature;
 use lark_ty::Ty;
-use lark_ty::{BaseData, BaseKind};
+use lark_ty::{BaseData, BaseKind, BoundVarOr};
 use lark_unify::InferVar;
 use lark_unify::Inferable;
 
@@ -62,7 +63,7 @@ where
         // Complete all deferred type operations; run to steady state.
         loop {
             let vars: Vec<InferVar> = self.unify.drain_events().collect();
-            if vars.is_empty() {
+            if vars.is_empty() || self.ops_limit_exceeded {
                 break;
             }
             for var in vars {
@@ -72,6 +73,12 @@ where
 
         let mut unresolved_variables = vec![];
 
+        // If we gave up, we've already reported an error, and
+        // unresolved variables are to be expected.
+        if self.ops_limit_exceeded {
+            return unresolved_variables;
+        }
+
         // Look for any deferred operations that never executed. Those
         // variables that they are blocked on must not be resolved; record
         // as an error.
@@ -80,6 +87,63 @@ where
         unresolved_variables
     }
 
+    /// Reports an error for each of the given unresolved inference
+    /// variables, pointing at the variable or expression it came from
+    /// when that is known (and otherwise at the whole fn body). If
+    /// we gave up on type-checking, unresolved variables are to be
+    /// expected (and we've already reported an error), so nothing is
+    /// reported.
+    crate fn report_unresolved_variables(&mut self, unresolved_variables: Vec<InferVar>) {
+        if self.ops_limit_exceeded {
+            return;
+        }
+
+        for var in unresolved_variables {
+            let root = self.unify.root_var(var);
+            let mut origin = None;
+            for (&origin_var, &origin_index) in &self.var_origins {
+                if self.unify.root_var(origin_var) == root {
+                    origin = Some(origin_index);
+                    break;
+                }
+            }
+
+            let error = match origin {
+                Some(origin) => Diagnostic::new(
+                    format!(
+                        "Unresolved variable: cannot infer the type of {}",
+                        self.describe_origin(origin),
+                    ),
+                    self.hir.span(origin),
+                ),
+
+                None => Diagnostic::new(
+                    "Unresolved variable".into(),
+                    self.hir.span(self.hir.root_expression),
+                ),
+            };
+            self.errors.push(error);
+        }
+    }
+
+    /// Describes the variable or expression `origin` for use in
+    /// diagnostics, e.g. "the variable `x`".
+    fn describe_origin(&self, origin: hir::MetaIndex) -> String {
+        match origin {
+            hir::MetaIndex::Variable(variable) => {
+                let name = self.hir[self.hir[variable].name].text.untern(self);
+                format!("the variable `{}`", name)
+            }
+
+            hir::MetaIndex::Expression(expression) => match self.hir[expression] {
+                hir::ExpressionData::If { .. } => "the `if` expression".to_string(),
+                _ => "the expression".to_string(),
+            },
+
+            _ => "the value".to_string(),
+        }
+    }
+
     /// Type-check the expression `expression` in the given mode
     /// (either "check", which specifies the type the expression must
     /// have, or "synthesize").
@@ -97,13 +161,24 @@ where
         access_ty
     }
 
-    fn type_or_infer_variable(&mut self, mode: Mode<F>) -> Ty<F> {
+    fn type_or_infer_variable(&mut self, mode: Mode<F>, origin: hir::Expression) -> Ty<F> {
         match mode {
-            Synthesize => self.new_variable(),
+            Synthesize => self.new_variable_for(origin),
             CheckType(expected_ty, _) => expected_ty,
         }
     }
 
+    /// Creates a new type with a fresh inference variable standing
+    /// for the type of `origin`, which is recorded so that we can say
+    /// where the variable came from if it is never resolved.
+    crate fn new_variable_for(&mut self, origin: impl Into<hir::MetaIndex>) -> Ty<F> {
+        let ty: Ty<F> = self.new_variable();
+        if let Some(var) = ty.base.as_infer_var(&self.f_tables) {
+            self.var_origins.insert(var, origin.into());
+        }
+        ty
+    }
+
     /// Common helper for checking and synthesizing the type of an expression.
     ///
     /// If `expected_ty` is `None`, this will synthesize. Otherwise, it will consider
@@ -154,7 +229,7 @@ where
             }
 
             hir::ExpressionData::Sequence { first, second } => {
-                self.check_expression(CheckType(self.unit_type(), expression.into()), first);
+                self.check_discarded_expression(expression, first);
                 self.check_expression(mode, second)
             }
 
@@ -165,7 +240,7 @@ where
             } => {
                 self.check_expression(CheckType(self.boolean_type(), expression.into()), condition);
 
-                let ty = self.type_or_infer_variable(mode);
+                let ty = self.type_or_infer_variable(mode, expression);
                 self.check_expression(
                     CheckType(ty, HirLocation::AfterExpression(expression)),
                     if_true,
@@ -178,10 +253,7 @@ where
                 ty
             }
 
-            hir::ExpressionData::Literal { data } => match data.kind {
-                hir::LiteralKind::String => self.string_type(),
-                hir::LiteralKind::UnsignedInteger => self.uint_type(),
-            },
+            hir::ExpressionData::Literal { data } => self.literal_type(data.kind),
 
             hir::ExpressionData::Unit {} => self.unit_type(),
 
@@ -261,6 +333,56 @@ where
         }
     }
 
+    /// Helper for `check_expression`: type-check `discarded`, the
+    /// first half of `sequence`, whose value is thrown away. A call
+    /// may return a value that goes unused (see `check_unused_result`);
+    /// any other expression must produce unit.
+    fn check_discarded_expression(
+        &mut self,
+        sequence: hir::Expression,
+        discarded: hir::Expression,
+    ) {
+        match self.hir[discarded] {
+            hir::ExpressionData::Call { .. } | hir::ExpressionData::MethodCall { .. } => {
+                self.discarded_calls.insert(discarded);
+                self.check_expression(Synthesize, discarded);
+            }
+
+            _ => {
+                self.check_expression(CheckType(self.unit_type(), sequence.into()), discarded);
+            }
+        }
+    }
+
+    /// Helper for `check_fn_call` and `check_method_call`: warns if
+    /// `call` is a discarded call to `callee`, a fn marked
+    /// `@must_use` whose declared return type is not unit.
+    fn check_unused_result(
+        &mut self,
+        call: hir::Expression,
+        callee: Entity,
+        signature: &Signature<Declaration>,
+    ) {
+        if !self.discarded_calls.contains(&call) {
+            return;
+        }
+
+        let must_use = self.db.well_known_identifiers().must_use;
+        let annotations = self.db.entity_annotations(callee);
+        if annotations.iter().all(|a| a.name != must_use) {
+            return;
+        }
+
+        let unit = EntityData::LangItem(LangItem::Tuple(0)).intern(self);
+        match signature.output.base.untern(self) {
+            BoundVarOr::Known(BaseData {
+                kind: BaseKind::Named(entity),
+                ..
+            }) if entity == unit => {}
+            _ => self.record_warning("unused return value", call),
+        }
+    }
+
     /// Helper for `check_expression`: Compute the type from a method call.
     fn compute_fn_call_ty(
         &mut self,
@@ -313,6 +435,7 @@ where
                         <Signature<Declaration>>::error_sentinel(self, arguments.len())
                     }
                 };
+                self.check_unused_result(expression, entity, &signature_decl);
                 let signature = self.substitute(expression, &generics, signature_decl);
 
                 self.check_arguments_against_signature(
@@ -389,6 +512,7 @@ where
                         <Signature<Declaration>>::error_sentinel(self, arguments.len())
                     }
                 };
+                self.check_unused_result(expression, method_entity, &signature_decl);
                 let signature = self.substitute(expression, &generics, signature_decl);
 
                 // Relate the owner type to the input
@@ -588,6 +712,8 @@ where
                         this.check_binary_with_both_inputs_known(
                             expression,
                             operator,
+                            left_ty,
+                            right_ty,
                             left_base_data,
                             right_base_data,
                         )
@@ -620,6 +746,8 @@ where
         &mut self,
         expression: hir::Expression,
         operator: hir::BinaryOperator,
+        left_ty: Ty<F>,
+        right_ty: Ty<F>,
         left_base_data: BaseData<F>,
         right_base_data: BaseData<F>,
     ) -> Ty<F> {
@@ -639,7 +767,7 @@ where
                         EntityData::LangItem(LangItem::Int) => int_type,
                         EntityData::LangItem(LangItem::Uint) => uint_type,
                         EntityData::Error(_) => self.error_type(),
-                        _ => {
+                        EntityData::LangItem(_) => {
                             self.record_error(
                                 format!(
                                     "type {:?} does not support this operation",
@@ -649,6 +777,17 @@ where
                             );
                             self.error_type()
                         }
+
+                        // Other types may overload the operator with a
+                        // method (e.g., `add` for `+`).
+                        _ => self.check_operator_method(
+                            expression,
+                            operator,
+                            *entity,
+                            left_ty,
+                            right_ty,
+                            &left_base_data,
+                        ),
                     }
                 }
 
@@ -687,6 +826,70 @@ where
         }
     }
 
+    /// Invoked to check an arithmetic operator whose operands are
+    /// both of the type `def_id`, which is not a builtin numeric
+    /// type. Resolves the operator to the corresponding method of
+    /// that type (e.g., `a + b` to `a.add(b)`), recording it as the
+    /// entity for `expression`.
+    fn check_operator_method(
+        &mut self,
+        expression: hir::Expression,
+        operator: hir::BinaryOperator,
+        def_id: Entity,
+        left_ty: Ty<F>,
+        right_ty: Ty<F>,
+        left_base_data: &BaseData<F>,
+    ) -> Ty<F> {
+        let well_known = self.db.well_known_identifiers();
+        let method_name = match operator {
+            hir::BinaryOperator::Add => well_known.add,
+            hir::BinaryOperator::Subtract => well_known.sub,
+            hir::BinaryOperator::Multiply => well_known.mul,
+            hir::BinaryOperator::Divide => well_known.div,
+            // Only called for arithmetic operators; `==` and `!=` are
+            // checked by comparing the operand types directly.
+            hir::BinaryOperator::Equals | hir::BinaryOperator::NotEquals => {
+                unreachable!("no method for operator {:?}", operator)
+            }
+        };
+
+        let method_entity = self
+            .db
+            .member_entity(def_id, MemberKind::Method, method_name);
+        let method_entity = match method_entity {
+            Some(method_entity) => method_entity,
+            None => {
+                self.record_error(
+                    format!(
+                        "no `{}` operator for type {}",
+                        hir::operator_symbol(operator),
+                        left_base_data.pretty_print(self.db)
+                    ),
+                    expression,
+                );
+                return self.error_type();
+            }
+        };
+
+        self.record_entity(expression, method_entity);
+
+        let signature_decl = match self.db.signature(method_entity).into_value() {
+            Ok(s) => s,
+            Err(ErrorReported(_)) => <Signature<Declaration>>::error_sentinel(self, 2),
+        };
+        let signature = self.substitute(expression, &left_base_data.generics, signature_decl);
+
+        // The method is invoked as `left.method(right)`.
+        if signature.inputs.len() != 2 {
+            self.record_error("mismatched argument count", expression);
+            return self.error_type();
+        }
+        self.equate(expression, expression, left_ty, signature.inputs[0]);
+        self.equate(expression, expression, right_ty, signature.inputs[1]);
+
+        signature.output
+    }
+
     fn check_unary(
         &mut self,
         expression: hir::Expression,
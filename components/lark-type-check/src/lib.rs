@@ -6,13 +6,15 @@
 #![feature(trait_alias)]
 
 use generational_arena::Arena;
-use lark_collections::{FxIndexMap, IndexVec};
+use lark_collections::{FxIndexMap, FxIndexSet, IndexVec, Seq};
 use lark_debug_derive::DebugWith;
 use lark_entity::{Entity, EntityTables};
 use lark_error::{Diagnostic, WithError};
 use lark_hir as hir;
 use lark_parser::ParserDatabase;
 use lark_pretty_print::PrettyPrintDatabase;
+use lark_span::{ByteIndex, FileName, Span};
+use lark_string::GlobalIdentifierTables;
 use lark_ty::base_inferred::BaseInferred;
 use lark_ty::base_inferred::BaseInferredTables;
 use lark_ty::declaration::Declaration;
@@ -38,23 +40,88 @@ mod ops;
 mod results;
 mod substitute;
 
+/// Default value for the `type_check_ops_limit` input. This is meant
+/// to be generous enough that no reasonable fn body will ever hit it.
+pub const DEFAULT_TYPE_CHECK_OPS_LIMIT: usize = 100_000;
+
 #[salsa::query_group(TypeCheckStorage)]
 pub trait TypeCheckDatabase:
     ParserDatabase + AsRef<BaseInferredTables> + AsRef<FullInferredTables> + PrettyPrintDatabase
 {
+    /// The maximum number of suspended type-check operations that
+    /// may be pending at any one time while checking a single fn
+    /// body. If this is exceeded, we give up on type-checking that fn
+    /// body and report an error, rather than consuming unbounded
+    /// memory.
+    #[salsa::input]
+    fn type_check_ops_limit(&self) -> usize;
+
     /// Compute the "base type information" for a given fn body.
     /// This is the type information excluding permissions.
     #[salsa::invoke(base_inference::query_definition::base_type_check)]
     fn base_type_check(&self, key: Entity) -> WithError<Arc<TypeCheckResults<BaseInferred>>>;
 
+    /// Returns the type of the variable `variable` (e.g., one bound
+    /// by a `let`) in the fn body of `key`, whether declared or
+    /// inferred. If no type could be inferred, returns the error type
+    /// along with a diagnostic.
+    #[salsa::invoke(base_inference::query_definition::variable_type)]
+    fn variable_type(&self, key: Entity, variable: hir::Variable) -> WithError<Ty<BaseInferred>>;
+
+    /// Lists the calls and method calls in the fn body of `key`
+    /// (including nested ones) in source order, each with the fn or
+    /// method it invokes -- or `None` if that could not be resolved
+    /// -- and its span.
+    #[salsa::invoke(base_inference::query_definition::call_sites)]
+    fn call_sites(&self, key: Entity) -> Seq<(Option<Entity>, Span<FileName>)>;
+
     /// Compute the "base type information" for a given fn body.
     /// This is the type information excluding permissions.
     #[salsa::invoke(full_inference::query_definition::full_type_check)]
     fn full_type_check(&self, key: Entity) -> WithError<Arc<TypeCheckResults<FullInferred>>>;
+
+    /// Type-checks the fn body of every entity in `file` (including
+    /// methods), returning all of the type errors found.
+    #[salsa::invoke(full_inference::query_definition::type_check_file)]
+    fn type_check_file(&self, file: FileName) -> WithError<()>;
+
+    /// Like `type_check_file`, but only says whether the file is free
+    /// of type errors (warnings do not count). Stops at the first fn
+    /// body with an error, so the remaining bodies are never lowered
+    /// or checked; use this when a pass/fail answer is enough.
+    #[salsa::invoke(full_inference::query_definition::type_check_file_passes)]
+    fn type_check_file_passes(&self, file: FileName) -> bool;
+
+    /// Finds what is at `index` in `file`: the innermost expression
+    /// containing it (along with its type), or else the innermost
+    /// entity. Meant for hover, goto-definition and the like, so that
+    /// they need not each search the fn body themselves.
+    #[salsa::invoke(full_inference::query_definition::resolve_at)]
+    fn resolve_at(&self, file: FileName, index: ByteIndex) -> Resolution;
 }
 
+pub use base_inference::query_definition::blocked_vars;
 pub use results::TypeCheckResults;
 
+/// What is found at some position in a file; see `resolve_at`.
+#[derive(Clone, Debug, DebugWith, PartialEq, Eq)]
+pub enum Resolution {
+    /// An entity whose definition contains the position, if no
+    /// expression within it does.
+    Entity(Entity),
+
+    /// The innermost expression containing the position, within the
+    /// fn body of `entity`.
+    Expression {
+        entity: Entity,
+        expression: hir::Expression,
+        ty: Ty<FullInferred>,
+    },
+
+    /// Nothing is there (e.g., whitespace between items).
+    Nothing,
+}
+
 struct TypeChecker<'me, F: TypeCheckerFamily, S> {
     /// Salsa database.
     db: &'me dyn TypeCheckDatabase,
@@ -87,8 +154,21 @@ struct TypeChecker<'me, F: TypeCheckerFamily, S> {
     /// Information about each universe that we have created.
     universe_binders: IndexVec<Universe, UniverseBinder>,
 
+    /// For inference variables created to stand for the type of some
+    /// particular variable or expression, that HIR element. Used to
+    /// say where an unresolved variable came from.
+    var_origins: FxIndexMap<InferVar, hir::MetaIndex>,
+
+    /// Calls whose value is thrown away, being followed by more
+    /// expressions in a sequence; see `check_discarded_expression`.
+    discarded_calls: FxIndexSet<hir::Expression>,
+
     /// Errors that we encountered during the type-check.
     errors: Vec<Diagnostic>,
+
+    /// Set to true if we exceeded `type_check_ops_limit`; once this
+    /// happens, no further operations are enqueued.
+    ops_limit_exceeded: bool,
 }
 
 enum UniverseBinder {
@@ -173,7 +253,8 @@ where
     ///
     /// - field names, in places and aggregate expressions
     /// - method names, in calls
-    fn record_entity(&mut self, index: hir::Identifier, entity: Entity);
+    /// - binary expressions, for overloaded operators
+    fn record_entity(&mut self, index: impl Into<hir::MetaIndex>, entity: Entity);
 
     /// Records that `index` refers to `entity` and returns the
     /// generic parameters it uses to do so; may instantiate fresh
@@ -219,6 +300,15 @@ where
     }
 }
 
+impl<F, S> AsRef<GlobalIdentifierTables> for TypeChecker<'_, F, S>
+where
+    F: TypeCheckerFamily,
+{
+    fn as_ref(&self) -> &GlobalIdentifierTables {
+        self.db.as_ref()
+    }
+}
+
 impl<F, S> AsRef<EntityTables> for TypeChecker<'_, F, S>
 where
     F: TypeCheckerFamily,
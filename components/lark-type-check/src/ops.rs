@@ -62,8 +62,8 @@ where
         self.primitive_type(LangItem::Uint)
     }
 
-    crate fn string_type(&self) -> Ty<F> {
-        self.primitive_type(LangItem::String)
+    crate fn literal_type(&self, kind: hir::LiteralKind) -> Ty<F> {
+        self.primitive_type(hir::literal_lang_item(kind))
     }
 
     crate fn unit_type(&self) -> Ty<F> {
@@ -99,6 +99,38 @@ where
         self.errors.push(Diagnostic::new(label.into(), span));
     }
 
+    /// Record a warning at the given location; unlike an error, this
+    /// does not stop the program from being built.
+    crate fn record_warning(
+        &mut self,
+        label: impl Into<String>,
+        location: impl Into<hir::MetaIndex>,
+    ) {
+        let span = self.hir.span(location.into());
+        self.errors.push(Diagnostic::warning(label.into(), span));
+    }
+
+    /// Record that a type described as `found` could not be equated
+    /// with one described as `expected`. At `HirLocation::Return`,
+    /// `cause` is the trailing expression of the fn body and
+    /// `expected` is the declared return type.
+    crate fn record_type_mismatch(
+        &mut self,
+        cause: hir::MetaIndex,
+        location: HirLocation,
+        found: String,
+        expected: String,
+    ) {
+        let label = match location {
+            HirLocation::Return => format!(
+                "mismatched return type (expected {}, found {})",
+                expected, found
+            ),
+            _ => format!("mismatched types ({} vs {})", found, expected),
+        };
+        self.record_error(label, cause);
+    }
+
     crate fn own_perm(&mut self) -> F::Perm {
         F::own_perm(self)
     }
@@ -123,6 +155,7 @@ where
         let GenericDeclarations {
             parent_item,
             declarations,
+            ..
         } = &*self
             .db
             .generic_declarations(def_id)
@@ -160,6 +193,7 @@ where
         let GenericDeclarations {
             parent_item,
             declarations,
+            ..
         } = &*self
             .db
             .generic_declarations(entity)
@@ -258,6 +292,16 @@ where
         values: impl IntoIterator<Item = impl Inferable<F::InternTables>>,
         closure: impl FnOnce(&mut Self) + 'static,
     ) {
+        if self.ops_limit_exceeded {
+            return;
+        }
+
+        if self.ops_arena.len() >= self.db.type_check_ops_limit() {
+            self.ops_limit_exceeded = true;
+            self.record_error("type inference too complex", self.hir.root_expression);
+            return;
+        }
+
         let op: Box<dyn BoxedTypeCheckerOp<Self>> = Box::new(ClosureTypeCheckerOp { closure });
         let op_index = OpIndex {
             index: self.ops_arena.insert(op),
@@ -296,6 +340,27 @@ where
         }
     }
 
+    /// Lists the inference variables that still have operations
+    /// blocked on them, along with how many such operations each
+    /// has. Meant for debugging inference that has stalled; this does
+    /// not execute or remove any operations.
+    crate fn blocked_vars(&self) -> Vec<(InferVar, usize)> {
+        self.ops_blocked
+            .iter()
+            .filter_map(|(&var, blocked_ops)| {
+                let pending = blocked_ops
+                    .iter()
+                    .filter(|&&OpIndex { index }| self.ops_arena.contains(index))
+                    .count();
+                if pending > 0 {
+                    Some((var, pending))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     /// Records any inference variables that are have
     /// not-yet-triggered operations. These must all be currently
     /// unresolved.
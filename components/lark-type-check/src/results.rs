@@ -33,6 +33,8 @@ pub struct TypeCheckResults<F: TypeFamily> {
     /// - `foo.bar(..)` -- attached to the identifier `bar`, entity of the method
     /// - `Foo { a: b }` -- attached to the identifier `a`, entity of the field
     /// - `foo` -- when an identifier refers to an entity
+    /// - `a + b` -- attached to the expression, when the operator is a
+    ///   method of a struct (e.g., `add`)
     pub entities: std::collections::BTreeMap<hir::MetaIndex, Entity>,
 }
 
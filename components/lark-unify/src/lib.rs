@@ -182,6 +182,13 @@ impl<Interners, Cause> UnificationTable<Interners, Cause> {
         self.probe(var).is_some()
     }
 
+    /// Returns the representative of the variables that have been
+    /// unified with `var`: two unbound variables have been unified
+    /// exactly when they have the same representative.
+    pub fn root_var(&mut self, var: InferVar) -> InferVar {
+        self.find(var).0
+    }
+
     /// Creates a new inferable thing.
     pub fn new_inferable<K>(&mut self) -> K
     where
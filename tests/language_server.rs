@@ -1,8 +1,8 @@
 #[cfg(test)]
 mod tests {
     use languageserver_types::{
-        ClientCapabilities, DidOpenTextDocumentParams, InitializeParams, InitializeResult,
-        PublishDiagnosticsParams, TextDocumentItem,
+        ClientCapabilities, DiagnosticSeverity, DidOpenTextDocumentParams, InitializeParams,
+        InitializeResult, PublishDiagnosticsParams, TextDocumentItem,
     };
     use lark_language_server::{JsonRPCNotification, JsonRPCResponse, LSPCommand};
     use serde::{Deserialize, Serialize};
@@ -145,7 +145,14 @@ mod tests {
 
         assert_eq!(result.method, "textDocument/publishDiagnostics",);
         assert_eq!(result.params.diagnostics.len(), 1,);
-        assert_eq!(result.params.diagnostics[0].message, "mismatched types (uint vs bool)",);
+        assert_eq!(
+            result.params.diagnostics[0].message,
+            "mismatched return type (expected bool, found uint)",
+        );
+        assert_eq!(
+            result.params.diagnostics[0].severity,
+            Some(DiagnosticSeverity::Error)
+        );
 
         Ok(())
     }
@@ -1,5 +1,14 @@
+use lark_entity::EntityData;
+use lark_hir as hir;
+use lark_intern::Intern;
+use lark_parser::Direction;
+use lark_parser::LexToken;
 use lark_parser::ParserDatabase;
+use lark_parser::ParserDatabaseExt;
+use lark_parser::TokenDiff;
 use lark_span::ByteIndex;
+use lark_span::Span;
+use lark_span::Spanned;
 use lark_test::*;
 
 #[test]
@@ -42,3 +51,532 @@ fn location() {
         &loc_4,
     );
 }
+
+#[test]
+fn file_overlay() {
+    let file_name = "foo.lark";
+    let mut db = db_with_test(file_name, "def foo() { }");
+    let file_name = file_name.into_file_name(&db);
+    assert_eq!(&db.file_text(file_name)[..], "def foo() { }");
+
+    db.set_file_overlay(file_name, Some("def bar() { }".into()));
+    assert_eq!(&db.file_text(file_name)[..], "def bar() { }");
+
+    db.set_file_overlay(file_name, None);
+    assert_eq!(&db.file_text(file_name)[..], "def foo() { }");
+}
+
+#[test]
+fn parsed_entity_after_removal() {
+    let file_name = "foo.lark";
+    let mut db = db_with_test(file_name, "def foo() { }\ndef bar() { }\n");
+    let file_name = file_name.into_file_name(&db);
+    let file_entity = EntityData::InputFile { file: file_name }.intern(&db);
+    let bar = db.child_entities(file_entity)[1];
+
+    // A request made before the edit may still hold on to `bar`.
+    db.set_file_overlay(file_name, Some("def foo() { }\n".into()));
+    assert_eq!(db.child_entities(file_entity).len(), 1);
+
+    let parsed_bar = db.parsed_entity(bar);
+    assert_eq!(parsed_bar.entity, bar);
+    let fn_body = db.fn_body(bar).into_value();
+    match fn_body[fn_body.root_expression] {
+        hir::ExpressionData::Error { .. } => {}
+        ref data => panic!("expected an error, found {:?}", data),
+    }
+}
+
+#[test]
+fn indentation_levels() {
+    let file_name = "foo.lark";
+    let db = db_with_test(
+        file_name,
+        "def foo(c: bool) {\n// { not a brace\nif c {\n\"}\"\n}\nbar\n}\n",
+    );
+    let file_name = file_name.into_file_name(&db);
+    assert_eq!(
+        &db.indentation_levels(file_name)[..],
+        &[(0, 0), (1, 1), (2, 1), (3, 2), (4, 1), (5, 1), (6, 0)]
+    );
+}
+
+#[test]
+fn token_before() {
+    let file_name = "foo.lark";
+    let db = db_with_test(file_name, "foo.\ndef x");
+    let file_name = file_name.into_file_name(&db);
+    let text = db.file_text(file_name);
+    let token_text = |index: usize, skip_trivia: bool| {
+        let token = db
+            .token_before(file_name, ByteIndex::from(index), skip_trivia)
+            .unwrap();
+        text[token.span.start().to_usize()..token.span.end().to_usize()].to_string()
+    };
+
+    // `foo.|`
+    assert_eq!(token_text(4, false), ".");
+
+    // `def |x`
+    assert_eq!(token_text(9, false), " ");
+    assert_eq!(token_text(9, true), "def");
+}
+
+#[test]
+fn token_at_end_of_file() {
+    let file_name = "foo.lark";
+    let text = "def foo() {\n}\nfo";
+    let db = db_with_test(file_name, text);
+    let file_name = file_name.into_file_name(&db);
+    let end = ByteIndex::from(db.file_len(file_name));
+
+    // There is no token *at* the end of the file, so we get an EOF.
+    let token = db.token_at(file_name, end);
+    assert_eq!(token.value, LexToken::EOF);
+    assert_eq!(token.span, Span::new(file_name, text.len(), text.len()));
+
+    // But the identifier being typed is just before it.
+    let token = db.token_before(file_name, end, true).unwrap();
+    assert_eq!(token.value, LexToken::Identifier);
+    assert_eq!(&text[token.span.start().to_usize()..], "fo");
+
+    let token = db.token_at(file_name, ByteIndex::from(text.len() - 1));
+    assert_eq!(token.value, LexToken::Identifier);
+}
+
+#[test]
+fn identifier_span_at() {
+    let file_name = "foo.lark";
+    let text = "def foo(a: uint) -> uint {\n  a + 1\n}\n";
+    let db = db_with_test(file_name, text);
+    let file_name = file_name.into_file_name(&db);
+
+    // Inside `foo`:
+    assert_eq!(
+        db.identifier_span_at(file_name, ByteIndex::from(5)),
+        Some(Span::new(file_name, 4, 7))
+    );
+
+    // On the `def` keyword and on the `+` operator:
+    assert_eq!(db.identifier_span_at(file_name, ByteIndex::from(1)), None);
+    assert_eq!(db.identifier_span_at(file_name, ByteIndex::from(31)), None);
+}
+
+#[test]
+fn well_known_identifiers() {
+    let db = db_with_test("foo.lark", "");
+    let well_known = db.well_known_identifiers();
+    assert_eq!(well_known.int, "int".intern(&db));
+    assert_eq!(well_known.self_, "self".intern(&db));
+}
+
+#[test]
+fn parse_tokens() {
+    let file_name = "foo.lark";
+    let db = db_with_test(file_name, "def f() {\n}");
+    let file_name = file_name.into_file_name(&db);
+
+    let token = |value: LexToken, start: usize, end: usize| {
+        Spanned::new(value, Span::new(file_name, start, end))
+    };
+    let tokens = vec![
+        token(LexToken::Identifier, 0, 3),
+        token(LexToken::Whitespace, 3, 4),
+        token(LexToken::Identifier, 4, 5),
+        token(LexToken::Sigil, 5, 6),
+        token(LexToken::Sigil, 6, 7),
+        token(LexToken::Whitespace, 7, 8),
+        token(LexToken::Sigil, 8, 9),
+        token(LexToken::Newline, 9, 10),
+        token(LexToken::Sigil, 10, 11),
+    ];
+
+    let (entities, len) = lark_parser::parse_tokens(&db, file_name, &tokens).assert_no_errors();
+    let expected = db.parsed_file(file_name).into_value().entities;
+    assert_eq!(len, 11);
+    assert_eq!(entities.len(), expected.len());
+    for (entity, expected) in entities.iter().zip(expected.iter()) {
+        assert_eq!(entity.entity, expected.entity);
+        assert_eq!(entity.full_span, expected.full_span);
+    }
+
+    // Tokens that run past the end of the file are rejected.
+    let too_long = vec![token(LexToken::Identifier, 0, 12)];
+    assert!(!lark_parser::parse_tokens(&db, file_name, &too_long)
+        .errors
+        .is_empty());
+}
+
+#[test]
+fn enclosing_fn() {
+    let file_name = "foo.lark";
+    let db = db_with_test(
+        file_name,
+        "struct Foo {\n  x: uint\n}\ndef a() {\n}\ndef b() {\n  1\n}\n",
+    );
+    // `Foo` starts at 0, `a` at 25 and `b` at 37.
+    let file_name = file_name.into_file_name(&db);
+    let entities = db.top_level_entities_in_file(file_name);
+
+    assert_eq!(
+        db.enclosing_fn(file_name, ByteIndex::from(49)),
+        Some(entities[2])
+    );
+    assert_eq!(db.enclosing_fn(file_name, ByteIndex::from(15)), None);
+}
+
+#[test]
+fn format_file() {
+    let file_name = "foo.lark";
+    let db = db_with_test(
+        file_name,
+        "def   foo( a:uint,b : uint )->uint{\n// add them\nlet x=a+b\n      x\n}\n\n\n",
+    );
+    let file_name = file_name.into_file_name(&db);
+    let formatted = db.format_file(file_name).assert_no_errors();
+    assert_eq!(
+        formatted,
+        "def foo(a: uint, b: uint) -> uint {\n  // add them\n  let x = a + b\n  x\n}\n"
+    );
+}
+
+#[test]
+fn format_file_is_idempotent() {
+    let file_name = "foo.lark";
+    let text = "struct Foo {\n  x: uint\n}\n\ndef foo(a: uint) -> uint {\n  a\n}\n";
+    let db = db_with_test(file_name, text);
+    let file_name = file_name.into_file_name(&db);
+    let formatted = db.format_file(file_name).assert_no_errors();
+    assert_eq!(formatted, text);
+
+    let db = db_with_test("bar.lark", &formatted);
+    let reformatted = db
+        .format_file("bar.lark".into_file_name(&db))
+        .assert_no_errors();
+    assert_eq!(reformatted, formatted);
+}
+
+#[test]
+fn indentation_warnings() {
+    let file_name = "foo.lark";
+    let text = "def foo(a: uint) -> uint {\n a\n}\n";
+    let mut db = db_with_test(file_name, text);
+    let file_name = file_name.into_file_name(&db);
+
+    // Off by default.
+    assert!(db.indentation_warnings(file_name).is_empty());
+
+    db.set_check_indentation(true);
+    let warnings = db.indentation_warnings(file_name);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].label, "expected indentation depth of 1");
+    assert!(!warnings[0].is_error());
+    assert_eq!(warnings[0].span, Span::new(file_name, 27, 29));
+}
+
+#[test]
+fn naming_warnings() {
+    let file_name = "foo.lark";
+    let text = "struct point {\n}\n\ndef myFunc() {\n}\n\ndef _Ignored() {\n}\n";
+    let mut db = db_with_test(file_name, text);
+    let file_name = file_name.into_file_name(&db);
+
+    // Off by default.
+    assert!(db.naming_warnings(file_name).is_empty());
+
+    db.set_check_naming(true);
+    let warnings = db.naming_warnings(file_name);
+    let labels: Vec<&str> = warnings.iter().map(|w| &w.label[..]).collect();
+    assert_eq!(
+        labels,
+        vec![
+            "struct name `point` does not follow naming conventions",
+            "function name `myFunc` does not follow naming conventions",
+        ]
+    );
+    let suggestions: Vec<_> = warnings.iter().map(|w| w.suggestion.clone()).collect();
+    assert_eq!(
+        suggestions,
+        vec![Some("Point".to_string()), Some("my_func".to_string())]
+    );
+    assert!(warnings.iter().all(|w| !w.is_error()));
+    assert_eq!(warnings[0].span, Span::new(file_name, 7, 12));
+}
+
+#[test]
+fn dead_code_warnings() {
+    let file_name = "foo.lark";
+    let text = "def main() {\n  used()\n}\n\ndef used() {\n}\n\ndef dead() {\n  used()\n}\n";
+    let mut db = db_with_test(file_name, text);
+    let file_name = file_name.into_file_name(&db);
+
+    // Off by default.
+    assert!(db.dead_code_warnings(file_name).is_empty());
+
+    db.set_check_dead_code(true);
+    let warnings = db.dead_code_warnings(file_name);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].label, "function `dead` is never used");
+    assert_eq!(warnings[0].span, Span::new(file_name, 45, 49));
+    assert!(!warnings[0].is_error());
+
+    // Without a `main`, nothing is reported.
+    db.add_file(file_name, "def dead() {\n}\n");
+    assert!(db.dead_code_warnings(file_name).is_empty());
+}
+
+#[test]
+fn token_diff() {
+    let file_name = "foo.lark";
+    let mut db = db_with_test(file_name, "def foo(a: uint) {\n  a\n}\n");
+    let file_name = file_name.into_file_name(&db);
+    db.record_file_revision(file_name, 1);
+
+    db.add_file(file_name, "def foo(b: uint) {\n  a\n}\n");
+    db.record_file_revision(file_name, 2);
+
+    // Only the parameter name changed: it is the fifth token, after
+    // `def`, a space, `foo`, and `(`.
+    assert_eq!(
+        db.token_diff(file_name, 1, 2),
+        TokenDiff {
+            removed: 4..5,
+            inserted: 4..5,
+        }
+    );
+    let new_tokens = db.file_tokens_at_revision(file_name, 2).into_value();
+    assert_eq!(&db.file_text(file_name)[new_tokens[4].span], "b");
+
+    // Nothing changed between a revision and itself.
+    let diff = db.token_diff(file_name, 2, 2);
+    assert!(diff.removed.is_empty() && diff.inserted.is_empty());
+}
+
+#[test]
+fn token_histogram() {
+    let file_name = "foo.lark";
+    let db = db_with_test(file_name, "def foo(a: uint) {\n  a\n}\n");
+    let histogram = db.token_histogram(file_name.into_file_name(&db));
+
+    // `def`, `foo`, `a`, `uint`, and `a` again.
+    assert_eq!(histogram[&LexToken::Identifier], 5);
+    assert_eq!(histogram[&LexToken::Newline], 3);
+    assert!(!histogram.contains_key(&LexToken::Integer));
+}
+
+#[test]
+fn test_database_builder() {
+    let db = TestDatabase::builder()
+        .file("a.lark", "def a() {\n}\n")
+        .file("b.lark", "struct B {\n}\n")
+        .build();
+
+    let a = "a.lark".into_file_name(&db);
+    let b = "b.lark".into_file_name(&db);
+    assert_eq!(db.file_names().len(), 2);
+    assert_eq!(&db.file_text(a)[..], "def a() {\n}\n");
+    assert_eq!(&db.file_text(b)[..], "struct B {\n}\n");
+
+    for &file_name in &[a, b] {
+        let parsed_file = db.parsed_file(file_name).assert_no_errors();
+        assert_eq!(parsed_file.entities().len(), 1);
+    }
+}
+
+#[test]
+fn sibling_entity() {
+    let file_name = "foo.lark";
+    let db = db_with_test(file_name, "def a() {\n}\ndef b() {\n}\ndef c() {\n}\n");
+    let file_name = file_name.into_file_name(&db);
+    let entities = db.top_level_entities_in_file(file_name);
+    assert_eq!(entities.len(), 3);
+
+    assert_eq!(
+        db.sibling_entity(entities[0], Direction::Next),
+        Some(entities[1])
+    );
+    assert_eq!(db.sibling_entity(entities[0], Direction::Previous), None);
+    assert_eq!(
+        db.sibling_entity(entities[2], Direction::Previous),
+        Some(entities[1])
+    );
+    assert_eq!(db.sibling_entity(entities[2], Direction::Next), None);
+}
+
+#[test]
+fn normalize_newlines() {
+    let file_name = "foo.lark";
+    let mut db = db_with_test(file_name, "a\r\nb\rc\nd");
+    let file_name = file_name.into_file_name(&db);
+
+    // Off by default: the lone `\r` does not end a line.
+    assert_eq!(&db.line_offsets(file_name)[..], &[0, 3, 7, 8]);
+
+    db.set_normalize_newlines(true);
+    assert_eq!(&db.file_text(file_name)[..], "a\nb\nc\nd");
+    assert_eq!(&db.line_offsets(file_name)[..], &[0, 2, 4, 6, 7]);
+
+    // Each line starts where `line_offsets` says it does, and
+    // `byte_index` maps the location back to the same byte.
+    for (line, &offset) in db.line_offsets(file_name).iter().enumerate().take(4) {
+        let location = db.location(file_name, ByteIndex::from(offset));
+        assert_eq!((location.line, location.column), (line, 0));
+        assert_eq!(
+            db.byte_index(file_name, line as u64, 0),
+            ByteIndex::from(offset)
+        );
+    }
+}
+
+#[test]
+fn empty_file() {
+    let file_name = "foo.lark";
+    let db = db_with_test(file_name, "");
+    let file_name = file_name.into_file_name(&db);
+
+    let parsed_file = db.parsed_file(file_name).assert_no_errors();
+    assert!(parsed_file.entities.is_empty());
+    assert_eq!(&db.line_offsets(file_name)[..], &[0]);
+
+    let file_entity = EntityData::InputFile { file: file_name }.intern(&db);
+    assert_eq!(&db.descendant_entities(file_entity)[..], &[file_entity]);
+
+    let location = db.location(file_name, ByteIndex::from(0));
+    assert_eq!((location.line, location.column), (0, 0));
+
+    // Positions beyond the end clamp rather than panic.
+    assert_eq!(db.byte_index(file_name, 3, 4), ByteIndex::from(0));
+    assert_eq!(db.location(file_name, ByteIndex::from(7)).line, 0);
+}
+
+#[test]
+fn file_len() {
+    let file_name = "foo.lark";
+    let mut db = db_with_test(file_name, "def a() {\n}\n");
+    let file_name = file_name.into_file_name(&db);
+    assert_eq!(db.file_len(file_name), db.file_text(file_name).len());
+
+    // A second call is answered from the cache, with the same result.
+    assert_eq!(db.file_len(file_name), 12);
+
+    // Overlays take precedence over the text on disk.
+    db.set_file_overlay(file_name, Some("def b() {}".into()));
+    assert_eq!(db.file_len(file_name), 10);
+}
+
+#[test]
+fn edit_invalidates_only_edited_file() {
+    let mut db = db_with_test("a.lark", "def a() {\n}\n");
+    db.add_file("b.lark", "def b() {\n}\n");
+    let a = "a.lark".into_file_name(&db);
+    let b = "b.lark".into_file_name(&db);
+    db.parsed_file(a);
+    db.parsed_file(b);
+
+    db.record_executed_queries();
+    db.set_file_overlay(a, Some("def a2() {\n}\n".into()));
+    db.parsed_file(a);
+    db.parsed_file(b);
+
+    let executed = db.take_executed_queries();
+    let executed_for = |query: &str, file_name| {
+        let file_name = format!("{:?}", file_name);
+        executed
+            .iter()
+            .any(|key| key.contains(query) && key.contains(&file_name))
+    };
+    assert!(executed_for("file_tokens", a));
+    assert!(executed_for("parsed_file", a));
+    assert!(!executed_for("file_tokens", b));
+    assert!(!executed_for("parsed_file", b));
+}
+
+#[test]
+fn fn_bodies_are_lowered_lazily() {
+    let db = db_with_test("a.lark", "def a() {\n}\n\ndef b() {\n}\n");
+    let file_name = "a.lark".into_file_name(&db);
+    let file_entity = EntityData::InputFile { file: file_name }.intern(&db);
+
+    // Opening the file parses it and finds its entities, but lowers
+    // no fn bodies.
+    db.record_executed_queries();
+    db.parsed_file(file_name).assert_no_errors();
+    let entities = db.child_entities(file_entity);
+    assert!(!db
+        .take_executed_queries()
+        .iter()
+        .any(|key| key.contains("fn_body")));
+
+    // Asking for one body lowers just that body.
+    db.record_executed_queries();
+    db.fn_body(entities[0]).assert_no_errors();
+    let executed = db.take_executed_queries();
+    assert_eq!(
+        executed
+            .iter()
+            .filter(|key| key.contains("fn_body"))
+            .count(),
+        1
+    );
+
+    // Lowering everything up front picks up the rest.
+    db.record_executed_queries();
+    db.lower_all_bodies(file_name).assert_no_errors();
+    let executed = db.take_executed_queries();
+    assert_eq!(
+        executed
+            .iter()
+            .filter(|key| key.contains("fn_body"))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn unicode_identifiers() {
+    let file_name = "foo.lark";
+    let mut db = db_with_test(file_name, "def café() {}");
+    let file_name = file_name.into_file_name(&db);
+
+    let token = db.token_at(file_name, ByteIndex::from(4));
+    assert_eq!(token.value, LexToken::Identifier);
+    assert_eq!(token.span, Span::new(file_name, 4, 9));
+    assert!(db.file_tokens(file_name).errors.is_empty());
+
+    db.set_ascii_identifiers(true);
+    let token = db.token_at(file_name, ByteIndex::from(4));
+    assert_eq!(token.value, LexToken::Error);
+    let errors = db.file_tokens(file_name).errors;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].label,
+        "identifier `café` contains non-ASCII characters"
+    );
+}
+
+#[test]
+fn invisible_characters() {
+    let file_name = "foo.lark";
+    let db = db_with_test(file_name, "def a\u{200B}b() {}");
+    let file_name = file_name.into_file_name(&db);
+
+    let errors = db.file_tokens(file_name).errors;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].label,
+        "invisible character U+200B is not allowed here"
+    );
+    assert_eq!(errors[0].span, Span::new(file_name, 5, 8));
+}
+
+#[test]
+fn unterminated_block_comment() {
+    let file_name = "foo.lark";
+    let db = db_with_test(file_name, "def f() {}\n/* {\n");
+    let file_name = file_name.into_file_name(&db);
+
+    let errors = db.file_tokens(file_name).errors;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].label, "unterminated block comment");
+    assert_eq!(errors[0].span, Span::new(file_name, 11, 13));
+}
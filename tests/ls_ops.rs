@@ -0,0 +1,361 @@
+use languageserver_types::{DocumentHighlightKind, Position, Range};
+use lark_parser::{ParserDatabase, ParserDatabaseExt};
+use lark_query_system::ls_ops::LsDatabase;
+use lark_span::Span;
+use lark_test::*;
+
+fn range(start: (u64, u64), end: (u64, u64)) -> Range {
+    Range::new(
+        Position::new(start.0, start.1),
+        Position::new(end.0, end.1),
+    )
+}
+
+/// Applies the edit of a code action that inserts `new_text` at a
+/// single (ASCII-only) position, and asserts that the result has no
+/// errors.
+fn assert_edit_is_clean(text: &str, range: Range, new_text: &str) {
+    assert_eq!(range.start, range.end);
+    let mut lines: Vec<String> = text.lines().map(|line| line.to_string()).collect();
+    lines[range.start.line as usize].insert_str(range.start.character as usize, new_text);
+    let edited = lines.join("\n");
+
+    let (_file_name, db) = lark_parser_db(edited);
+    let errors = db.errors_for_project().ok().unwrap();
+    assert!(errors.values().all(|errors| errors.is_empty()));
+}
+
+#[test]
+fn document_highlight_variable() {
+    let (_file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def foo() -> uint {
+          let x = 22
+          x + x
+        }
+        ",
+    ));
+
+    let highlights = db
+        .document_highlights_at_position("path1", Position::new(1, 6))
+        .ok()
+        .unwrap();
+
+    assert_eq!(
+        highlights,
+        vec![
+            (range((1, 6), (1, 7)), DocumentHighlightKind::Write),
+            (range((2, 2), (2, 3)), DocumentHighlightKind::Read),
+            (range((2, 6), (2, 7)), DocumentHighlightKind::Read),
+        ]
+    );
+}
+
+#[test]
+fn inlay_hints_for_let() {
+    let (_file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def foo() -> uint {
+          let x = 22
+          x
+        }
+        ",
+    ));
+
+    let hints = db
+        .inlay_hints_in_range("path1", range((0, 0), (3, 1)))
+        .ok()
+        .unwrap();
+
+    assert_eq!(hints, vec![(Position::new(1, 7), ": uint".to_string())]);
+}
+
+#[test]
+fn references_report_progress() {
+    let (_file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def foo() -> uint {
+          1
+        }
+        def bar() -> uint {
+          foo()
+        }
+        def baz() -> uint {
+          foo()
+        }
+        ",
+    ));
+
+    let mut progress = vec![];
+    let references = db
+        .find_all_references_at_position("path1", Position::new(4, 2), &mut |done, total| {
+            progress.push((done, total))
+        })
+        .ok()
+        .unwrap();
+
+    assert_eq!(references.len(), 3);
+    assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+}
+
+#[test]
+fn span_to_range() {
+    let db = db_with_test("foo.lark", "a\u{1F600}b\ncd\n");
+    let file_name = "foo.lark".into_file_name(&db);
+
+    // The emoji is four bytes in UTF-8 but two UTF-16 code units.
+    assert_eq!(
+        db.span_to_range(Span::new(file_name, 5, 8)),
+        range((0, 3), (1, 1))
+    );
+
+    assert_eq!(
+        db.span_to_range(Span::new(file_name, 7, 7)),
+        range((1, 0), (1, 0))
+    );
+}
+
+#[test]
+fn workspace_symbols() {
+    let mut db = db_with_test("file1", "struct Point {\n  x: uint\n}\n");
+    db.add_file("file2", "def poll() {\n}\ndef other() {\n}\n");
+
+    let mut names: Vec<String> = db
+        .workspace_symbols("po")
+        .ok()
+        .unwrap()
+        .into_iter()
+        .map(|(name, ..)| name)
+        .collect();
+    names.sort();
+
+    assert_eq!(names, vec!["Point".to_string(), "poll".to_string()]);
+}
+
+#[test]
+fn hover_shows_signature_and_docs() {
+    let (_file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        /// Returns one.
+        def one() -> uint {
+          1
+        }
+        def bar() -> uint {
+          one()
+        }
+        ",
+    ));
+
+    let content = db
+        .hover_at_position("path1", Position::new(5, 3))
+        .ok()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(content.signature, "def one() -> uint");
+    assert_eq!(content.docs, Some("Returns one.".to_string()));
+    assert_eq!(
+        content.to_markdown(),
+        "```lark\ndef one() -> uint\n```\n\nReturns one."
+    );
+}
+
+#[test]
+fn incoming_calls() {
+    let (_file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def foo() -> uint {
+          1
+        }
+        def bar() -> uint {
+          foo()
+        }
+        def baz() -> uint {
+          foo() + foo()
+        }
+        ",
+    ));
+
+    let calls = db
+        .incoming_calls_at_position("path1", Position::new(0, 4))
+        .ok()
+        .unwrap();
+
+    assert_eq!(
+        calls,
+        vec![
+            (
+                "bar".to_string(),
+                "path1".to_string(),
+                range((4, 2), (4, 5))
+            ),
+            (
+                "baz".to_string(),
+                "path1".to_string(),
+                range((7, 2), (7, 5))
+            ),
+            (
+                "baz".to_string(),
+                "path1".to_string(),
+                range((7, 10), (7, 13))
+            ),
+        ]
+    );
+}
+
+#[test]
+fn diagnostics_to_json() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def main() {
+          let foo = 1
+          fo
+        }
+        ",
+    ));
+
+    let main = db.top_level_entities_in_file(file_name)[0];
+    let errors = db.fn_body(main).errors;
+    let json: serde_json::Value = serde_json::from_str(&db.diagnostics_to_json(&errors)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([{
+            "file": "path1",
+            "range": {
+                "start": { "line": 2, "character": 2 },
+                "end": { "line": 2, "character": 4 },
+            },
+            "severity": "error",
+            "message": "unknown identifier `fo`",
+            "suggestion": "foo",
+        }])
+    );
+}
+
+#[test]
+fn selection_ranges() {
+    let (_file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def foo(a: uint, b: uint) -> uint {
+          a + b
+        }
+        ",
+    ));
+
+    let ranges = db
+        .selection_ranges_at_position("path1", Position::new(1, 2))
+        .ok()
+        .unwrap();
+
+    assert_eq!(
+        ranges,
+        vec![
+            range((1, 2), (1, 3)),
+            range((1, 2), (1, 7)),
+            range((0, 34), (2, 1)),
+            range((0, 0), (2, 1)),
+        ]
+    );
+}
+
+#[test]
+fn code_action_fills_in_missing_fields() {
+    let text = unindent::unindent(
+        "
+        struct Point {
+          x: uint
+          y: uint
+        }
+
+        def origin() -> Point {
+          Point(x: 1)
+        }
+        ",
+    );
+    let (_file_name, db) = lark_parser_db(&text);
+
+    let actions = db
+        .code_actions_in_range("path1", range((6, 2), (6, 13)))
+        .ok()
+        .unwrap();
+
+    assert_eq!(
+        actions,
+        vec![(
+            "Fill in missing fields: y".to_string(),
+            range((6, 12), (6, 12)),
+            ", y: 0".to_string(),
+        )]
+    );
+
+    let (_, edit_range, new_text) = &actions[0];
+    assert_edit_is_clean(&text, *edit_range, new_text);
+}
+
+#[test]
+fn code_action_fills_in_all_fields() {
+    let text = unindent::unindent(
+        "
+        struct Point {
+          x: uint
+          y: uint
+        }
+
+        def origin() -> Point {
+          Point()
+        }
+        ",
+    );
+    let (_file_name, db) = lark_parser_db(&text);
+
+    let actions = db
+        .code_actions_in_range("path1", range((6, 2), (6, 9)))
+        .ok()
+        .unwrap();
+
+    assert_eq!(
+        actions,
+        vec![(
+            "Fill in missing fields: x, y".to_string(),
+            range((6, 8), (6, 8)),
+            "x: 0, y: 0".to_string(),
+        )]
+    );
+
+    let (_, edit_range, new_text) = &actions[0];
+    assert_edit_is_clean(&text, *edit_range, new_text);
+}
+
+#[test]
+fn code_action_after_trailing_comma() {
+    let text = unindent::unindent(
+        "
+        struct Point {
+          x: uint
+          y: uint
+        }
+
+        def origin() -> Point {
+          Point(x: 1,)
+        }
+        ",
+    );
+    let (_file_name, db) = lark_parser_db(&text);
+
+    let actions = db
+        .code_actions_in_range("path1", range((6, 2), (6, 14)))
+        .ok()
+        .unwrap();
+
+    assert_eq!(
+        actions,
+        vec![(
+            "Fill in missing fields: y".to_string(),
+            range((6, 13), (6, 13)),
+            " y: 0".to_string(),
+        )]
+    );
+
+    let (_, edit_range, new_text) = &actions[0];
+    assert_edit_is_clean(&text, *edit_range, new_text);
+}
@@ -2,14 +2,17 @@ use lark_debug_derive::DebugWith;
 use lark_debug_with::DebugWith;
 use lark_entity::Entity;
 use lark_entity::EntityData;
+use lark_entity::LangItem;
 use lark_hir as hir;
 use lark_intern::Intern;
 use lark_intern::Untern;
 use lark_parser::ParserDatabase;
 use lark_query_system::LarkDatabase;
 use lark_span::FileName;
+use lark_span::Span;
 use lark_string::GlobalIdentifierTables;
 use lark_test::*;
+use lark_ty::{BaseKind, BoundVarOr};
 
 #[derive(Debug, DebugWith, PartialEq, Eq)]
 struct EntityTree {
@@ -372,19 +375,27 @@ fn eof_extra_sigil() {
             [
                 Diagnostic {
                     span: synthetic,
-                    label: "unexpected character"
+                    label: "unexpected character",
+                    severity: Error,
+                    suggestion: None
                 },
                 Diagnostic {
                     span: synthetic,
-                    label: "unexpected character"
+                    label: "unexpected character",
+                    severity: Error,
+                    suggestion: None
                 },
                 Diagnostic {
                     span: synthetic,
-                    label: "unexpected character"
+                    label: "unexpected character",
+                    severity: Error,
+                    suggestion: None
                 },
                 Diagnostic {
                     span: synthetic,
-                    label: "unexpected character"
+                    label: "unexpected character",
+                    severity: Error,
+                    suggestion: None
                 }
             ]"#,
         ),
@@ -703,3 +714,1232 @@ fn parse_call_variations() {
     };
     assert_equal(&(), &debug1, &debug2);
 }
+
+#[test]
+fn unknown_identifier_suggestion() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def foo() {
+              let width = 22
+              widht
+            }
+        ",
+    ));
+
+    let fn_body = db.fn_body(select_entity(&db, file_name, 0));
+    assert_eq!(fn_body.errors.len(), 1);
+    assert_eq!(fn_body.errors[0].label, "unknown identifier `widht`");
+    assert_eq!(fn_body.errors[0].suggestion.as_ref().unwrap(), "width");
+}
+
+#[test]
+fn string_unicode_escape() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        r#"
+            def foo() -> String {
+              "\u{48}i"
+            }
+        "#,
+    ));
+
+    let fn_body = db
+        .fn_body(select_entity(&db, file_name, 0))
+        .assert_no_errors();
+    match fn_body[fn_body.root_expression] {
+        hir::ExpressionData::Literal { data } => {
+            assert_eq!(data.value.untern(&db).to_string(), "Hi");
+        }
+        ref data => panic!("expected a literal, found {:?}", data),
+    }
+}
+
+#[test]
+fn string_escapes() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        r#"
+            def foo() -> String {
+              "a\n\\\u{22}\u{5C}n"
+            }
+        "#,
+    ));
+
+    let fn_body = db
+        .fn_body(select_entity(&db, file_name, 0))
+        .assert_no_errors();
+    match fn_body[fn_body.root_expression] {
+        hir::ExpressionData::Literal { data } => {
+            // Each escape is decoded exactly once.
+            assert_eq!(data.value.untern(&db).to_string(), "a\n\\\"\\n");
+        }
+        ref data => panic!("expected a literal, found {:?}", data),
+    }
+}
+
+#[test]
+fn string_invalid_unicode_escape() {
+    let text = unindent::unindent(
+        r#"
+            def foo() -> String {
+              "\u{FFFFFF}"
+            }
+        "#,
+    );
+    let escape = text.find(r"\u").unwrap();
+    let (file_name, db) = lark_parser_db(&text);
+
+    let fn_body = db.fn_body(select_entity(&db, file_name, 0));
+    assert_eq!(fn_body.errors.len(), 1);
+    assert_eq!(
+        fn_body.errors[0].label,
+        r"invalid unicode code point `\u{FFFFFF}`"
+    );
+    assert_eq!(
+        fn_body.errors[0].span,
+        Span::new(file_name, escape, escape + r"\u{FFFFFF}".len())
+    );
+    match fn_body.value[fn_body.value.root_expression] {
+        hir::ExpressionData::Error { .. } => {}
+        ref data => panic!("expected an error, found {:?}", data),
+    }
+}
+
+#[test]
+fn unknown_identifier_no_suggestion() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def foo() {
+              let width = 22
+              height
+            }
+        ",
+    ));
+
+    let fn_body = db.fn_body(select_entity(&db, file_name, 0));
+    assert_eq!(fn_body.errors.len(), 1);
+    assert_eq!(fn_body.errors[0].label, "unknown identifier `height`");
+    assert_eq!(fn_body.errors[0].span, Span::new(file_name, 31, 37));
+}
+
+#[test]
+fn declaring_file() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Foo {
+          x: uint
+        }
+        ",
+    ));
+
+    let foo = select_entity(&db, file_name, 0);
+    let x = db.child_entities(foo)[0];
+    assert_eq!(db.declaring_file(x), Some(file_name));
+
+    let uint = EntityData::LangItem(LangItem::Uint).intern(&db);
+    assert_eq!(db.declaring_file(uint), None);
+}
+
+#[test]
+fn parse_if_branches_value_or_unit() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def foo(c: bool) -> uint {
+              if c { 1 } else { 2 }
+            }
+        ",
+    ));
+    let fn_body = db
+        .fn_body(select_entity(&db, file_name, 0))
+        .assert_no_errors();
+    match fn_body[fn_body.root_expression] {
+        hir::ExpressionData::If {
+            if_true, if_false, ..
+        } => {
+            for branch in vec![if_true, if_false] {
+                match fn_body[branch] {
+                    hir::ExpressionData::Literal { .. } => {}
+                    ref data => panic!("expected a literal, found {:?}", data),
+                }
+            }
+        }
+        ref data => panic!("expected an `if`, found {:?}", data),
+    }
+
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def foo(c: bool, a: uint, b: uint) {
+              if c { a; } else { b; }
+            }
+        ",
+    ));
+    let fn_body = db
+        .fn_body(select_entity(&db, file_name, 0))
+        .assert_no_errors();
+    match fn_body[fn_body.root_expression] {
+        hir::ExpressionData::If {
+            if_true, if_false, ..
+        } => {
+            for branch in vec![if_true, if_false] {
+                match fn_body[branch] {
+                    hir::ExpressionData::Sequence { second, .. } => match fn_body[second] {
+                        hir::ExpressionData::Unit {} => {}
+                        ref data => panic!("expected unit, found {:?}", data),
+                    },
+                    ref data => panic!("expected a sequence, found {:?}", data),
+                }
+            }
+        }
+        ref data => panic!("expected an `if`, found {:?}", data),
+    }
+}
+
+#[test]
+fn parse_else_if_chain() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def foo(a: bool, b: bool, c: bool) -> uint {
+              if a { 1 } else if b { 2 } else if c { 3 } else { 4 }
+            }
+        ",
+    ));
+    let fn_body = db
+        .fn_body(select_entity(&db, file_name, 0))
+        .assert_no_errors();
+
+    // Each `else if` is the `if_false` of the previous `if`, with no
+    // block in between.
+    let mut expression = fn_body.root_expression;
+    let mut ifs = 0;
+    while let hir::ExpressionData::If { if_false, .. } = fn_body[expression] {
+        ifs += 1;
+        expression = if_false;
+    }
+    assert_eq!(ifs, 3);
+    match fn_body[expression] {
+        hir::ExpressionData::Literal { .. } => {}
+        ref data => panic!("expected a literal, found {:?}", data),
+    }
+}
+
+#[test]
+fn parse_statements_separated_by_newlines() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def foo(a: uint, b: uint) -> uint {
+              a
+              a +
+                b
+            }
+        ",
+    ));
+    let fn_body = db
+        .fn_body(select_entity(&db, file_name, 0))
+        .assert_no_errors();
+
+    // The newline after `a` ends the first statement, but the one
+    // after `+` does not.
+    match fn_body[fn_body.root_expression] {
+        hir::ExpressionData::Sequence { first, second } => {
+            match fn_body[first] {
+                hir::ExpressionData::Place { .. } => {}
+                ref data => panic!("expected a place, found {:?}", data),
+            }
+            match fn_body[second] {
+                hir::ExpressionData::Binary { .. } => {}
+                ref data => panic!("expected a binary expression, found {:?}", data),
+            }
+        }
+        ref data => panic!("expected a sequence, found {:?}", data),
+    }
+}
+
+#[test]
+fn parse_operator_at_start_of_line() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def foo(a: uint, b: uint) -> uint {
+              a
+              + b
+            }
+        ",
+    ));
+    let fn_body = db.fn_body(select_entity(&db, file_name, 0));
+    assert_eq!(fn_body.errors.len(), 1);
+    assert_eq!(
+        fn_body.errors[0].label,
+        "a binary operator cannot start a line; move it to the end of the previous line"
+    );
+}
+
+#[test]
+fn missing_return_values() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def f(c: bool) -> uint {
+              if c { 1 }
+            }
+
+            def g(c: bool) -> uint {
+              if c { 1 } else { 2 }
+            }
+
+            def h(c: bool) {
+              if c { debug(1) }
+            }
+        ",
+    ));
+
+    let f = select_entity(&db, file_name, 0);
+    let diagnostics = db.missing_return_values(f);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].label, "not all control paths return a value");
+    // Reported at the `if` keyword.
+    assert_eq!(diagnostics[0].span, Span::new(file_name, 27, 29));
+
+    // Every path produces a value, or none needs to.
+    let g = select_entity(&db, file_name, 1);
+    assert!(db.missing_return_values(g).is_empty());
+    let h = select_entity(&db, file_name, 2);
+    assert!(db.missing_return_values(h).is_empty());
+}
+
+#[test]
+fn free_variables() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def foo(b: uint) -> uint {
+              { let a = 1; a + b }
+            }
+        ",
+    ));
+
+    let foo = select_entity(&db, file_name, 0);
+    let fn_body = db.fn_body(foo).assert_no_errors();
+    let names: Vec<String> = db
+        .free_variables(foo, fn_body.root_expression)
+        .iter()
+        .map(|&variable| fn_body[fn_body[variable].name].text.untern(&db).to_string())
+        .collect();
+    assert_eq!(names, vec!["b".to_string()]);
+}
+
+#[test]
+fn unused_parameters() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def f(a: uint, b: uint, _c: uint) -> uint {
+              a
+            }
+        ",
+    ));
+
+    let f = select_entity(&db, file_name, 0);
+    let fn_body = db.fn_body(f).assert_no_errors();
+    let names: Vec<String> = db
+        .unused_parameters(f)
+        .iter()
+        .map(|&(variable, _)| fn_body[fn_body[variable].name].text.untern(&db).to_string())
+        .collect();
+    assert_eq!(names, vec!["b".to_string()]);
+}
+
+#[test]
+fn expression_depth() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def flat() -> uint {
+              22
+            }
+
+            def nested(a: uint) -> uint {
+              a + 1 + 2 + 3
+            }
+        ",
+    ));
+
+    let flat = select_entity(&db, file_name, 0);
+    db.fn_body(flat).assert_no_errors();
+    assert_eq!(db.expression_depth(flat), 1);
+
+    // `((a + 1) + 2) + 3`: three nested additions, plus the operands
+    // of the innermost one.
+    let nested = select_entity(&db, file_name, 1);
+    db.fn_body(nested).assert_no_errors();
+    assert_eq!(db.expression_depth(nested), 4);
+}
+
+#[test]
+fn source_map() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+            def foo(a: uint) -> uint {
+              let b = a + 1
+              b * 2
+            }
+        ",
+    ));
+    let foo = select_entity(&db, file_name, 0);
+    let fn_body = db.fn_body(foo).assert_no_errors();
+
+    let (multiply, _) = fn_body
+        .tables
+        .expressions
+        .iter_enumerated()
+        .find(|(_, data)| match data {
+            hir::ExpressionData::Binary {
+                operator: hir::BinaryOperator::Multiply,
+                ..
+            } => true,
+            _ => false,
+        })
+        .unwrap();
+
+    let source_map = db.source_map(foo);
+    assert_eq!(
+        source_map.expressions.len(),
+        fn_body.tables.expressions.len()
+    );
+    let location = source_map.expressions[&multiply];
+    assert_eq!((location.line, location.column), (2, 2));
+}
+
+#[test]
+fn cfg_gated_entity() {
+    let (file_name, mut db) = lark_parser_db(unindent::unindent(
+        "
+        def foo() {
+        }
+
+        @cfg(test)
+        def bar() {
+          baz
+        }
+        ",
+    ));
+
+    let file_entity = EntityData::InputFile { file: file_name }.intern(&db);
+    let names = |db: &LarkDatabase| -> Vec<String> {
+        db.descendant_entities(file_entity)
+            .iter()
+            .map(|entity| entity.untern(db).relative_name(db))
+            .collect()
+    };
+
+    // `test` is inactive by default, so `bar` (and its unknown
+    // identifier) are ignored entirely.
+    assert_eq!(names(&db), vec!["InputFile(path1)", "ItemName(foo)"]);
+
+    let test = "test".intern(&db);
+    db.set_active_cfg_names(std::iter::once(test).collect());
+    assert_eq!(
+        names(&db),
+        vec!["InputFile(path1)", "ItemName(foo)", "ItemName(bar)"]
+    );
+}
+
+#[test]
+fn cfg_gated_entity_errors() {
+    let (file_name, mut db) = lark_parser_db(unindent::unindent(
+        "
+        @cfg(foo)
+        dfe bar() {
+        }
+
+        @cfg(foo)
+        def baz(: uint) {
+        }
+
+        def ok() {
+        }
+        ",
+    ));
+
+    // `foo` is inactive, so neither the typo in `bar` nor the broken
+    // signature of `baz` is reported, and `ok` still parses.
+    assert!(db.parsed_file(file_name).errors.is_empty());
+    let file_entity = EntityData::InputFile { file: file_name }.intern(&db);
+    let names: Vec<String> = db
+        .child_entities(file_entity)
+        .iter()
+        .map(|entity| entity.untern(&db).relative_name(&db))
+        .collect();
+    assert_eq!(names, vec!["ItemName(ok)"]);
+
+    let foo = "foo".intern(&db);
+    db.set_active_cfg_names(std::iter::once(foo).collect());
+    assert!(!db.parsed_file(file_name).errors.is_empty());
+}
+
+#[test]
+fn keyword_typo_recovery() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        dfe f() {
+        }
+        ",
+    ));
+
+    let parsed_file = db.parsed_file(file_name);
+    assert_eq!(parsed_file.errors.len(), 1);
+    assert_eq!(
+        parsed_file.errors[0].message(),
+        "no macro with this name; did you mean `def`?"
+    );
+
+    let f = select_entity(&db, file_name, 0);
+    assert_eq!(f.untern(&db).relative_name(&db), "ItemName(f)");
+}
+
+#[test]
+fn generic_def() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def identity[T](x: T) -> T {
+          x
+        }
+        ",
+    ));
+
+    let identity = select_entity(&db, file_name, 0);
+    let generic_declarations = db
+        .generic_declarations(identity)
+        .assert_no_errors()
+        .unwrap();
+    let names: Vec<String> = generic_declarations
+        .declarations
+        .iter()
+        .map(|declaration| declaration.clone().assert_ty().name.untern(&db).to_string())
+        .collect();
+    assert_eq!(names, vec!["T".to_string()]);
+
+    // Both `x: T` and the return type resolve to the type parameter.
+    let signature = db.signature(identity).assert_no_errors().unwrap();
+    assert_eq!(signature.inputs[0], signature.output);
+}
+
+#[test]
+fn generic_def_where_clause() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f[T](x: T) where T: Display {}
+        ",
+    ));
+
+    let f = select_entity(&db, file_name, 0);
+    let generic_declarations = db.generic_declarations(f).assert_no_errors().unwrap();
+    assert_eq!(generic_declarations.constraints.len(), 1);
+    let constraint = &generic_declarations.constraints[0];
+    assert_eq!(constraint.parameter, lark_ty::BoundVar::new(0));
+    assert_eq!(constraint.bound.untern(&db).to_string(), "Display");
+}
+
+#[test]
+fn where_clause_unknown_type_parameter() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f[T](x: T) where U: Display {
+          x
+        }
+        ",
+    ));
+
+    let f = select_entity(&db, file_name, 0);
+    let generic_declarations = db.generic_declarations(f);
+    assert_eq!(generic_declarations.errors.len(), 1);
+    assert_eq!(
+        generic_declarations.errors[0].label,
+        "unknown type parameter `U`"
+    );
+    assert!(generic_declarations.value.unwrap().constraints.is_empty());
+
+    // The rest of the definition still parses.
+    db.fn_body(f).assert_no_errors();
+}
+
+#[test]
+fn duplicate_type_parameter() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Pair[T, T] {
+          a: T
+        }
+        ",
+    ));
+
+    let pair = select_entity(&db, file_name, 0);
+    let generic_declarations = db.generic_declarations(pair);
+    assert_eq!(generic_declarations.errors.len(), 1);
+    assert_eq!(
+        generic_declarations.errors[0].label,
+        "duplicate type parameter `T`"
+    );
+}
+
+#[test]
+fn entity_keyword_span() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Foo {
+          x: uint
+        }
+        def bar() {
+        }
+        ",
+    ));
+
+    let foo = select_entity(&db, file_name, 0);
+    let bar = select_entity(&db, file_name, 1);
+    assert_eq!(
+        db.entity_keyword_span(foo),
+        Some(Span::new(file_name, 0, 6))
+    );
+    assert_eq!(
+        db.entity_keyword_span(bar),
+        Some(Span::new(file_name, 25, 28))
+    );
+
+    let x = db.child_entities(foo)[0];
+    assert_eq!(db.entity_keyword_span(x), None);
+}
+
+#[test]
+fn entity_docs() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        /// Adds one.
+        ///
+        ///   Indented.
+        def foo() {
+        }
+
+        /// Not attached.
+
+        def bar() {
+        }
+        // Not a doc comment.
+        def baz() {
+        }
+        ",
+    ));
+
+    let foo = select_entity(&db, file_name, 0);
+    let bar = select_entity(&db, file_name, 1);
+    let baz = select_entity(&db, file_name, 2);
+    assert_eq!(
+        db.entity_docs(foo),
+        Some("Adds one.\n\n  Indented.".to_string())
+    );
+    assert_eq!(db.entity_docs(bar), None);
+    assert_eq!(db.entity_docs(baz), None);
+}
+
+#[test]
+fn referenced_entities() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f() {
+        }
+        def h() {
+        }
+        def g() {
+          f()
+          h()
+          g()
+        }
+        ",
+    ));
+
+    let f = select_entity(&db, file_name, 0);
+    let h = select_entity(&db, file_name, 1);
+    let g = select_entity(&db, file_name, 2);
+    let referenced = db.referenced_entities(g);
+    assert!(referenced.contains(&f));
+    assert!(referenced.contains(&h));
+    assert!(referenced.contains(&g));
+    assert_eq!(referenced.len(), 3);
+}
+
+#[test]
+fn transitive_dependencies() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def a() {
+          b()
+        }
+        def b() {
+          c()
+        }
+        def c() {
+          a()
+        }
+        def d() {
+        }
+        ",
+    ));
+
+    let a = select_entity(&db, file_name, 0);
+    let b = select_entity(&db, file_name, 1);
+    let c = select_entity(&db, file_name, 2);
+    let d = select_entity(&db, file_name, 3);
+    assert_eq!(&db.transitive_dependencies(a)[..], &[b, c, a]);
+    assert!(db.transitive_dependencies(d).is_empty());
+}
+
+#[test]
+fn append_fn_body_tables() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def foo() -> uint {
+          1 + 2
+        }
+        def baz(a: uint, b: uint) {
+        }
+        def bar(x: uint) {
+          baz(x, x)
+        }
+        ",
+    ));
+
+    let foo = db.fn_body(select_entity(&db, file_name, 0)).into_value();
+    let bar = db.fn_body(select_entity(&db, file_name, 2)).into_value();
+
+    let mut merged = foo.tables.clone();
+    let offsets = merged.append(&bar.tables);
+
+    // The original nodes are untouched.
+    assert_eq!(merged[foo.root_expression], foo.tables[foo.root_expression]);
+
+    // The appended nodes are found through the remapped indices.
+    let root = offsets.remap(bar.root_expression);
+    assert_ne!(root, bar.root_expression);
+    assert_eq!(merged.span(root), bar.span(bar.root_expression));
+
+    let x = "x".intern(&db);
+    match merged[root] {
+        hir::ExpressionData::Call { arguments, .. } => {
+            assert_eq!(arguments.len(), 2);
+            for argument in arguments.iter(&merged) {
+                match merged[argument] {
+                    hir::ExpressionData::Place { place } => match merged[place] {
+                        hir::PlaceData::Variable(variable) => {
+                            assert_eq!(merged[merged[variable].name].text, x);
+                        }
+                        data => panic!("unexpected place {:?}", data),
+                    },
+                    data => panic!("unexpected argument {:?}", data),
+                }
+            }
+        }
+        data => panic!("unexpected root expression {:?}", data),
+    }
+}
+
+#[test]
+fn local_bindings() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f(a: uint) {
+          let b = 1
+        }
+        ",
+    ));
+
+    let f = select_entity(&db, file_name, 0);
+    assert_eq!(
+        &db.local_bindings(f)[..],
+        &[
+            ("a".intern(&db), Span::new(file_name, 6, 7)),
+            ("b".intern(&db), Span::new(file_name, 23, 24)),
+        ]
+    );
+}
+
+#[test]
+fn expression_nesting_too_deep() {
+    let (file_name, mut db) = lark_parser_db(format!(
+        "def foo() {{\n  {}1{}\n}}\n",
+        "(".repeat(20),
+        ")".repeat(20),
+    ));
+    db.set_expression_depth_limit(10);
+
+    let fn_body = db.fn_body(select_entity(&db, file_name, 0));
+    assert_eq!(fn_body.errors[0].label, "nesting too deep");
+}
+
+#[test]
+fn deprecated_entity() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        @deprecated(\"use bar instead\")
+        def foo() {
+        }
+
+        def bar() {
+          foo()
+        }
+        ",
+    ));
+
+    let foo = select_entity(&db, file_name, 0);
+    let bar = select_entity(&db, file_name, 1);
+    assert_eq!(
+        db.entity_deprecation(foo).as_ref().map(|m| &m[..]),
+        Some("use bar instead")
+    );
+    assert_eq!(db.entity_deprecation(bar), None);
+
+    // Only uses of a deprecated entity are reported, not its definition.
+    db.fn_body(foo).assert_no_errors();
+    let fn_body = db.fn_body(bar);
+    assert_eq!(fn_body.errors.len(), 1);
+    assert_eq!(
+        fn_body.errors[0].label,
+        "`foo` is deprecated: use bar instead"
+    );
+    assert!(!fn_body.errors[0].is_error());
+    assert_eq!(fn_body.errors[0].span, Span::new(file_name, 60, 63));
+}
+
+#[test]
+fn unknown_attributes_are_preserved() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        @custom(1)
+        def f() {}
+        ",
+    ));
+
+    assert!(db.parsed_file(file_name).errors.is_empty());
+    let f = select_entity(&db, file_name, 0);
+    let annotations = db.entity_annotations(f);
+    assert_eq!(annotations.len(), 1);
+    assert_eq!(annotations[0].name.untern(&db).to_string(), "custom");
+    assert_eq!(annotations[0].arguments.as_ref().map(|a| &a[..]), Some("1"));
+    assert_eq!(annotations[0].span, Span::new(file_name, 0, 10));
+    assert_eq!(db.entity_deprecation(f), None);
+}
+
+#[test]
+fn malformed_attribute() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        @custom(1
+        def f() {}
+        ",
+    ));
+
+    assert!(!db.parsed_file(file_name).errors.is_empty());
+}
+
+#[test]
+fn generic_type_arguments() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct List[T] {
+          head: T
+        }
+
+        struct Foo {
+          items: List[int]
+        }
+        ",
+    ));
+
+    let list = select_entity(&db, file_name, 0);
+    let foo = select_entity(&db, file_name, 1);
+    let items = db.child_entities(foo)[0];
+
+    let base_data = match db.ty(items).assert_no_errors().base.untern(&db) {
+        BoundVarOr::Known(base_data) => base_data,
+        BoundVarOr::BoundVar(_) => panic!("expected a named type"),
+    };
+    assert_eq!(base_data.kind, BaseKind::Named(list));
+    assert_eq!(base_data.generics.len(), 1);
+
+    let argument = base_data.generics.elements()[0].assert_ty();
+    match argument.base.untern(&db) {
+        BoundVarOr::Known(argument) => assert_eq!(
+            argument.kind,
+            BaseKind::Named(EntityData::LangItem(LangItem::Int).intern(&db))
+        ),
+        BoundVarOr::BoundVar(_) => panic!("expected a named type"),
+    }
+}
+
+#[test]
+fn malformed_type_arguments() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct List[T] {
+          head: T
+        }
+
+        struct Foo {
+          items: List[int uint]
+        }
+        ",
+    ));
+
+    // The type arguments are reported, but `Foo` and its field are
+    // still parsed.
+    let errors = db.parsed_file(file_name).errors;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].label, "expected `]`");
+
+    let foo = select_entity(&db, file_name, 1);
+    assert_eq!(db.child_entities(foo).len(), 1);
+}
+
+#[test]
+fn qualified_name() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Point {
+          x: uint,
+          y: uint,
+        }
+        ",
+    ));
+
+    let point = select_entity(&db, file_name, 0);
+    let x = db.child_entities(point)[0];
+    assert_eq!(db.qualified_name(x, false), "Point::x");
+    assert_eq!(db.qualified_name(x, true), "path1::Point::x");
+    assert_eq!(db.qualified_name(point, false), "Point");
+
+    let uint = EntityData::LangItem(LangItem::Uint).intern(&db);
+    assert_eq!(db.qualified_name(uint, false), "uint");
+}
+
+#[test]
+fn parameter_type() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f(a: int, b: String) {
+        }
+        ",
+    ));
+
+    let f = select_entity(&db, file_name, 0);
+    let b = db.parameter_type(f, 1).unwrap();
+    match b.base.untern(&db) {
+        BoundVarOr::Known(base_data) => assert_eq!(
+            base_data.kind,
+            BaseKind::Named(EntityData::LangItem(LangItem::String).intern(&db))
+        ),
+        BoundVarOr::BoundVar(_) => panic!("expected a named type"),
+    }
+    assert!(db.parameter_type(f, 2).is_none());
+}
+
+#[test]
+fn entity_full_spans() {
+    let text = unindent::unindent(
+        "
+        def foo() {
+        }
+
+        /// Docs for bar.
+        def bar() {
+        }
+        ",
+    );
+    let doc_comment = text.find("///").unwrap();
+    let (file_name, db) = lark_parser_db(&text);
+
+    let spans = db.entity_full_spans(file_name);
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0].0, select_entity(&db, file_name, 0));
+    assert_eq!(spans[1].0, select_entity(&db, file_name, 1));
+
+    // The spans cover the whole file, without overlapping, and the
+    // doc comment goes with `bar`.
+    assert_eq!(spans[0].1.start().to_usize(), 0);
+    assert_eq!(spans[0].1.end(), spans[1].1.start());
+    assert_eq!(spans[1].1.end().to_usize(), text.len());
+    assert!(spans[1].1.start().to_usize() <= doc_comment);
+}
+
+#[test]
+fn child_entities_spanned() {
+    let text = unindent::unindent(
+        "
+        struct Foo {
+          x: uint,
+          y: bool,
+          z: uint,
+        }
+        ",
+    );
+    let (file_name, db) = lark_parser_db(&text);
+    let foo = select_entity(&db, file_name, 0);
+
+    let children = db.child_entities_spanned(foo);
+    let names: Vec<_> = children
+        .iter()
+        .map(|&(entity, span)| {
+            assert_eq!(db.entity_span(entity), span);
+            let name = match entity.untern(&db) {
+                EntityData::MemberName { id, .. } => id.untern(&db),
+                data => panic!("unexpected child {:?}", data),
+            };
+            (
+                name.to_string(),
+                &text[span.start().to_usize()..span.end().to_usize()],
+            )
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            ("x".to_string(), "x: uint"),
+            ("y".to_string(), "y: bool"),
+            ("z".to_string(), "z: uint"),
+        ]
+    );
+}
+
+#[test]
+fn is_descendant_of() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Point {
+          x: uint,
+        }
+
+        struct Other {
+        }
+        ",
+    ));
+
+    let file_entity = EntityData::InputFile { file: file_name }.intern(&db);
+    let point = select_entity(&db, file_name, 0);
+    let other = select_entity(&db, file_name, 1);
+    let x = db.child_entities(point)[0];
+
+    assert!(db.is_descendant_of(x, point));
+    assert!(db.is_descendant_of(x, file_entity));
+    assert!(!db.is_descendant_of(x, other));
+    assert!(!db.is_descendant_of(point, x));
+    assert!(!db.is_descendant_of(point, point));
+}
+
+#[test]
+fn unknown_field_type() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Point {
+          x: Nope,
+          y: uint,
+        }
+        ",
+    ));
+
+    let point = select_entity(&db, file_name, 0);
+    let members = db.members(point).unwrap();
+    assert_eq!(members.len(), 2);
+    let x = members[0].entity;
+
+    // The field is still there, but with the error type.
+    let ty = db.ty(x);
+    assert_eq!(ty.errors.len(), 1);
+    assert_eq!(ty.errors[0].label, "unknown type: `Nope`");
+    assert_eq!(ty.errors[0].span, Span::new(file_name, 20, 24));
+    match ty.value.base.untern(&db) {
+        BoundVarOr::Known(base_data) => assert_eq!(base_data.kind, BaseKind::Error),
+        BoundVarOr::BoundVar(_) => panic!("expected the error type"),
+    }
+
+    db.ty(members[1].entity).assert_no_errors();
+}
+
+#[test]
+fn operator_symbols() {
+    let symbols: Vec<_> = [
+        hir::BinaryOperator::Add,
+        hir::BinaryOperator::Subtract,
+        hir::BinaryOperator::Multiply,
+        hir::BinaryOperator::Divide,
+        hir::BinaryOperator::Equals,
+        hir::BinaryOperator::NotEquals,
+    ]
+    .iter()
+    .map(|&op| hir::operator_symbol(op))
+    .collect();
+    assert_eq!(symbols, vec!["+", "-", "*", "/", "==", "!="]);
+}
+
+#[test]
+fn chained_comparison() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def foo(a: uint, b: uint, c: uint) -> bool {
+          a == b == c
+        }
+        ",
+    ));
+
+    let fn_body = db.fn_body(select_entity(&db, file_name, 0));
+    assert_eq!(fn_body.errors.len(), 1);
+    assert_eq!(
+        fn_body.errors[0].label,
+        "chained comparison is not supported; compare `a == b` and `b == c` separately"
+    );
+    assert_eq!(fn_body.errors[0].span, Span::new(file_name, 47, 58));
+}
+
+#[test]
+fn initializer_of() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f(a: uint) -> uint {
+          let x = 1 + 2
+          x + a
+        }
+        ",
+    ));
+
+    let f = select_entity(&db, file_name, 0);
+    let fn_body = db.fn_body(f).assert_no_errors();
+    let variable = |name: &str| {
+        fn_body
+            .tables
+            .variables
+            .indices()
+            .find(|&v| fn_body[fn_body[v].name].text.untern(&db).to_string() == name)
+            .unwrap()
+    };
+
+    let initializer = db.initializer_of(f, variable("x")).unwrap();
+    match fn_body[initializer] {
+        hir::ExpressionData::Binary {
+            operator: hir::BinaryOperator::Add,
+            ..
+        } => {}
+        ref data => panic!("expected `1 + 2`, found {:?}", data),
+    }
+
+    assert_eq!(db.initializer_of(f, variable("a")), None);
+}
+
+#[test]
+fn missing_comma_between_arguments() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f(a: uint, b: uint) {}
+        def g() { f(1 2) }
+        ",
+    ));
+
+    let fn_body = db.fn_body(select_entity(&db, file_name, 1));
+    assert_eq!(fn_body.errors.len(), 1);
+    assert_eq!(fn_body.errors[0].label, "expected `,` between arguments");
+    assert_eq!(fn_body.errors[0].span, Span::new(file_name, 40, 41));
+
+    let arguments = fn_body
+        .tables
+        .expressions
+        .iter()
+        .filter_map(|data| match data {
+            hir::ExpressionData::Call { arguments, .. } => Some(arguments.len()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(arguments, vec![2]);
+}
+
+#[test]
+fn entity_token_range() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def foo() {
+        }
+        def bar() {
+        }
+        ",
+    ));
+
+    let foo = select_entity(&db, file_name, 0);
+    let bar = select_entity(&db, file_name, 1);
+    let text = db.file_text(file_name);
+    let tokens = db.file_tokens(file_name).into_value();
+
+    let (start, end) = db.entity_token_range(foo).unwrap();
+    let span = tokens[start]
+        .span
+        .extended_until_end_of(tokens[end - 1].span);
+    assert_eq!(&text[span], "def foo() {\n}");
+
+    let (bar_start, _) = db.entity_token_range(bar).unwrap();
+    assert!(bar_start >= end);
+    assert_eq!(&text[tokens[bar_start].span], "def");
+}
+
+#[test]
+fn unused_entities() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Point {
+          x: uint
+        }
+        def main() {
+          used()
+        }
+        def used() -> Point {
+          Point(x: 1)
+        }
+        def dead() {
+          used()
+        }
+        ",
+    ));
+
+    let main = select_entity(&db, file_name, 1);
+    let dead = select_entity(&db, file_name, 3);
+    let unused = db.unused_entities(file_name, vec![main].into());
+    assert_eq!(&unused[..], &[dead]);
+}
+
+#[test]
+fn argument_span() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f(x: uint, y: uint) {}
+        def g(a: uint, b: uint, c: uint) {
+          f(a, b + c)
+        }
+        ",
+    ));
+
+    let g = select_entity(&db, file_name, 1);
+    let fn_body = db.fn_body(g).assert_no_errors();
+    let call = fn_body
+        .tables
+        .expressions
+        .indices()
+        .find(|&expression| match fn_body[expression] {
+            hir::ExpressionData::Call { .. } => true,
+            _ => false,
+        })
+        .unwrap();
+
+    let span = db.argument_span(g, call, 1).unwrap();
+    assert_eq!(&db.file_text(file_name)[span], "b + c");
+    assert_eq!(db.argument_span(g, call, 2), None);
+}
+
+#[test]
+fn entity_indent() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Point {
+            x: uint
+        }
+        ",
+    ));
+
+    let point = select_entity(&db, file_name, 0);
+    let x = db.child_entities(point)[0];
+    assert_eq!(db.entity_indent(point), 0);
+    assert_eq!(db.entity_indent(x), 4);
+}
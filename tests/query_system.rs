@@ -0,0 +1,196 @@
+use languageserver_types::{Position, Range};
+use lark_actor::{Actor, LspResponse, Priority, QueryRequest};
+use lark_entity::{EntityData, LangItem};
+use lark_intern::Intern;
+use lark_parser::ParserDatabase;
+use lark_query_system::{InFlightLimit, QuerySystem};
+use lark_span::IntoFileName;
+use std::collections::VecDeque;
+use std::sync::mpsc::channel;
+use url::Url;
+
+#[test]
+fn reject_queries_when_busy() {
+    let (send_channel, responses) = channel();
+    let mut query_system = QuerySystem::with_max_in_flight(send_channel, 0);
+
+    let url = Url::parse("file:///test.lark").unwrap();
+    let mut messages = VecDeque::new();
+    messages.push_back(QueryRequest::OpenFile(
+        url.clone(),
+        "def main() {}".to_string(),
+    ));
+    messages.push_back(QueryRequest::Hover(1, url.clone(), Position::new(0, 4)));
+    messages.push_back(QueryRequest::TypeAtPosition(
+        2,
+        url.clone(),
+        Position::new(0, 4),
+    ));
+    while !messages.is_empty() {
+        query_system.receive_messages(&mut messages);
+    }
+
+    // With no room for queries, both are rejected...
+    match responses.recv().unwrap() {
+        LspResponse::Error(1, message) => assert_eq!(message, "server busy"),
+        _ => panic!("expected hover to be rejected"),
+    }
+    match responses.recv().unwrap() {
+        LspResponse::Error(2, message) => assert_eq!(message, "server busy"),
+        _ => panic!("expected type query to be rejected"),
+    }
+
+    // ...but the file is still checked for errors.
+    match responses.recv().unwrap() {
+        LspResponse::Diagnostics(diagnostics_url, diagnostics) => {
+            assert_eq!(diagnostics_url, url);
+            assert!(diagnostics.is_empty());
+        }
+        _ => panic!("expected diagnostics"),
+    }
+}
+
+#[test]
+fn reject_low_priority_queries_first() {
+    let (send_channel, responses) = channel();
+    let mut query_system = QuerySystem::with_max_in_flight(send_channel, 1);
+
+    let url = Url::parse("file:///test.lark").unwrap();
+    let mut messages = VecDeque::new();
+    messages.push_back(QueryRequest::OpenFile(
+        url.clone(),
+        "def main() {}".to_string(),
+    ));
+    messages.push_back(QueryRequest::Hover(1, url.clone(), Position::new(0, 4)));
+    messages.push_back(QueryRequest::DefinitionAtPosition(
+        2,
+        url.clone(),
+        Position::new(0, 4),
+    ));
+    while !messages.is_empty() {
+        query_system.receive_messages(&mut messages);
+    }
+
+    // The one slot is not for low-priority queries like hover...
+    match responses.recv().unwrap() {
+        LspResponse::Error(1, message) => assert_eq!(message, "server busy"),
+        _ => panic!("expected hover to be rejected"),
+    }
+
+    // ...but going to a definition is answered, and the file is
+    // checked for errors (in either order).
+    let mut answered = false;
+    let mut checked = false;
+    for _ in 0..2 {
+        match responses.recv().unwrap() {
+            LspResponse::Error(..) => panic!("expected definition to be answered"),
+            LspResponse::Diagnostics(..) => checked = true,
+            response => answered = response.task_id() == Some(2),
+        }
+    }
+    assert!(answered && checked);
+}
+
+#[test]
+fn selection_ranges_for_each_position() {
+    let (send_channel, responses) = channel();
+    let mut query_system = QuerySystem::new(send_channel);
+
+    let url = Url::parse("file:///test.lark").unwrap();
+    let mut messages = VecDeque::new();
+    messages.push_back(QueryRequest::OpenFile(
+        url.clone(),
+        "def main() {}\n\ndef other() {}\n".to_string(),
+    ));
+    messages.push_back(QueryRequest::SelectionRange(
+        1,
+        url.clone(),
+        vec![
+            Position::new(0, 4),
+            Position::new(2, 4),
+            Position::new(1, 0),
+        ],
+    ));
+    while !messages.is_empty() {
+        query_system.receive_messages(&mut messages);
+    }
+
+    for _ in 0..2 {
+        match responses.recv().unwrap() {
+            LspResponse::SelectionRanges(1, ranges) => {
+                assert_eq!(ranges.len(), 3);
+
+                // Each fn is selected from its own name...
+                assert_eq!(ranges[0].last().unwrap().start, Position::new(0, 0));
+                assert_eq!(ranges[1].last().unwrap().start, Position::new(2, 0));
+
+                // ...and the blank line between them gets an empty range.
+                let empty = Range::new(Position::new(1, 0), Position::new(1, 0));
+                assert_eq!(ranges[2], vec![empty]);
+            }
+            LspResponse::Diagnostics(..) => {}
+            _ => panic!("expected selection ranges"),
+        }
+    }
+}
+
+#[test]
+fn in_flight_limit() {
+    let limit = InFlightLimit::new(4);
+    let first = limit.start();
+    let second = limit.start();
+
+    // Low-priority queries may only fill half of the slots.
+    assert!(limit.is_full(Priority::Low));
+    assert!(!limit.is_full(Priority::High));
+
+    let third = limit.start();
+    let fourth = limit.start();
+    assert!(limit.is_full(Priority::High));
+
+    // Slots are freed as soon as queries finish.
+    drop(fourth);
+    assert!(!limit.is_full(Priority::High));
+    drop(third);
+    drop(second);
+    assert!(!limit.is_full(Priority::Low));
+    drop(first);
+}
+
+#[test]
+fn analyze_files() {
+    let files = vec![
+        (
+            "a.lark".to_string(),
+            "def a() {\n  debug(1)\n}\n".to_string(),
+        ),
+        (
+            "b.lark".to_string(),
+            "def b() {\n  debug(2)\n}\n".to_string(),
+        ),
+        (
+            "c.lark".to_string(),
+            "def c(x: bool) -> uint {\n  x\n}\n".to_string(),
+        ),
+    ];
+
+    let analysis = lark_query_system::analyze_files(&files);
+    assert_eq!(analysis.parse_diagnostics, 0);
+    assert_eq!(analysis.lower_diagnostics, 0);
+    assert_eq!(analysis.type_check_diagnostics, 1);
+    assert_eq!(analysis.total_diagnostics(), 1);
+
+    // The fns in `a.lark` and `b.lark` refer to the very same `debug`
+    // entity, since all the files share one set of interners.
+    let db = &analysis.db;
+    let first_fn_in = |file: &str| {
+        let file_entity = EntityData::InputFile {
+            file: file.into_file_name(db),
+        }
+        .intern(db);
+        db.child_entities(file_entity)[0]
+    };
+    let debug = EntityData::LangItem(LangItem::Debug).intern(db);
+    assert_eq!(&db.referenced_entities(first_fn_in("a.lark"))[..], &[debug]);
+    assert_eq!(&db.referenced_entities(first_fn_in("b.lark"))[..], &[debug]);
+}
@@ -0,0 +1,394 @@
+use lark_entity::{Entity, EntityData, LangItem, MemberKind};
+use lark_hir as hir;
+use lark_intern::{Intern, Untern};
+use lark_parser::ParserDatabase;
+use lark_parser::ParserDatabaseExt;
+use lark_query_system::LarkDatabase;
+use lark_span::{ByteIndex, Span};
+use lark_test::*;
+use lark_ty::{BaseData, BaseKind};
+use lark_type_check::{Resolution, TypeCheckDatabase};
+use salsa::Database;
+
+#[test]
+fn ops_limit_exceeded() {
+    // Each `+` on a value of unknown type enqueues an operation that
+    // is blocked until the type of `x` is known (which it never is).
+    let (file_name, mut db) = lark_parser_db(unindent::unindent(
+        "
+        def foo() {
+          let x
+          let y = x + x + x + x + x + x + x + x
+        }
+        ",
+    ));
+    db.query_mut(lark_type_check::TypeCheckOpsLimitQuery)
+        .set((), 4);
+
+    let foo = db.top_level_entities_in_file(file_name)[0];
+    let labels: Vec<String> = db
+        .base_type_check(foo)
+        .errors
+        .into_iter()
+        .map(|error| error.label)
+        .collect();
+    assert_eq!(labels, vec!["type inference too complex".to_string()]);
+}
+
+#[test]
+fn operator_overloaded_by_method() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Point {
+          x: uint,
+          add(other: Point) -> Point {
+            Point(x: self.x + other.x)
+          }
+        }
+
+        def sum(p: Point, q: Point) -> Point {
+          p + q
+        }
+        ",
+    ));
+
+    let entities = db.top_level_entities_in_file(file_name);
+    let (point, sum) = (entities[0], entities[1]);
+    let add = db
+        .member_entity(point, MemberKind::Method, "add".intern(&db))
+        .unwrap();
+
+    let fn_body = db.fn_body(sum).into_value();
+    let (binary, _) = fn_body
+        .tables
+        .expressions
+        .iter_enumerated()
+        .find(|(_, data)| match data {
+            hir::ExpressionData::Binary { .. } => true,
+            _ => false,
+        })
+        .unwrap();
+
+    let results = db.base_type_check(sum).assert_no_errors();
+    assert_eq!(results.entities[&hir::MetaIndex::from(binary)], add);
+    match add.untern(&db) {
+        EntityData::MemberName { base, .. } => assert_eq!(base, point),
+        data => panic!("unexpected entity {:?}", data),
+    }
+}
+
+#[test]
+fn operator_without_method() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        struct Point {
+          x: uint
+        }
+
+        def sum(p: Point, q: Point) -> Point {
+          p + q
+        }
+        ",
+    ));
+
+    let sum = db.top_level_entities_in_file(file_name)[1];
+    let labels: Vec<String> = db
+        .base_type_check(sum)
+        .errors
+        .into_iter()
+        .map(|error| error.label)
+        .collect();
+    assert_eq!(labels, vec!["no `+` operator for type Point".to_string()]);
+}
+
+#[test]
+fn return_type_mismatch() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f() -> int {
+          \"x\"
+        }
+        ",
+    ));
+
+    let f = db.top_level_entities_in_file(file_name)[0];
+    let errors = db.base_type_check(f).errors;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].label,
+        "mismatched return type (expected int, found String)"
+    );
+    assert_eq!(errors[0].span, Span::new(file_name, 19, 22));
+}
+
+#[test]
+fn unused_return_value() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        @must_use
+        def one() -> uint {
+          1
+        }
+
+        def two() -> uint {
+          2
+        }
+
+        def main() {
+          one()
+          two()
+          debug(true)
+        }
+        ",
+    ));
+
+    // Only the `@must_use` fn is warned about, and only as a warning.
+    let main = db.top_level_entities_in_file(file_name)[2];
+    let errors = db.base_type_check(main).errors;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].label, "unused return value");
+    assert_eq!(errors[0].span, Span::new(file_name, 79, 84));
+    assert!(!errors[0].is_error());
+    assert!(db.type_check_file_passes(file_name));
+}
+
+#[test]
+fn variable_type() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def main() {
+          let x = 1
+        }
+        ",
+    ));
+
+    let main = db.top_level_entities_in_file(file_name)[0];
+    let fn_body = db.fn_body(main).into_value();
+    let (x, _) = fn_body.tables.variables.iter_enumerated().next().unwrap();
+
+    let ty = db.variable_type(main, x).assert_no_errors();
+    match ty.base.untern(&db) {
+        BaseData {
+            kind: BaseKind::Named(entity),
+            ..
+        } => assert_eq!(entity, EntityData::LangItem(LangItem::Uint).intern(&db)),
+        data => panic!("unexpected type {:?}", data),
+    }
+}
+
+#[test]
+fn blocked_vars() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def main() {
+          let x
+          x.foo()
+          x.bar()
+        }
+        ",
+    ));
+
+    // Both method calls wait on the type of `x`, which is never
+    // inferred.
+    let main = db.top_level_entities_in_file(file_name)[0];
+    let blocked = lark_type_check::blocked_vars(&db, main);
+    assert_eq!(blocked.len(), 1);
+    assert_eq!(blocked[0].1, 2);
+}
+
+#[test]
+fn unresolved_variable_origin() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def main() {
+          let y = 1
+          let x
+        }
+        ",
+    ));
+
+    let main = db.top_level_entities_in_file(file_name)[0];
+    let errors = db.base_type_check(main).errors;
+    assert!(!errors.is_empty());
+    for error in errors {
+        assert_eq!(
+            error.label,
+            "Unresolved variable: cannot infer the type of the variable `x`"
+        );
+        assert_eq!(error.span, Span::new(file_name, 31, 32));
+    }
+}
+
+#[test]
+fn call_sites() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def f() { g(h()) }
+        def g(x: uint) { }
+        def h() -> uint { 1 }
+        ",
+    ));
+
+    let entities = db.top_level_entities_in_file(file_name);
+    let (f, g, h) = (entities[0], entities[1], entities[2]);
+    assert_eq!(
+        &db.call_sites(f)[..],
+        &[
+            (Some(g), Span::new(file_name, 10, 16)),
+            (Some(h), Span::new(file_name, 12, 15)),
+        ]
+    );
+}
+
+#[test]
+fn type_check_file() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        "
+        def good(x: uint) -> uint {
+          x
+        }
+
+        def bad(x: bool) -> uint {
+          x
+        }
+        ",
+    ));
+
+    let errors = db.type_check_file(file_name).errors;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].label,
+        "mismatched return type (expected uint, found bool)"
+    );
+    assert_eq!(errors[0].span, Span::new(file_name, 64, 65));
+}
+
+#[test]
+fn type_check_file_passes() {
+    let source = unindent::unindent(
+        "
+        def bad(x: bool) -> uint {
+          x
+        }
+
+        def good(x: uint) -> uint {
+          x
+        }
+        ",
+    );
+    let count_type_checks = |db: &LarkDatabase| {
+        db.take_executed_queries()
+            .iter()
+            .filter(|key| key.contains("full_type_check"))
+            .count()
+    };
+
+    let (file_name, db) = lark_parser_db(source.clone());
+    db.record_executed_queries();
+    assert_eq!(db.type_check_file(file_name).errors.len(), 1);
+    assert_eq!(count_type_checks(&db), 2);
+
+    // The fast mode gives up after `bad`, never checking `good`.
+    let (file_name, db) = lark_parser_db(source);
+    db.record_executed_queries();
+    assert!(!db.type_check_file_passes(file_name));
+    assert_eq!(count_type_checks(&db), 1);
+}
+
+#[test]
+fn resolve_at_call() {
+    let text = unindent::unindent(
+        "
+        def foo() -> uint {
+          22
+        }
+
+        def bar() -> uint {
+          foo()
+        }
+        ",
+    );
+    let call = text.find("foo()").unwrap();
+    let (file_name, db) = lark_parser_db(&text);
+    let bar = db.top_level_entities_in_file(file_name)[1];
+
+    // On the `(`, past the `foo` place but within the call.
+    match db.resolve_at(file_name, ByteIndex::from(call + 3)) {
+        Resolution::Expression {
+            entity,
+            expression,
+            ty,
+        } => {
+            assert_eq!(entity, bar);
+            let fn_body = db.fn_body(bar).into_value();
+            match fn_body[expression] {
+                hir::ExpressionData::Call { .. } => {}
+                ref data => panic!("expected a call, found {:?}", data),
+            }
+            match ty.base.untern(&db) {
+                BaseData {
+                    kind: BaseKind::Named(entity),
+                    ..
+                } => assert_eq!(entity, EntityData::LangItem(LangItem::Uint).intern(&db)),
+                data => panic!("unexpected type {:?}", data),
+            }
+        }
+        resolution => panic!("expected an expression, found {:?}", resolution),
+    }
+
+    // Between the two functions, there is only the file.
+    let between = text.find("\n\n").unwrap() + 1;
+    assert_eq!(
+        db.resolve_at(file_name, ByteIndex::from(between)),
+        Resolution::Nothing
+    );
+}
+
+#[test]
+fn literal_types() {
+    let (file_name, db) = lark_parser_db(unindent::unindent(
+        r#"
+        def main() {
+          let a = 1
+          let b = "hi"
+          let c = true
+        }
+        "#,
+    ));
+
+    let main = db.top_level_entities_in_file(file_name)[0];
+    let fn_body = db.fn_body(main).assert_no_errors();
+    let literal_items: Vec<LangItem> = fn_body
+        .tables
+        .expressions
+        .iter()
+        .filter_map(|data| match data {
+            hir::ExpressionData::Literal { data } => Some(hir::literal_lang_item(data.kind)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(literal_items, vec![LangItem::Uint, LangItem::String]);
+
+    // `true` is not a literal but a lang item of its own; either way,
+    // each variable gets the type of its initializer.
+    let types: Vec<Entity> = fn_body
+        .tables
+        .variables
+        .indices()
+        .map(|variable| {
+            let ty = db.variable_type(main, variable).assert_no_errors();
+            match ty.base.untern(&db) {
+                BaseData {
+                    kind: BaseKind::Named(entity),
+                    ..
+                } => entity,
+                data => panic!("unexpected type {:?}", data),
+            }
+        })
+        .collect();
+    let expected: Vec<Entity> = vec![LangItem::Uint, LangItem::String, LangItem::Boolean]
+        .into_iter()
+        .map(|item| EntityData::LangItem(item).intern(&db))
+        .collect();
+    assert_eq!(types, expected);
+}